@@ -0,0 +1,2190 @@
+use std::any::Any;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use image::RgbaImage;
+#[cfg(target_os = "linux")]
+use winit::platform::unix::WindowBuilderExtUnix;
+#[cfg(windows)]
+use winit::platform::windows::WindowBuilderExtWindows;
+use winit::{
+    dpi::LogicalSize,
+    dpi::PhysicalPosition,
+    event::{
+        ElementState, Event, Ime as WinitIme, MouseButton as WinitButton, MouseScrollDelta, Touch,
+        TouchPhase, WindowEvent,
+    },
+    event_loop::{ControlFlow, EventLoop},
+    window::{Icon, Window, WindowBuilder, WindowId},
+};
+
+use crate::backend::{self, Backend, PresentMode, RenderBackend, Scaling};
+use crate::bitmap::{Bitmap, ImageSource};
+use crate::clipboard::Clipboard;
+use crate::color::Color;
+use crate::cursor::Cursor;
+use crate::damage::DamageTracker;
+use crate::drag::{Drag, DragEvent};
+use crate::draw_list::{DrawList, Layer};
+use crate::error::Error;
+use crate::event::{EventFilters, FilterId, InputEvent};
+use crate::font::Fonts;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::{GamepadButton, GamepadEvent, GamepadEventKind, Gamepads};
+use crate::geometry::{Point, Rect, Size};
+use crate::gesture::{self, GestureEvent, LongPress, Touches};
+#[cfg(feature = "hotkeys")]
+use crate::hotkey::Hotkeys;
+use crate::keyboard::{
+    self, Composition, Ime, Key, KeyCode, KeyEvent, KeyRepeat, Keyboard, Modifiers, TextEvent,
+};
+#[cfg(feature = "notifications")]
+use crate::notify::{NotificationEvent, Notifications};
+use crate::path::Path;
+use crate::pointer::{MouseButton, PointerDispatcher, PointerEvent, PointerEventKind};
+use crate::popup::{PopupId, PopupOptions, PopupRequest, Popups};
+use crate::record::{Recorder, Replay};
+use crate::scroll::{ScrollEvent, ScrollOptions, Scroller};
+use crate::shortcut::{ShortcutEvent, Shortcuts};
+use crate::stroke::Stroke;
+use crate::style::{self, State, Style, Stylesheet};
+#[cfg(feature = "taskbar")]
+use crate::taskbar::{Progress, Taskbar};
+#[cfg(feature = "system-theme")]
+use crate::theme::AccentWatcher;
+use crate::theme::{self, Appearance, Theme, Tokens};
+use crate::transition::Transitions;
+#[cfg(feature = "tray")]
+use crate::tray::{Tray, TrayEvent};
+use crate::widget::WidgetId;
+use crate::window::{
+    self, EdgeResize, Edges, Fullscreen, Geometry, Monitor, WindowRequest, WindowState,
+};
+
+/// Initial window configuration
+#[derive(Debug, Clone)]
+pub struct WindowOptions {
+    pub title: String,
+    /// Name of the application's `.desktop` file without the extension, e.g.
+    /// `"org.example.Editor"`, by which Linux desktops group its windows and
+    /// show its taskbar progress
+    pub app_id: Option<String>,
+    /// Icon for the title bar and taskbar, in several sizes for a crisp one
+    /// at each place, e.g. 16, 32 and 256 pixels square
+    ///
+    /// Images are PNG files or bytes, or RGBA bitmaps. The one closest to 32
+    /// pixels goes in the title bar, and on Windows the largest in the
+    /// taskbar. macOS shows the application bundle's icon instead.
+    pub icon: Vec<ImageSource>,
+    pub width: u32,
+    pub height: u32,
+    /// Smallest size the user can resize the window to, in logical pixels
+    pub min_size: Option<(u32, u32)>,
+    /// Largest size the user can resize the window to, in logical pixels
+    pub max_size: Option<(u32, u32)>,
+    /// Let the user resize the window
+    pub resizable: bool,
+    /// Open the window filling the screen
+    pub fullscreen: Option<Fullscreen>,
+    /// Show the system's title bar and borders; without them, draw a title
+    /// bar of your own with `Context::drag_window` and the like
+    pub decorations: bool,
+    /// Keep the window above all others, e.g. for tool palettes and
+    /// monitoring overlays
+    pub always_on_top: bool,
+    /// Save the window's position, size and maximized state on exit under
+    /// this application name, in the platform's config directory, and
+    /// restore them on the next launch
+    ///
+    /// A position no longer on any monitor is left to the system.
+    pub remember_geometry: Option<String>,
+    /// Open the window centered on the monitor at this index of
+    /// `Context::monitors`, e.g. `Some(1)` for a second display; `None` or a
+    /// monitor that is not connected leaves it to the system
+    pub monitor: Option<usize>,
+    /// Width in logical pixels of the band along the edges of a window
+    /// without decorations that resizes it when dragged
+    pub resize_border: f32,
+    /// Let what is behind the window show through wherever the frame is not
+    /// opaque, for overlays, splash screens and windows of any shape
+    ///
+    /// Give the theme a background with alpha 0, usually along with
+    /// `decorations: false`.
+    /// Whether the window really blends with the desktop is up to the
+    /// platform's compositor.
+    pub transparent: bool,
+    /// Colors to draw with in light mode, including the background the frame
+    /// is cleared to and the focus ring
+    pub theme: Theme,
+    /// Colors to draw with in dark mode
+    pub dark_theme: Theme,
+    /// Spacing, radii and text sizes, shared by both themes
+    pub tokens: Tokens,
+    /// Styles for widgets by type, id and class, e.g.
+    /// `vec![Stylesheet::load("app.css")?]`, later ones winning ties
+    pub stylesheets: Vec<Stylesheet>,
+    /// Load stylesheets again whenever their files change and restyle the
+    /// window, e.g. `cfg!(debug_assertions)` to try out looks without
+    /// restarting
+    pub watch_stylesheets: bool,
+    /// Use the light or dark theme whatever the system prefers, `None` to
+    /// follow the system
+    ///
+    /// Windows is always followed; macOS and Linux need the `system-theme`
+    /// feature, and are checked again whenever the window gains focus.
+    pub appearance: Option<Appearance>,
+    /// Draw in the accent color picked in the system's settings, read in the
+    /// background on Windows, macOS and Linux desktops that share it with the
+    /// `system-theme` feature, see `Theme::with_accent`
+    pub system_accent: bool,
+    pub backend: Backend,
+    /// Whether frames wait for the display's refresh; fixed for the life of
+    /// the window
+    pub present_mode: PresentMode,
+    /// Draw at most this many frames per second, e.g. `Some(30)` to save
+    /// battery, `None` for as many as input and animations call for
+    pub max_frame_rate: Option<u32>,
+    /// How the frame is fitted into a window of another size
+    pub scaling: Scaling,
+    /// Resize the frame along with the window, so the UI is laid out for the new
+    /// size instead of being scaled
+    pub resize_buffer: bool,
+    /// Draw at this multiple of the frame's resolution and filter it down, e.g.
+    /// `2.0` for smoother text and shapes on low-DPI displays
+    pub render_scale: f32,
+    /// Longest time between presses that still makes a double or triple click
+    pub double_click_interval: Duration,
+    /// Smoothing and momentum of wheel and trackpad scrolling
+    pub scroll: ScrollOptions,
+    /// Let the first finger on a touchscreen act as the mouse, pressing,
+    /// moving and releasing the left button
+    ///
+    /// Windows already turns touches into mouse input, so it is off there by
+    /// default.
+    pub touch_as_pointer: bool,
+    /// How long a finger or the left button is held still for a
+    /// `GestureKind::LongPress`
+    pub long_press: Duration,
+    /// Pace of held arrow keys, Backspace and the like, `None` to leave it
+    /// to the OS
+    pub key_repeat: Option<KeyRepeat>,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            title: "Wixe GUI Framework".to_string(),
+            app_id: None,
+            icon: Vec::new(),
+            width: 800,
+            height: 600,
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            fullscreen: None,
+            decorations: true,
+            always_on_top: false,
+            remember_geometry: None,
+            monitor: None,
+            resize_border: 6.0,
+            transparent: false,
+            theme: Theme::light(),
+            dark_theme: Theme::dark(),
+            tokens: Tokens::default(),
+            stylesheets: Vec::new(),
+            watch_stylesheets: false,
+            appearance: None,
+            system_accent: true,
+            backend: Backend::default(),
+            present_mode: PresentMode::default(),
+            max_frame_rate: None,
+            scaling: Scaling::default(),
+            resize_buffer: true,
+            render_scale: 1.0,
+            double_click_interval: Duration::from_millis(500),
+            scroll: ScrollOptions::default(),
+            touch_as_pointer: !cfg!(windows),
+            long_press: Duration::from_millis(500),
+            key_repeat: Some(KeyRepeat::default()),
+        }
+    }
+}
+
+/// Time between checks of watched stylesheets for changes
+const STYLESHEET_POLL: Duration = Duration::from_millis(500);
+
+/// Time between frames while something animates without input
+const ANIMATION_FRAME: Duration = Duration::from_millis(16);
+
+/// Space between a focused widget and its focus ring
+const FOCUS_RING_GAP: f32 = 2.0;
+const FOCUS_RING_WIDTH: f32 = 2.0;
+
+/// Framework state available to the application
+pub struct Context {
+    damage: DamageTracker,
+    width: u32,
+    height: u32,
+    renderer: Box<dyn RenderBackend>,
+    /// Shortest time between frames, from the frame-rate cap
+    frame_interval: Option<Duration>,
+    last_frame: Option<Instant>,
+    fonts: Fonts,
+    /// The theme of the appearance in effect
+    theme: Theme,
+    light_theme: Theme,
+    dark_theme: Theme,
+    appearance: Appearance,
+    system_appearance: Appearance,
+    /// Appearance chosen by the application over the system's
+    forced_appearance: Option<Appearance>,
+    /// Accent color of the system's settings, if followed and it has one
+    system_accent: Option<Color>,
+    #[cfg(feature = "system-theme")]
+    accent_watcher: Option<AccentWatcher>,
+    tokens: Tokens,
+    transitions: Transitions,
+    stylesheets: Vec<Stylesheet>,
+    /// When the stylesheets' files were last checked for changes, if watched
+    stylesheets_checked: Option<Instant>,
+    pointer: PointerDispatcher,
+    drag: Option<Drag>,
+    /// Files dropped on the window in the current batch of events
+    dropped_files: Vec<PathBuf>,
+    keyboard: Keyboard,
+    focused: Option<WidgetId>,
+    /// Focus was last moved from the keyboard, so the ring is shown
+    focus_visible: bool,
+    /// The key being handled should not move focus
+    key_consumed: bool,
+    shortcuts: Shortcuts,
+    ime: Ime,
+    clipboard: Clipboard,
+    /// Cursor last shown by the window
+    cursor: Cursor,
+    /// Area of the custom cursor drawn into the last frame
+    cursor_drawn: Option<Rect>,
+    scroll: Scroller,
+    touches: Touches,
+    touch_as_pointer: bool,
+    long_press: LongPress,
+    #[cfg(feature = "gamepad")]
+    gamepads: Gamepads,
+    filters: EventFilters,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+    /// Changes to the window since the last batch of events
+    window_requests: Vec<WindowRequest>,
+    title: String,
+    fullscreen: Option<Fullscreen>,
+    window_state: WindowState,
+    /// The system reported the window hidden from view
+    occluded: bool,
+    always_on_top: bool,
+    visible: bool,
+    resize_border: f32,
+    /// Window edges under the pointer and its position in physical pixels
+    edge_hover: Option<(Edges, PhysicalPosition<f64>)>,
+    edge_resize: Option<EdgeResize>,
+    /// Top-left corner of the window's client area on the screen
+    window_origin: PhysicalPosition<i32>,
+    monitors: Vec<Monitor>,
+    /// Index into `monitors` of the one showing most of the window
+    monitor: Option<usize>,
+    scale_factor: f64,
+    popups: Popups,
+    /// Exit after this batch of events
+    exit: bool,
+    /// The close being asked for should not happen
+    close_cancelled: bool,
+    #[cfg(feature = "hotkeys")]
+    hotkeys: Hotkeys,
+    #[cfg(feature = "tray")]
+    tray: Tray,
+    #[cfg(feature = "notifications")]
+    notifications: Notifications,
+    #[cfg(feature = "taskbar")]
+    taskbar: Taskbar,
+}
+
+impl Context {
+    fn new(width: u32, height: u32, renderer: Box<dyn RenderBackend>) -> Self {
+        let mut damage = DamageTracker::new(width, height);
+        damage.invalidate_all();
+        Self {
+            damage,
+            width,
+            height,
+            renderer,
+            frame_interval: None,
+            last_frame: None,
+            fonts: Fonts::default(),
+            theme: Theme::default(),
+            light_theme: Theme::light(),
+            dark_theme: Theme::dark(),
+            appearance: Appearance::Light,
+            system_appearance: Appearance::Light,
+            forced_appearance: None,
+            system_accent: None,
+            #[cfg(feature = "system-theme")]
+            accent_watcher: None,
+            tokens: Tokens::default(),
+            transitions: Transitions::default(),
+            stylesheets: Vec::new(),
+            stylesheets_checked: None,
+            pointer: PointerDispatcher::default(),
+            drag: None,
+            dropped_files: Vec::new(),
+            keyboard: Keyboard::default(),
+            focused: None,
+            focus_visible: false,
+            key_consumed: false,
+            shortcuts: Shortcuts::default(),
+            clipboard: Clipboard::default(),
+            cursor: Cursor::default(),
+            cursor_drawn: None,
+            scroll: Scroller::default(),
+            touches: Touches::default(),
+            touch_as_pointer: false,
+            long_press: LongPress::default(),
+            #[cfg(feature = "gamepad")]
+            gamepads: Gamepads::new(),
+            ime: Ime::default(),
+            filters: EventFilters::default(),
+            recorder: None,
+            replay: None,
+            window_requests: Vec::new(),
+            title: String::new(),
+            fullscreen: None,
+            window_state: WindowState::Normal,
+            occluded: false,
+            always_on_top: false,
+            visible: true,
+            resize_border: 0.0,
+            edge_hover: None,
+            edge_resize: None,
+            window_origin: PhysicalPosition::default(),
+            monitors: Vec::new(),
+            monitor: None,
+            scale_factor: 1.0,
+            popups: Popups::default(),
+            exit: false,
+            close_cancelled: false,
+            #[cfg(feature = "hotkeys")]
+            hotkeys: Hotkeys::default(),
+            #[cfg(feature = "tray")]
+            tray: Tray::default(),
+            #[cfg(feature = "notifications")]
+            notifications: Notifications::default(),
+            #[cfg(feature = "taskbar")]
+            taskbar: Taskbar::default(),
+        }
+    }
+
+    /// Size of the frame buffer in pixels
+    pub fn size(&self) -> Size {
+        Size::new(self.width as f32, self.height as f32)
+    }
+
+    /// Fonts registered for the application
+    pub fn fonts(&self) -> &Fonts {
+        &self.fonts
+    }
+
+    /// Register fonts, e.g. `ctx.fonts_mut().register_file("mono", path)`
+    ///
+    /// Text already drawn keeps its old font until it is repainted.
+    pub fn fonts_mut(&mut self) -> &mut Fonts {
+        &mut self.fonts
+    }
+
+    /// Colors to draw widgets with
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Draw with `theme` from the next frame, e.g. to preview a custom theme
+    /// or apply one picked in the application's settings
+    ///
+    /// It replaces the theme of the appearance in effect, so switching to the
+    /// other appearance brings back that one's theme; pin the appearance with
+    /// `set_appearance` to keep it. Every widget is restyled and repainted.
+    pub fn set_theme(&mut self, theme: Theme) {
+        match self.appearance {
+            Appearance::Light => self.light_theme = theme.clone(),
+            Appearance::Dark => self.dark_theme = theme.clone(),
+        }
+        self.use_theme(theme);
+    }
+
+    /// Spacing, radii and text sizes to lay out and draw widgets with
+    pub fn tokens(&self) -> &Tokens {
+        &self.tokens
+    }
+
+    /// Change the spacing, radii or text sizes, restyling everything, e.g.
+    /// `ctx.tokens_mut().density = Density::Compact`
+    pub fn tokens_mut(&mut self) -> &mut Tokens {
+        self.request_repaint();
+        &mut self.tokens
+    }
+
+    /// Whether the light or the dark theme is in use
+    pub fn appearance(&self) -> Appearance {
+        self.appearance
+    }
+
+    /// Use the light or dark theme whatever the system prefers, e.g. from an
+    /// appearance setting, or with `None` follow the system again
+    pub fn set_appearance(&mut self, appearance: Option<Appearance>) {
+        self.forced_appearance = appearance;
+    }
+
+    /// How a widget of type `kind` looks, e.g.
+    /// `ctx.style("Button", id, &["primary"])` while drawing it, after
+    /// `begin_widget`
+    ///
+    /// Properties cascade in this order, each step overriding the last:
+    ///
+    /// 1. Text color and size inherited from the innermost widget around it
+    ///    whose style was taken, or the theme's text color at the top
+    /// 2. Stylesheet rules that match it, the more specific winning
+    /// 3. Its own overrides from `Context::set_style`
+    /// 4. Variants of its overrides for the states it is in, see
+    ///    `Context::widget_states`
+    ///
+    /// Rules for states, such as `Button:hover`, only match while the widget
+    /// is in them, and it is repainted when they change.
+    ///
+    /// Properties still unset are `None`, for the widget to take from the
+    /// theme. Widgets inside it inherit from the result. Properties with a
+    /// transition that changed since the last frame come back part of the
+    /// way to their new value, and the widget is repainted until they
+    /// arrive.
+    pub fn style(&mut self, kind: &str, id: WidgetId, classes: &[&str]) -> Style {
+        let states = self.widget_states(id);
+        let widgets = &self.pointer.widgets;
+        let mut style = match widgets.parent_style(id) {
+            Some(parent) => parent.inherited(),
+            None => Style::default().color(self.theme.text),
+        };
+        let sheets = &self.stylesheets;
+        style.merge(&style::cascade(
+            sheets,
+            &self.tokens,
+            kind,
+            id,
+            classes,
+            &states,
+        ));
+        if let Some(own) = widgets.style_of(id) {
+            style.merge(own);
+        }
+        style.resolve_states(&states);
+        let now = self.last_frame.unwrap_or_else(Instant::now);
+        let style = self.transitions.animate(id, style, now);
+        self.pointer.widgets.set_computed_style(id, style.clone());
+        style
+    }
+
+    /// Override properties of a widget laid out this frame whatever the
+    /// stylesheets say, e.g. `ctx.set_style(id, |s| s.background(red))`
+    ///
+    /// Calls add up, each getting the overrides so far.
+    pub fn set_style(&mut self, id: WidgetId, style: impl FnOnce(Style) -> Style) {
+        self.pointer.widgets.set_style(id, style);
+    }
+
+    /// Style widgets with `sheet` as well, winning ties with those added
+    /// before
+    pub fn add_stylesheet(&mut self, sheet: Stylesheet) {
+        self.stylesheets.push(sheet);
+        self.request_repaint();
+    }
+
+    fn theme_of(&self, appearance: Appearance) -> &Theme {
+        match appearance {
+            Appearance::Light => &self.light_theme,
+            Appearance::Dark => &self.dark_theme,
+        }
+    }
+
+    /// Clear to the theme's background and repaint everything with it, in
+    /// the system's accent color if it has one
+    fn use_theme(&mut self, theme: Theme) {
+        let theme = match self.system_accent {
+            Some(accent) => theme.with_accent(accent),
+            None => theme,
+        };
+        self.renderer.set_clear_color(theme.background);
+        self.popups.set_clear_color(theme.background);
+        self.theme = theme;
+        self.request_repaint();
+    }
+
+    /// Lay out a widget in `rect` for pointer input; widgets added before the
+    /// matching `end_widget` are its children
+    ///
+    /// Widgets are collected anew every time `Application::draw` runs, in
+    /// drawing order, so the ones drawn last are hit first.
+    pub fn begin_widget(&mut self, id: WidgetId, rect: Rect) {
+        self.pointer.widgets.begin(id, rect);
+    }
+
+    pub fn end_widget(&mut self) {
+        self.pointer.widgets.end();
+    }
+
+    /// Lay out a widget without children
+    pub fn add_widget(&mut self, id: WidgetId, rect: Rect) {
+        self.begin_widget(id, rect);
+        self.end_widget();
+    }
+
+    /// Whether the pointer is over the widget or one of its children
+    pub fn is_hovered(&self, id: WidgetId) -> bool {
+        self.pointer.is_hovered(id)
+    }
+
+    /// Whether a pointer button was pressed on the widget and is still held
+    pub fn is_pressed(&self, id: WidgetId) -> bool {
+        self.pointer.is_pressed(id)
+    }
+
+    /// Show a widget laid out this frame, and the widgets inside it, as not
+    /// usable for now: styled for `State::Disabled` and skipped by focus
+    ///
+    /// Pointer events still reach it, for the application to ignore.
+    pub fn set_disabled(&mut self, id: WidgetId) {
+        self.pointer.widgets.set_disabled(id);
+    }
+
+    pub fn is_disabled(&self, id: WidgetId) -> bool {
+        self.pointer.widgets.is_disabled(id)
+    }
+
+    /// The states a widget is styled for, from the pointer, focus and
+    /// `set_disabled`
+    pub fn widget_states(&self, id: WidgetId) -> Vec<State> {
+        if self.is_disabled(id) {
+            return vec![State::Disabled];
+        }
+        let states = [
+            (State::Hover, self.is_hovered(id)),
+            (State::Focus, self.has_focus(id)),
+            (State::Active, self.is_pressed(id)),
+        ];
+        (states.into_iter())
+            .filter_map(|(state, on)| on.then_some(state))
+            .collect()
+    }
+
+    /// Repaint a widget whose state changed if its look depends on it
+    fn invalidate_state(&mut self, id: WidgetId) {
+        let widgets = &self.pointer.widgets;
+        if let Some(rect) = widgets.rect(id).filter(|_| widgets.is_styled(id)) {
+            self.damage.add(rect.round_out());
+        }
+    }
+
+    /// Innermost widget at `p` in frame pixels, hit the way the pointer hits
+    /// it, as laid out by the last `Application::draw`
+    pub fn widget_at(&self, p: Point) -> Option<WidgetId> {
+        self.pointer.widgets.path_at(p).last().copied()
+    }
+
+    /// Rect a widget was laid out with by the last `Application::draw`, e.g. to
+    /// place a popover beside it; `None` if it was not laid out
+    pub fn widget_bounds(&self, id: WidgetId) -> Option<Rect> {
+        self.pointer.widgets.rect(id)
+    }
+
+    /// Send pointer moves and releases to `id` wherever the pointer is, until
+    /// every button is released
+    ///
+    /// Pressing a button already captures the pointer for the widget under it,
+    /// so a slider thumb keeps following the pointer off its track and out of
+    /// the window. This moves capture to another widget.
+    pub fn capture_pointer(&mut self, id: WidgetId) {
+        self.pointer.captured = Some(id);
+    }
+
+    /// Send pointer events to the widget under the pointer again
+    pub fn release_pointer_capture(&mut self) {
+        self.pointer.captured = None;
+    }
+
+    /// Widget holding pointer capture
+    pub fn pointer_capture(&self) -> Option<WidgetId> {
+        self.pointer.captured
+    }
+
+    /// Pointer position in frame pixels, `None` when it is outside the window
+    pub fn pointer_position(&self) -> Option<Point> {
+        self.pointer.position()
+    }
+
+    /// Show `cursor` while the pointer is over a widget laid out this frame,
+    /// unless a child under it sets its own
+    ///
+    /// Takes a `CursorIcon` or a `CustomCursor` image.
+    pub fn set_cursor(&mut self, id: WidgetId, cursor: impl Into<Cursor>) {
+        self.pointer.widgets.set_cursor(id, cursor.into());
+    }
+
+    /// Area the custom cursor covers at the pointer, if one is shown
+    fn custom_cursor_rect(&self) -> Option<Rect> {
+        match &self.cursor {
+            Cursor::Custom(custom) => Some(custom.rect(self.pointer.position()?)),
+            Cursor::Icon(_) => None,
+        }
+    }
+
+    fn record_cursor(&mut self, list: &mut DrawList) {
+        if let (Cursor::Custom(custom), Some(p)) = (&self.cursor, self.pointer.position()) {
+            custom.record(p, list);
+        }
+        self.cursor_drawn = self.custom_cursor_rect();
+    }
+
+    /// Send wheel and trackpad scrolling over a widget laid out this frame, or
+    /// its children, to it, unless a scrollable child is nearer the pointer
+    pub fn set_scrollable(&mut self, id: WidgetId) {
+        self.pointer.widgets.set_scrollable(id);
+    }
+
+    /// Move the window when the left button is pressed on a widget laid out
+    /// this frame, e.g. a title bar of your own or the empty space of a
+    /// toolbar, but not on its children
+    pub fn set_drag_region(&mut self, id: WidgetId) {
+        self.pointer.widgets.set_drag_region(id);
+    }
+
+    /// Let a widget laid out this frame take focus, by Tab in layout order
+    pub fn set_focusable(&mut self, id: WidgetId) {
+        self.set_tab_index(id, 0);
+    }
+
+    /// Let a widget laid out this frame take focus, in the order of `tab_index`
+    ///
+    /// As on the web, Tab visits positive indices first, lowest first, then
+    /// index 0 in layout order. Widgets with a negative index are skipped by
+    /// Tab but still focused by clicks and arrow keys.
+    pub fn set_tab_index(&mut self, id: WidgetId, tab_index: i32) {
+        self.pointer.widgets.set_tab_index(id, tab_index);
+    }
+
+    /// Move focus among the focusable widgets inside a composite widget laid
+    /// out this frame, like a list or radio group, with the arrow keys
+    pub fn set_focus_group(&mut self, id: WidgetId) {
+        self.pointer.widgets.set_focus_group(id);
+    }
+
+    /// Send keyboard input to the widget from now on
+    ///
+    /// Pressing the pointer moves focus to the focusable widget under it, or
+    /// clears it over none.
+    pub fn focus(&mut self, id: WidgetId) {
+        self.set_focus(Some(id), self.focus_visible);
+    }
+
+    /// Send keyboard input to no widget
+    pub fn clear_focus(&mut self) {
+        self.set_focus(None, false);
+    }
+
+    fn set_focus(&mut self, id: Option<WidgetId>, visible: bool) {
+        if (self.focused, self.focus_visible) == (id, visible) {
+            return;
+        }
+        self.invalidate_focus_ring();
+        let old = std::mem::replace(&mut self.focused, id);
+        self.focus_visible = visible;
+        self.invalidate_focus_ring();
+        for id in [old, id].into_iter().flatten() {
+            self.invalidate_state(id);
+        }
+    }
+
+    /// Whether focus was last moved from the keyboard, as when a focus ring
+    /// should be shown
+    pub fn is_focus_visible(&self) -> bool {
+        self.focus_visible
+    }
+
+    /// Keep Tab and the arrow keys from moving focus, from `Application::key_event`
+    pub fn consume_key(&mut self) {
+        self.key_consumed = true;
+    }
+
+    /// Area of the focus ring around the focused widget, when shown
+    fn focus_ring_rect(&self) -> Option<Rect> {
+        if !self.focus_visible {
+            return None;
+        }
+        Some(
+            self.pointer
+                .widgets
+                .rect(self.focused?)?
+                .inset(-FOCUS_RING_GAP),
+        )
+    }
+
+    fn invalidate_focus_ring(&mut self) {
+        if let Some(rect) = self.focus_ring_rect() {
+            self.damage.add(rect.inset(-FOCUS_RING_WIDTH).round_out());
+        }
+    }
+
+    fn record_focus_ring(&self, list: &mut DrawList) {
+        if let (Some(color), Some(rect)) = (self.theme.focus_ring, self.focus_ring_rect()) {
+            let ring = Path::rounded_rect(rect, FOCUS_RING_GAP + 1.0);
+            list.layer(Layer::Content)
+                .stroke_path(ring, Stroke::new(FOCUS_RING_WIDTH), color);
+        }
+    }
+
+    /// Widget keyboard input goes to
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focused
+    }
+
+    pub fn has_focus(&self, id: WidgetId) -> bool {
+        self.focused == Some(id)
+    }
+
+    /// Let an input method compose text for the focused widget, e.g. while a
+    /// text field has focus
+    ///
+    /// Composed text arrives through `Application::composition` and, once
+    /// committed, `Application::text_input`. Off by default.
+    pub fn set_ime_allowed(&mut self, allowed: bool) {
+        if self.ime.allowed != allowed {
+            self.ime.allowed = allowed;
+            self.ime.dirty = true;
+        }
+    }
+
+    /// Open the input method's candidate window at `p` in frame pixels,
+    /// usually just below the caret
+    pub fn set_ime_position(&mut self, p: Point) {
+        if self.ime.position != Some(p) {
+            self.ime.position = Some(p);
+            self.ime.dirty = true;
+        }
+    }
+
+    /// Keyboard shortcuts, e.g. to show a shortcut beside a menu item
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+
+    /// Register shortcuts, e.g. `ctx.shortcuts_mut().register("Ctrl+S", Msg::Save)`
+    pub fn shortcuts_mut(&mut self) -> &mut Shortcuts {
+        &mut self.shortcuts
+    }
+
+    /// Register system-wide hotkeys, e.g.
+    /// `ctx.hotkeys_mut().register("Ctrl+Shift+Space", Msg::ShowLauncher)`
+    #[cfg(feature = "hotkeys")]
+    pub fn hotkeys_mut(&mut self) -> &mut Hotkeys {
+        &mut self.hotkeys
+    }
+
+    /// Put an icon with a context menu into the system tray, e.g.
+    /// `ctx.tray_mut().show(options)`
+    #[cfg(feature = "tray")]
+    pub fn tray_mut(&mut self) -> &mut Tray {
+        &mut self.tray
+    }
+
+    /// Show desktop notifications that report clicks, e.g.
+    /// `ctx.notifications_mut().show("Saved", "report.pdf", None, Msg::Open)`
+    #[cfg(feature = "notifications")]
+    pub fn notifications_mut(&mut self) -> &mut Notifications {
+        &mut self.notifications
+    }
+
+    /// The system clipboard, e.g. `ctx.clipboard().set_text(row.name())?` on Ctrl+C
+    pub fn clipboard(&mut self) -> &mut Clipboard {
+        &mut self.clipboard
+    }
+
+    /// Modifier keys held down
+    pub fn modifiers(&self) -> Modifiers {
+        self.keyboard.modifiers
+    }
+
+    /// Run `filter` on every input event before the application gets it, e.g.
+    /// to close a popup on Escape anywhere or to log input
+    ///
+    /// Filters run in the order they were added. One returning `true` consumes
+    /// the event, so later filters and the application never see it.
+    pub fn add_event_filter(
+        &mut self,
+        filter: impl FnMut(&mut Context, &InputEvent) -> bool + 'static,
+    ) -> FilterId {
+        self.filters.add(Box::new(filter))
+    }
+
+    pub fn remove_event_filter(&mut self, id: FilterId) {
+        self.filters.remove(id);
+    }
+
+    /// Write every input event the application gets to a file, with its
+    /// time, until `stop_recording`, e.g. to attach to a bug report
+    pub fn start_recording(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        self.stop_recording()?;
+        self.recorder = Some(Recorder::create(path.as_ref())?);
+        Ok(())
+    }
+
+    /// Finish the recording, failing if any of it could not be written
+    pub fn stop_recording(&mut self) -> Result<(), Error> {
+        self.recorder.take().map_or(Ok(()), Recorder::finish)
+    }
+
+    /// Play a file made with `start_recording` back at its original pace,
+    /// ignoring input from the user until it ends
+    ///
+    /// The application gets the same events as it did while recording, with
+    /// the same targets, so a UI test can replay them and then check its
+    /// state. Event filters see them too. Drag payloads are not recorded, and
+    /// hover and pointer capture follow the live pointer.
+    pub fn replay(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        self.replay = Some(Replay::load(path.as_ref())?);
+        Ok(())
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Start dragging `payload`, e.g. on a pointer move with the button held
+    ///
+    /// Widgets under the pointer get drag events until the button is released
+    /// over one of them, which drops it there. `preview` follows the pointer,
+    /// centered on it, above everything else.
+    pub fn start_drag(&mut self, payload: impl Any, preview: Option<Bitmap>) {
+        self.cancel_drag();
+        let drag = Drag::new(Box::new(payload), preview, self.pointer.position());
+        self.invalidate_drag_preview(&drag);
+        self.drag = Some(drag);
+    }
+
+    /// The value being dragged, if it is a `T`
+    pub fn drag_payload<T: Any>(&self) -> Option<&T> {
+        self.drag.as_ref()?.payload().downcast_ref()
+    }
+
+    /// Files dragged over the window from outside the application
+    pub fn dragged_files(&self) -> Option<&[PathBuf]> {
+        self.drag_payload::<Vec<PathBuf>>().map(Vec::as_slice)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Stop dragging without dropping; the widget under it gets no leave event
+    pub fn cancel_drag(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            self.invalidate_drag_preview(&drag);
+        }
+    }
+
+    fn invalidate_drag_preview(&mut self, drag: &Drag) {
+        if let Some(rect) = drag.preview_rect() {
+            self.damage.add(rect.round_out());
+        }
+    }
+
+    /// Draw into a frame of a new size from the next frame on
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.renderer.resize_buffer(width, height)?;
+        (self.width, self.height) = (width, height);
+        self.damage.resize(width, height);
+        Ok(())
+    }
+
+    /// Change the text of the window's title bar, e.g.
+    /// `ctx.set_title("notes.txt — modified")`
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        let title = title.into();
+        if self.title != title {
+            self.title.clone_from(&title);
+            self.window_requests.push(WindowRequest::Title(title));
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Show the progress of a long operation on the window's taskbar button,
+    /// or with `None` stop showing it
+    ///
+    /// Linux docks show it only with `WindowOptions::app_id` naming an
+    /// installed `.desktop` file; macOS shows none.
+    #[cfg(feature = "taskbar")]
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.taskbar.set_progress(progress);
+    }
+
+    #[cfg(feature = "taskbar")]
+    pub fn progress(&self) -> Option<Progress> {
+        self.taskbar.progress()
+    }
+
+    /// Fill the screen with the window, or with `None` go back to a normal
+    /// window, e.g. on F11
+    ///
+    /// The frame and layout follow the new window size, as on any resize.
+    pub fn set_fullscreen(&mut self, fullscreen: Option<Fullscreen>) {
+        self.fullscreen = fullscreen;
+        self.window_requests
+            .push(WindowRequest::Fullscreen(fullscreen));
+    }
+
+    /// Switch between a borderless fullscreen window and a normal one
+    pub fn toggle_fullscreen(&mut self) {
+        let fullscreen = match self.fullscreen {
+            Some(_) => None,
+            None => Some(Fullscreen::Borderless),
+        };
+        self.set_fullscreen(fullscreen);
+    }
+
+    pub fn fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen
+    }
+
+    /// Show or hide the system's title bar and borders
+    pub fn set_decorations(&mut self, decorations: bool) {
+        self.window_requests
+            .push(WindowRequest::Decorations(decorations));
+    }
+
+    /// Keep the window above all others, or let it go behind them again
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.always_on_top = always_on_top;
+        self.window_requests
+            .push(WindowRequest::AlwaysOnTop(always_on_top));
+    }
+
+    pub fn is_always_on_top(&self) -> bool {
+        self.always_on_top
+    }
+
+    /// Move the window along with the pointer until the button is released,
+    /// e.g. on a press in a title bar of your own
+    pub fn drag_window(&mut self) {
+        self.window_requests.push(WindowRequest::Drag);
+    }
+
+    pub fn minimize(&mut self) {
+        self.window_requests.push(WindowRequest::Minimize);
+    }
+
+    /// Bring the window back from being minimized or maximized
+    pub fn restore(&mut self) {
+        self.window_requests.push(WindowRequest::Restore);
+    }
+
+    /// Whether the window is minimized, maximized or fullscreen, which
+    /// changes through `Application::window_state_changed`
+    ///
+    /// Nothing is drawn while it is minimized; the damage is repainted once
+    /// it is back.
+    pub fn window_state(&self) -> WindowState {
+        self.window_state
+    }
+
+    pub fn set_maximized(&mut self, maximized: bool) {
+        self.window_requests
+            .push(WindowRequest::Maximize(maximized));
+    }
+
+    /// Hide the window, e.g. to the tray, or show and focus it again
+    ///
+    /// The application keeps running while its window is hidden.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.window_requests.push(WindowRequest::Visible(visible));
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Displays of the computer, as of the window's last move or focus
+    pub fn monitors(&self) -> &[Monitor] {
+        &self.monitors
+    }
+
+    /// The monitor showing most of the window
+    pub fn current_monitor(&self) -> Option<&Monitor> {
+        self.monitors.get(self.monitor?)
+    }
+
+    /// Physical pixels per logical pixel on the window's monitor, which
+    /// changes through `Application::scale_factor_changed`
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Center the window on the monitor at `index` of `Context::monitors`
+    pub fn move_to_monitor(&mut self, index: usize) {
+        self.window_requests
+            .push(WindowRequest::MoveToMonitor(index));
+    }
+
+    /// Open a window of its own for a menu, dropdown or tooltip, which can
+    /// reach beyond the main window
+    ///
+    /// It has no decorations and stays above other windows. Draw it in
+    /// `Application::draw_popup`, which runs whenever the main window is
+    /// drawn, and take its clicks in `Application::popup_pointer_event`. Keys
+    /// typed while it has focus go to the main window.
+    pub fn open_popup(&mut self, options: PopupOptions) -> PopupId {
+        let id = self.popups.next_id();
+        self.popups.requests.push(PopupRequest::Open(id, options));
+        id
+    }
+
+    pub fn close_popup(&mut self, id: PopupId) {
+        self.popups.requests.push(PopupRequest::Close(id));
+    }
+
+    pub fn is_popup_open(&self, id: PopupId) -> bool {
+        self.popups.is_open(id)
+    }
+
+    /// Physical screen position of the frame position `p`, e.g. to open a
+    /// popup next to a widget
+    ///
+    /// Wayland does not reveal where windows are, so there it is relative to
+    /// the window.
+    pub fn screen_position(&self, p: Point) -> Point {
+        let p = self.renderer.window_position(p);
+        let origin = self.window_origin;
+        Point::new(origin.x as f32 + p.x, origin.y as f32 + p.y)
+    }
+
+    /// Draw at most this many frames per second from now on, or with `None`
+    /// as many as needed, e.g. fewer while running on battery
+    pub fn set_max_frame_rate(&mut self, fps: Option<u32>) {
+        self.frame_interval = fps.map(|fps| Duration::from_secs(1) / fps.max(1));
+    }
+
+    pub fn max_frame_rate(&self) -> Option<u32> {
+        let interval = self.frame_interval?;
+        Some((1.0 / interval.as_secs_f64()).round() as u32)
+    }
+
+    /// Close the window and exit once the current event is handled
+    pub fn close(&mut self) {
+        self.exit = true;
+    }
+
+    /// Keep the window open, from `Application::close_requested`
+    pub fn cancel_close(&mut self) {
+        self.close_cancelled = true;
+    }
+
+    /// Repaint the whole window on the next frame
+    pub fn request_repaint(&mut self) {
+        self.damage.invalidate_all();
+    }
+
+    /// Repaint only `rect` on the next frame
+    pub fn invalidate(&mut self, rect: Rect) {
+        self.damage.add(rect);
+    }
+
+    /// The frame currently on screen
+    pub fn screenshot(&mut self) -> RgbaImage {
+        self.renderer.capture().to_rgba_image()
+    }
+
+    /// Save the frame currently on screen, in the format the extension names
+    pub fn save_screenshot(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        self.renderer.capture().save(path)
+    }
+
+    /// Run `hook` on every finished frame before it is presented
+    ///
+    /// It gets the premultiplied RGBA pixels along with the frame's width and height.
+    pub fn set_post_process(&mut self, hook: impl FnMut(&mut [u8], u32, u32) + 'static) {
+        self.renderer.set_post_process(Some(Box::new(hook)));
+        self.request_repaint();
+    }
+
+    /// Present frames as drawn again
+    pub fn clear_post_process(&mut self) {
+        self.renderer.set_post_process(None);
+        self.request_repaint();
+    }
+}
+
+/// A Wixe application
+pub trait Application: 'static {
+    /// Record what the window shows
+    ///
+    /// Only the areas invalidated through the context are repainted from the list,
+    /// so call `ctx.invalidate` for anything that changed.
+    fn draw(&mut self, ctx: &mut Context, list: &mut DrawList);
+
+    /// React to the pointer, e.g. invalidate a button it pressed
+    fn pointer_event(&mut self, _ctx: &mut Context, _event: &PointerEvent) {}
+
+    /// React to a drag started with `Context::start_drag` or files dragged in
+    /// from the OS, e.g. highlight a drop target while it is over it and take
+    /// the payload on `Drop`
+    fn drag_event(&mut self, _ctx: &mut Context, _event: &DragEvent) {}
+
+    /// Scroll a widget made scrollable with `Context::set_scrollable`, e.g.
+    /// move its content by `event.delta` and invalidate it
+    fn scroll_event(&mut self, _ctx: &mut Context, _event: &ScrollEvent) {}
+
+    /// React to a tap, drag, swipe or pinch on a touchscreen, or a trackpad pinch
+    fn gesture_event(&mut self, _ctx: &mut Context, _event: &GestureEvent) {}
+
+    /// React to a gamepad, e.g. move a game character with the sticks
+    ///
+    /// Afterwards the d-pad moves focus, South presses Enter and East presses
+    /// Escape on the focused widget through `key_event`.
+    #[cfg(feature = "gamepad")]
+    fn gamepad_event(&mut self, _ctx: &mut Context, _event: &GamepadEvent) {}
+
+    /// React to the window being minimized, maximized, made fullscreen or
+    /// restored, by the user or the application, e.g. pause work that only
+    /// feeds what is on screen while it is minimized
+    fn window_state_changed(&mut self, _ctx: &mut Context, _state: WindowState) {}
+
+    /// A watched stylesheet changed but could not be loaded, e.g. show the
+    /// line it fails at; it keeps its rules from before
+    fn stylesheet_error(&mut self, _ctx: &mut Context, _error: &Error) {}
+
+    /// React to the switch between the light and dark theme, which
+    /// `Context::theme` has already made, e.g. by reloading icons drawn for
+    /// the other
+    fn appearance_changed(&mut self, _ctx: &mut Context, _appearance: Appearance) {}
+
+    /// Adapt to the window moving to a monitor with another scale factor, or
+    /// the user changing it, e.g. by loading sharper images
+    ///
+    /// The frame is laid out in logical pixels, so it is resized to keep its
+    /// physical size after this.
+    fn scale_factor_changed(&mut self, _ctx: &mut Context, _scale_factor: f64) {}
+
+    /// The user asked to close the window, e.g. with its close button or
+    /// Alt+F4; it closes unless this calls `ctx.cancel_close`
+    ///
+    /// To ask "Save changes?" first, cancel, show the question and call
+    /// `ctx.close` once it is answered.
+    fn close_requested(&mut self, _ctx: &mut Context) {}
+
+    /// Draw the popup `popup` opened with `Context::open_popup`, in frame
+    /// pixels of its own
+    fn draw_popup(&mut self, _ctx: &mut Context, _popup: PopupId, _list: &mut DrawList) {}
+
+    /// React to the pointer over a popup; the events have no target and
+    /// positions are in the popup's frame pixels
+    fn popup_pointer_event(&mut self, _ctx: &mut Context, _popup: PopupId, _event: &PointerEvent) {}
+
+    /// React to a key, e.g. run a command on Ctrl+S or move a caret
+    ///
+    /// Don't insert text here; it arrives through `text_input`, which also
+    /// covers dead keys and other layouts. Afterwards Tab and the arrow keys
+    /// move focus, unless the key was consumed with `Context::consume_key`.
+    fn key_event(&mut self, _ctx: &mut Context, _event: &KeyEvent) {}
+
+    /// Run the action of a shortcut registered with `Context::shortcuts_mut`,
+    /// e.g. `if let Some(msg) = event.action::<Msg>()`
+    fn shortcut(&mut self, _ctx: &mut Context, _event: &ShortcutEvent) {}
+
+    /// Run the action of a system-wide hotkey registered with
+    /// `Context::hotkeys_mut`, pressed in this or any other application
+    #[cfg(feature = "hotkeys")]
+    fn hotkey(&mut self, _ctx: &mut Context, _event: &ShortcutEvent) {}
+
+    /// React to the icon shown with `Context::tray_mut`, e.g. show a hidden
+    /// window again on a click or run the action of a menu item
+    #[cfg(feature = "tray")]
+    fn tray_event(&mut self, _ctx: &mut Context, _event: &TrayEvent) {}
+
+    /// Run the action of a notification shown with
+    /// `Context::notifications_mut`, e.g. open the message it announced
+    #[cfg(feature = "notifications")]
+    fn notification_clicked(&mut self, _ctx: &mut Context, _event: &NotificationEvent) {}
+
+    /// Insert text typed into the focused widget
+    fn text_input(&mut self, _ctx: &mut Context, _event: &TextEvent) {}
+
+    /// Show text an input method is composing, enabled with
+    /// `Context::set_ime_allowed`
+    fn composition(&mut self, _ctx: &mut Context, _event: &Composition) {}
+}
+
+/// Run the event filters on `event`, returning whether one consumed it
+fn filter_event(ctx: &mut Context, event: &InputEvent) -> bool {
+    if ctx.filters.is_empty() {
+        return false;
+    }
+    let mut filters = ctx.filters.take();
+    let consumed = filters.iter_mut().any(|(_, filter)| filter(ctx, event));
+    ctx.filters.restore(filters);
+    consumed
+}
+
+/// Deliver live input to its hook, recording it, returning `false` if a
+/// filter consumed it or a recording is being replayed in its place
+fn deliver<A: Application>(app: &mut A, ctx: &mut Context, event: InputEvent) -> bool {
+    if ctx.replay.is_some() {
+        return false;
+    }
+    if let Some(recorder) = &mut ctx.recorder {
+        recorder.record(&event);
+    }
+    dispatch(app, ctx, event)
+}
+
+/// Deliver `event` to its hook, returning `false` if a filter consumed it
+fn dispatch<A: Application>(app: &mut A, ctx: &mut Context, event: InputEvent) -> bool {
+    if filter_event(ctx, &event) {
+        return false;
+    }
+    match &event {
+        InputEvent::Pointer(event) => app.pointer_event(ctx, event),
+        InputEvent::Drag(event) => app.drag_event(ctx, event),
+        InputEvent::Key(event) => dispatch_key(app, ctx, event),
+        InputEvent::Text(event) => app.text_input(ctx, event),
+        InputEvent::Composition(event) => app.composition(ctx, event),
+        InputEvent::Scroll(event) => app.scroll_event(ctx, event),
+        InputEvent::Gesture(event) => app.gesture_event(ctx, event),
+        #[cfg(feature = "gamepad")]
+        InputEvent::Gamepad(event) => app.gamepad_event(ctx, event),
+    }
+    true
+}
+
+fn dispatch_pointer<A: Application>(app: &mut A, ctx: &mut Context, events: Vec<PointerEvent>) {
+    for event in events {
+        let changes_state = matches!(
+            event.kind,
+            PointerEventKind::Enter
+                | PointerEventKind::Leave
+                | PointerEventKind::Press(_)
+                | PointerEventKind::Release(_)
+        );
+        if let (true, Some(target)) = (changes_state, event.target) {
+            ctx.invalidate_state(target);
+        }
+        deliver(app, ctx, InputEvent::Pointer(event));
+    }
+}
+
+/// Move a drag in progress along with the pointer to `p`
+fn update_drag<A: Application>(app: &mut A, ctx: &mut Context, p: Point) {
+    let Some(mut drag) = ctx.drag.take() else {
+        return;
+    };
+    ctx.invalidate_drag_preview(&drag);
+    let events = drag.moved(p, ctx.pointer.target());
+    ctx.invalidate_drag_preview(&drag);
+    ctx.drag = Some(drag);
+    for event in events {
+        deliver(app, ctx, InputEvent::Drag(event));
+    }
+}
+
+/// Drop a drag in progress on the widget under the pointer
+fn finish_drag<A: Application>(app: &mut A, ctx: &mut Context) {
+    if let Some(event) = ctx.drag.as_ref().and_then(Drag::drop_event) {
+        deliver(app, ctx, InputEvent::Drag(event));
+    }
+    ctx.cancel_drag();
+}
+
+/// End a drag in progress without dropping, telling the widget under it
+fn leave_drag<A: Application>(app: &mut A, ctx: &mut Context) {
+    if let Some(event) = ctx.drag.as_mut().and_then(Drag::leave) {
+        deliver(app, ctx, InputEvent::Drag(event));
+    }
+    ctx.cancel_drag();
+}
+
+/// Add a file dragged in from the OS, starting a drag of files with the first
+fn hover_file<A: Application>(app: &mut A, ctx: &mut Context, path: PathBuf) {
+    let drag = ctx.drag.as_mut();
+    if let Some(files) = drag.and_then(|d| d.payload_mut().downcast_mut::<Vec<PathBuf>>()) {
+        files.push(path);
+        return;
+    }
+    ctx.start_drag(vec![path], None);
+    if let Some(p) = ctx.pointer.position() {
+        update_drag(app, ctx, p);
+    }
+}
+
+/// Drop the files dropped in this batch of events together
+///
+/// The OS reports each file separately, so they are only dropped once all of
+/// them are in.
+fn drop_files<A: Application>(app: &mut A, ctx: &mut Context) {
+    if ctx.dropped_files.is_empty() {
+        return;
+    }
+    let files = std::mem::take(&mut ctx.dropped_files);
+    match ctx.drag.as_mut() {
+        Some(drag) if drag.payload().is::<Vec<PathBuf>>() => drag.set_payload(Box::new(files)),
+        _ => ctx.start_drag(files, None),
+    }
+    if let Some(p) = ctx.pointer.position() {
+        update_drag(app, ctx, p);
+    }
+    finish_drag(app, ctx);
+}
+
+/// Deliver a key event, or the shortcut it triggers in its place
+fn dispatch_key<A: Application>(app: &mut A, ctx: &mut Context, event: &KeyEvent) {
+    let scopes = ctx
+        .focused
+        .map_or_else(Vec::new, |id| ctx.pointer.widgets.ancestry_of(id));
+    if let Some(shortcut) = ctx.shortcuts.find(event, &scopes) {
+        app.shortcut(ctx, &shortcut);
+        return;
+    }
+    ctx.key_consumed = false;
+    app.key_event(ctx, event);
+    navigate_focus(ctx, event);
+}
+
+/// Move focus with Tab, Shift+Tab and, inside focus groups, the arrow keys
+fn navigate_focus(ctx: &mut Context, event: &KeyEvent) {
+    let m = event.modifiers;
+    if !event.pressed || std::mem::take(&mut ctx.key_consumed) || m.ctrl || m.alt || m.logo {
+        return;
+    }
+    let widgets = &ctx.pointer.widgets;
+    let in_group = |backwards| {
+        ctx.focused
+            .and_then(|id| widgets.next_in_group(id, backwards))
+    };
+    let next = match event.key {
+        Key::Tab => widgets.next_focus(ctx.focused, m.shift),
+        Key::ArrowLeft | Key::ArrowUp => in_group(true),
+        Key::ArrowRight | Key::ArrowDown => in_group(false),
+        _ => None,
+    };
+    if let Some(id) = next {
+        ctx.set_focus(Some(id), true);
+    }
+}
+
+/// Pass input method events on as compositions and committed text
+fn dispatch_ime<A: Application>(app: &mut A, ctx: &mut Context, ime: WinitIme) {
+    let target = ctx.focused;
+    let composition = |text, cursor| Composition {
+        text,
+        cursor,
+        target,
+    };
+    match ime {
+        WinitIme::Preedit(text, cursor) => {
+            let cursor = cursor.map(|(start, end)| start..end);
+            deliver(app, ctx, InputEvent::Composition(composition(text, cursor)));
+        }
+        WinitIme::Commit(text) => {
+            let cleared = composition(String::new(), None);
+            deliver(app, ctx, InputEvent::Composition(cleared));
+            deliver(app, ctx, InputEvent::Text(TextEvent { text, target }));
+        }
+        WinitIme::Disabled => {
+            deliver(
+                app,
+                ctx,
+                InputEvent::Composition(composition(String::new(), None)),
+            );
+        }
+        WinitIme::Enabled => {}
+    }
+}
+
+/// Scroll the widget under the pointer, or pinch it with Ctrl held as
+/// trackpads do
+fn wheel<A: Application>(
+    app: &mut A,
+    ctx: &mut Context,
+    delta: MouseScrollDelta,
+    phase: TouchPhase,
+) {
+    if ctx.keyboard.modifiers.ctrl {
+        let (lines, pixels) = match delta {
+            MouseScrollDelta::LineDelta(_, y) => (y, 0.0),
+            MouseScrollDelta::PixelDelta(d) => (0.0, d.y as f32),
+        };
+        let target = ctx.pointer.target();
+        let position = ctx.pointer.position().unwrap_or_default();
+        let event = gesture::wheel_pinch(target, position, lines, pixels);
+        deliver(app, ctx, InputEvent::Gesture(event));
+        return;
+    }
+    let widgets = &ctx.pointer.widgets;
+    let Some(target) = widgets.scrollable_in(ctx.pointer.hovered()) else {
+        return;
+    };
+    let (position, now) = (ctx.pointer.position().unwrap_or_default(), Instant::now());
+    let event = match delta {
+        MouseScrollDelta::LineDelta(x, y) => {
+            ctx.scroll.lines(target, position, Point::new(x, y), now)
+        }
+        MouseScrollDelta::PixelDelta(d) => {
+            // Physical window pixels to frame pixels
+            let origin = ctx.renderer.frame_position(0.0, 0.0);
+            let p = ctx.renderer.frame_position(d.x as f32, d.y as f32);
+            let delta = Point::new(p.x - origin.x, p.y - origin.y);
+            let touching = !matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled);
+            ctx.scroll.pixels(target, position, delta, touching, now)
+        }
+    };
+    if let Some(event) = event {
+        deliver(app, ctx, InputEvent::Scroll(event));
+    }
+}
+
+/// Recognize gestures from a touch, and let the primary finger move the pointer
+fn touch_input<A: Application>(app: &mut A, ctx: &mut Context, touch: Touch) {
+    let location = touch.location;
+    let p = (ctx.renderer).frame_position(location.x as f32, location.y as f32);
+    let now = Instant::now();
+    let as_pointer = |ctx: &Context| ctx.touch_as_pointer && ctx.touches.primary == Some(touch.id);
+    let mut pointer = Vec::new();
+    let gestures = match touch.phase {
+        TouchPhase::Started => {
+            let target = ctx.pointer.widgets.path_at(p).last().copied();
+            let gestures = ctx.touches.start(touch.id, p, target, now);
+            match ctx.touches.count() {
+                1 => ctx.long_press.press(Some(touch.id), target, p, now),
+                _ => ctx.long_press.cancel(),
+            }
+            if as_pointer(ctx) {
+                ctx.scroll.stop();
+                pointer = ctx.pointer.moved(p);
+                pointer.extend(ctx.pointer.button(MouseButton::Left, true, now));
+            }
+            gestures
+        }
+        TouchPhase::Moved => {
+            ctx.long_press.moved(Some(touch.id), p);
+            if as_pointer(ctx) {
+                pointer = ctx.pointer.moved(p);
+            }
+            ctx.touches.moved(touch.id, p, now)
+        }
+        TouchPhase::Ended | TouchPhase::Cancelled => {
+            ctx.long_press.release(Some(touch.id));
+            if as_pointer(ctx) {
+                pointer = ctx.pointer.button(MouseButton::Left, false, now);
+                // A lifted finger hovers nothing
+                pointer.extend(ctx.pointer.left());
+            }
+            let cancelled = touch.phase == TouchPhase::Cancelled;
+            ctx.touches.end(touch.id, p, now, cancelled)
+        }
+    };
+    dispatch_pointer(app, ctx, pointer);
+    for event in gestures {
+        deliver(app, ctx, InputEvent::Gesture(event));
+    }
+}
+
+/// Deliver the long press of a press held long enough
+fn long_press<A: Application>(app: &mut A, ctx: &mut Context) {
+    if let Some((event, touch)) = ctx.long_press.tick(Instant::now()) {
+        if let Some(id) = touch {
+            ctx.touches.cancel_tap(id);
+        }
+        deliver(app, ctx, InputEvent::Gesture(event));
+    }
+}
+
+/// Deliver gamepad input, then navigate with it as with the keyboard
+#[cfg(feature = "gamepad")]
+fn gamepad_input<A: Application>(app: &mut A, ctx: &mut Context) {
+    for event in ctx.gamepads.poll() {
+        if !deliver(app, ctx, InputEvent::Gamepad(event)) {
+            continue;
+        }
+        let (button, pressed) = match event.kind {
+            GamepadEventKind::Press(button) => (button, true),
+            GamepadEventKind::Release(button) => (button, false),
+            _ => continue,
+        };
+        let key = match button {
+            GamepadButton::South => Key::Enter,
+            GamepadButton::East => Key::Escape,
+            GamepadButton::DPadUp | GamepadButton::DPadLeft if pressed => {
+                gamepad_focus(ctx, true);
+                continue;
+            }
+            GamepadButton::DPadDown | GamepadButton::DPadRight if pressed => {
+                gamepad_focus(ctx, false);
+                continue;
+            }
+            _ => continue,
+        };
+        // Keys pressed from a gamepad have no place on the keyboard
+        let event = KeyEvent {
+            key,
+            code: KeyCode(0),
+            modifiers: Modifiers::NONE,
+            pressed,
+            repeat: false,
+            target: ctx.focused,
+        };
+        deliver(app, ctx, InputEvent::Key(event));
+    }
+}
+
+/// Move focus within its group, or else in tab order, with the d-pad
+#[cfg(feature = "gamepad")]
+fn gamepad_focus(ctx: &mut Context, backwards: bool) {
+    let widgets = &ctx.pointer.widgets;
+    let next = ctx
+        .focused
+        .and_then(|id| widgets.next_in_group(id, backwards))
+        .or_else(|| widgets.next_focus(ctx.focused, backwards));
+    if let Some(id) = next {
+        ctx.set_focus(Some(id), true);
+    }
+}
+
+/// Deliver the events of a recording being replayed that are due
+fn replay<A: Application>(app: &mut A, ctx: &mut Context) {
+    let Some(replay) = &mut ctx.replay else {
+        return;
+    };
+    let events = replay.due(Instant::now());
+    if replay.is_done() {
+        ctx.replay = None;
+    }
+    for event in events {
+        dispatch(app, ctx, event);
+    }
+}
+
+/// Earliest time the frame-rate cap allows the next frame
+fn next_frame(ctx: &Context) -> Option<Instant> {
+    Some(ctx.last_frame? + ctx.frame_interval?)
+}
+
+/// When the event loop must wake up without input, for animations and timers
+fn next_wake(ctx: &Context) -> Option<Instant> {
+    let animating = ctx.scroll.is_animating() || ctx.transitions.is_animating();
+    // Gamepads have no events of their own to wake it up, so they are polled
+    #[cfg(feature = "gamepad")]
+    let animating = animating || ctx.gamepads.is_active();
+    let interval = ctx
+        .frame_interval
+        .map_or(ANIMATION_FRAME, |i| i.max(ANIMATION_FRAME));
+    let frame = animating.then(|| Instant::now() + interval);
+    // Damage held back by the frame-rate cap is drawn once it allows
+    let capped = next_frame(ctx).filter(|next| !ctx.damage.is_empty() && *next > Instant::now());
+    [
+        frame,
+        capped,
+        ctx.long_press.deadline(),
+        ctx.keyboard.repeat_deadline(),
+        ctx.replay.as_ref().and_then(Replay::deadline),
+        ctx.stylesheets_checked
+            .map(|checked| checked + STYLESHEET_POLL),
+    ]
+    .into_iter()
+    .flatten()
+    .min()
+}
+
+/// Load the watched stylesheets whose files changed, restyling the window
+fn reload_stylesheets<A: Application>(app: &mut A, ctx: &mut Context) {
+    let now = Instant::now();
+    match ctx.stylesheets_checked {
+        Some(checked) if now >= checked + STYLESHEET_POLL => {
+            ctx.stylesheets_checked = Some(now);
+        }
+        _ => return,
+    }
+    let mut errors = Vec::new();
+    let mut changed = false;
+    for sheet in &mut ctx.stylesheets {
+        match sheet.reload() {
+            Ok(reloaded) => changed |= reloaded,
+            Err(e) => errors.push(e),
+        }
+    }
+    if changed {
+        ctx.request_repaint();
+    }
+    for error in errors {
+        app.stylesheet_error(ctx, &error);
+    }
+}
+
+/// Repaint the widgets whose style is mid-transition
+fn update_transitions(ctx: &mut Context) {
+    let widgets = &ctx.pointer.widgets;
+    for id in ctx.transitions.animating() {
+        match widgets.rect(id) {
+            Some(rect) => ctx.damage.add(rect.round_out()),
+            None => ctx.damage.invalidate_all(),
+        }
+    }
+}
+
+/// Take up the system's accent color if a read of it came in and changed it
+#[cfg(feature = "system-theme")]
+fn update_accent(ctx: &mut Context) {
+    let Some(accent) = ctx.accent_watcher.as_ref().and_then(AccentWatcher::poll) else {
+        return;
+    };
+    if accent != ctx.system_accent {
+        ctx.system_accent = accent;
+        ctx.use_theme(ctx.theme_of(ctx.appearance).clone());
+    }
+}
+
+/// Switch to the theme of the appearance now in effect, if it changed
+fn update_appearance<A: Application>(app: &mut A, ctx: &mut Context) {
+    let appearance = ctx.forced_appearance.unwrap_or(ctx.system_appearance);
+    if appearance == ctx.appearance {
+        return;
+    }
+    ctx.appearance = appearance;
+    ctx.use_theme(ctx.theme_of(appearance).clone());
+    app.appearance_changed(ctx, appearance);
+}
+
+fn update_window_state<A: Application>(app: &mut A, ctx: &mut Context, window: &Window) {
+    let state = WindowState::of(window, ctx.occluded);
+    if state == ctx.window_state {
+        return;
+    }
+    // Repaint what was damaged while nothing was drawn
+    if ctx.window_state == WindowState::Minimized {
+        window.request_redraw();
+    }
+    ctx.window_state = state;
+    app.window_state_changed(ctx, state);
+}
+
+/// Take stock of the monitors, which winit reports no changes of
+fn update_monitors(ctx: &mut Context, window: &Window) {
+    let monitors: Vec<_> = window.available_monitors().collect();
+    let primary = window.primary_monitor();
+    ctx.monitors = (monitors.iter())
+        .map(|monitor| Monitor::new(monitor, primary.as_ref()))
+        .collect();
+    let current = window.current_monitor();
+    ctx.monitor = current.and_then(|current| monitors.iter().position(|m| *m == current));
+}
+
+/// Draw and present the popup shown in `window`
+fn draw_popup<A: Application>(
+    app: &mut A,
+    ctx: &mut Context,
+    window: WindowId,
+    list: &mut DrawList,
+) {
+    let Some(id) = ctx.popups.get_mut(window).map(|p| p.id) else {
+        return;
+    };
+    list.clear();
+    app.draw_popup(ctx, id, list);
+    // The popup may have been closed while drawing
+    if let Some(popup) = ctx.popups.get_mut(window) {
+        let _ = popup.render(list);
+    }
+}
+
+fn popup_input<A: Application>(
+    app: &mut A,
+    ctx: &mut Context,
+    window: WindowId,
+    event: &WindowEvent,
+) {
+    let Some(popup) = ctx.popups.get_mut(window) else {
+        return;
+    };
+    let id = popup.id;
+    if let WindowEvent::CloseRequested = event {
+        ctx.close_popup(id);
+    } else if let Some(event) = popup.pointer_event(event) {
+        app.popup_pointer_event(ctx, id, &event);
+    }
+}
+
+fn is_keyboard(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ReceivedCharacter(_)
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Ime(_)
+    )
+}
+
+/// Show the cursor of the widget under the pointer, or holding pointer
+/// capture, and repaint a custom one that moved
+fn update_cursor(ctx: &mut Context, window: &Window) {
+    let widgets = &ctx.pointer.widgets;
+    let captured = ctx.pointer.captured.map(|id| {
+        let mut path = widgets.ancestry_of(id);
+        path.reverse();
+        path
+    });
+    let cursor = widgets.cursor_in(captured.as_deref().unwrap_or(ctx.pointer.hovered()));
+    let mut cursor = cursor.cloned().unwrap_or_default();
+    let edges =
+        (ctx.edge_resize.map(|resize| resize.edges)).or(ctx.edge_hover.map(|(edges, _)| edges));
+    if let Some(edges) = edges {
+        cursor = Cursor::Icon(edges.cursor());
+    }
+    if cursor != ctx.cursor {
+        match &cursor {
+            Cursor::Icon(icon) => {
+                window.set_cursor_icon((*icon).into());
+                window.set_cursor_visible(true);
+            }
+            Cursor::Custom(_) => window.set_cursor_visible(false),
+        }
+        ctx.cursor = cursor;
+    }
+    let rect = ctx.custom_cursor_rect();
+    if rect != ctx.cursor_drawn {
+        for r in [rect, ctx.cursor_drawn].into_iter().flatten() {
+            ctx.damage.add(r.round_out());
+        }
+    }
+}
+
+/// `width` and `height` within the window's size limits
+///
+/// Window managers may not enforce the limits, but the frame keeps to them
+/// so the UI is never laid out for a size it does not allow.
+fn clamp_size(options: &WindowOptions, width: u32, height: u32) -> (u32, u32) {
+    let (min_width, min_height) = options.min_size.unwrap_or((0, 0));
+    let (max_width, max_height) = options.max_size.unwrap_or((u32::MAX, u32::MAX));
+    (
+        width.min(max_width).max(min_width),
+        height.min(max_height).max(min_height),
+    )
+}
+
+fn window_icon(bitmap: &Bitmap) -> Result<Icon, Error> {
+    let rgba = bitmap.to_rgba_image().into_raw();
+    Ok(Icon::from_rgba(rgba, bitmap.width(), bitmap.height())?)
+}
+
+/// Open a window and run `app` until it is closed
+pub fn run<A: Application>(options: WindowOptions, mut app: A) -> Result<(), Error> {
+    let event_loop = EventLoop::new();
+    let geometry_path = (options.remember_geometry.as_deref()).and_then(window::geometry_path);
+    let saved = geometry_path.as_deref().and_then(Geometry::load);
+    let (width, height) = saved.map_or((options.width, options.height), |g| g.size);
+    let (width, height) = clamp_size(&options, width, height);
+    let icons = (options.icon.iter())
+        .map(ImageSource::load)
+        .collect::<Result<Vec<_>, _>>()?;
+    let title_bar_icon = icons.iter().min_by_key(|icon| icon.width().abs_diff(32));
+    let builder = WindowBuilder::new()
+        .with_title(&options.title)
+        .with_window_icon(title_bar_icon.map(window_icon).transpose()?)
+        .with_inner_size(LogicalSize::new(width, height))
+        .with_resizable(options.resizable)
+        .with_decorations(options.decorations)
+        .with_transparent(options.transparent)
+        .with_always_on_top(options.always_on_top);
+    let builder = match options.min_size {
+        Some((width, height)) => builder.with_min_inner_size(LogicalSize::new(width, height)),
+        None => builder,
+    };
+    let builder = match options.max_size {
+        Some((width, height)) => builder.with_max_inner_size(LogicalSize::new(width, height)),
+        None => builder,
+    };
+    let monitor = options
+        .monitor
+        .and_then(|i| event_loop.available_monitors().nth(i));
+    let builder = match (saved, monitor) {
+        (Some(saved), _) if saved.is_reachable(event_loop.available_monitors()) => {
+            builder.with_position(saved.position)
+        }
+        (_, Some(monitor)) => {
+            let size = LogicalSize::new(width, height).to_physical(monitor.scale_factor());
+            builder.with_position(window::centered_on(&monitor, size))
+        }
+        _ => builder,
+    };
+    let builder = builder.with_maximized(saved.is_some_and(|g| g.maximized));
+    #[cfg(target_os = "linux")]
+    let builder = match &options.app_id {
+        Some(app_id) => builder.with_name(app_id, app_id),
+        None => builder,
+    };
+    #[cfg(windows)]
+    let builder = {
+        let taskbar_icon = icons.iter().max_by_key(|icon| icon.width());
+        builder.with_taskbar_icon(taskbar_icon.map(window_icon).transpose()?)
+    };
+    let window = builder.build(&event_loop)?;
+    let system_appearance = theme::system_appearance(&window).unwrap_or_default();
+    let appearance = options.appearance.unwrap_or(system_appearance);
+    let theme = match appearance {
+        Appearance::Light => &options.theme,
+        Appearance::Dark => &options.dark_theme,
+    };
+
+    let mut renderer = backend::create(
+        options.backend,
+        &window,
+        width,
+        height,
+        theme.background,
+        options.present_mode,
+    )?;
+    renderer.set_scaling(options.scaling);
+    renderer.set_render_scale(options.render_scale);
+
+    let mut ctx = Context::new(width, height, renderer);
+    ctx.pointer.click_interval = options.double_click_interval;
+    ctx.light_theme = options.theme.clone();
+    ctx.dark_theme = options.dark_theme.clone();
+    ctx.appearance = appearance;
+    ctx.system_appearance = system_appearance;
+    ctx.forced_appearance = options.appearance;
+    ctx.use_theme(theme.clone());
+    ctx.tokens = options.tokens.clone();
+    ctx.stylesheets.clone_from(&options.stylesheets);
+    if options.watch_stylesheets {
+        ctx.stylesheets_checked = Some(Instant::now());
+    }
+    ctx.scroll.options = options.scroll;
+    ctx.touch_as_pointer = options.touch_as_pointer;
+    ctx.long_press.delay = options.long_press;
+    ctx.keyboard.repeat = options.key_repeat;
+    ctx.resize_border = options.resize_border;
+    ctx.always_on_top = options.always_on_top;
+    ctx.title.clone_from(&options.title);
+    ctx.set_max_frame_rate(options.max_frame_rate);
+    #[cfg(all(feature = "taskbar", target_os = "linux"))]
+    ctx.taskbar.app_id.clone_from(&options.app_id);
+    if options.fullscreen.is_some() {
+        ctx.set_fullscreen(options.fullscreen);
+    }
+    #[cfg(feature = "hotkeys")]
+    ctx.hotkeys.listen(event_loop.create_proxy());
+    #[cfg(feature = "tray")]
+    ctx.tray.listen(event_loop.create_proxy());
+    #[cfg(feature = "notifications")]
+    ctx.notifications.listen(event_loop.create_proxy());
+    #[cfg(feature = "system-theme")]
+    if options.system_accent {
+        ctx.accent_watcher = Some(AccentWatcher::start(event_loop.create_proxy()));
+    }
+    ctx.window_origin = window.inner_position().unwrap_or_default();
+    ctx.scale_factor = window.scale_factor();
+    ctx.window_state = WindowState::of(&window, false);
+    update_monitors(&mut ctx, &window);
+    // Size and position to restore a maximized window to next time
+    let mut normal = saved.or_else(|| Geometry::of(&window));
+    let mut list = DrawList::new();
+    let mut popup_list = DrawList::new();
+
+    event_loop.run(move |event, target, control_flow| {
+        *control_flow = ControlFlow::Wait;
+
+        match event {
+            Event::RedrawRequested(id) if id != window.id() => {
+                draw_popup(&mut app, &mut ctx, id, &mut popup_list);
+            }
+            Event::RedrawRequested(_) => {
+                // Anything invalidated while drawing belongs to the next frame
+                let mut damage =
+                    std::mem::replace(&mut ctx.damage, DamageTracker::new(ctx.width, ctx.height));
+                ctx.last_frame = Some(Instant::now());
+                if !damage.is_empty() {
+                    list.clear();
+                    ctx.pointer.widgets.clear();
+                    app.draw(&mut ctx, &mut list);
+                    ctx.transitions.end_frame();
+                    ctx.record_focus_ring(&mut list);
+                    if let Some(drag) = &ctx.drag {
+                        drag.record_preview(&mut list);
+                    }
+                    ctx.record_cursor(&mut list);
+                    list.expand_damage(&mut damage);
+                }
+
+                if ctx.renderer.render(&list, &damage.take()).is_err() {
+                    *control_flow = ControlFlow::Exit;
+                }
+                // Widgets may have moved under a pointer that stood still
+                let events = ctx.pointer.update_hover();
+                dispatch_pointer(&mut app, &mut ctx, events);
+                update_cursor(&mut ctx, &window);
+            }
+            // Keys typed into a popup go on to the main window
+            Event::WindowEvent { window_id, event }
+                if window_id != window.id() && !is_keyboard(&event) =>
+            {
+                popup_input(&mut app, &mut ctx, window_id, &event);
+            }
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => {
+                    ctx.close_cancelled = false;
+                    app.close_requested(&mut ctx);
+                    if !std::mem::take(&mut ctx.close_cancelled) {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                }
+                WindowEvent::Moved(_) => {
+                    ctx.window_origin = window.inner_position().unwrap_or_default();
+                    update_monitors(&mut ctx, &window);
+                    if geometry_path.is_some() {
+                        normal = Geometry::of(&window).or(normal);
+                    }
+                }
+                WindowEvent::Resized(size) => {
+                    ctx.window_origin = window.inner_position().unwrap_or_default();
+                    if geometry_path.is_some() {
+                        normal = Geometry::of(&window).or(normal);
+                    }
+                    // The user may also enter and leave fullscreen, e.g. on macOS
+                    ctx.fullscreen = window.fullscreen().as_ref().map(Fullscreen::from);
+                    if ctx
+                        .renderer
+                        .resize_surface(size.width, size.height)
+                        .is_err()
+                    {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    // The frame is sized in logical pixels, like the window was created.
+                    // A minimized window reports zero, which no frame can have.
+                    let logical = size.to_logical::<u32>(window.scale_factor());
+                    let (width, height) = clamp_size(&options, logical.width, logical.height);
+                    if options.resize_buffer
+                        && logical.width > 0
+                        && logical.height > 0
+                        && (width, height) != (ctx.width, ctx.height)
+                        && ctx.resize(width, height).is_err()
+                    {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    window.request_redraw();
+                    update_window_state(&mut app, &mut ctx, &window);
+                }
+                WindowEvent::Occluded(occluded) => {
+                    ctx.occluded = occluded;
+                    update_window_state(&mut app, &mut ctx, &window);
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    if let Some(resize) = ctx.edge_resize {
+                        resize.moved(&window, position, |w, h| clamp_size(&options, w, h));
+                        return;
+                    }
+                    ctx.edge_hover = Edges::at(&window, position, ctx.resize_border)
+                        .map(|edges| (edges, position));
+                    let p = ctx
+                        .renderer
+                        .frame_position(position.x as f32, position.y as f32);
+                    ctx.long_press.moved(None, p);
+                    let events = ctx.pointer.moved(p);
+                    dispatch_pointer(&mut app, &mut ctx, events);
+                    update_drag(&mut app, &mut ctx, p);
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    ctx.edge_hover = None;
+                    leave_drag(&mut app, &mut ctx);
+                    let events = ctx.pointer.left();
+                    dispatch_pointer(&mut app, &mut ctx, events);
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let pressed = state == ElementState::Pressed;
+                    // Presses on the edges of a window without decorations
+                    // resize it instead of reaching the application
+                    if button == WinitButton::Left && ctx.edge_resize.is_some() {
+                        ctx.edge_resize = None;
+                        return;
+                    }
+                    if let (WinitButton::Left, true, Some((edges, p))) =
+                        (button, pressed, ctx.edge_hover)
+                    {
+                        ctx.edge_resize = EdgeResize::start(&window, edges, p);
+                        return;
+                    }
+                    let hovered = ctx.pointer.hovered().last().copied();
+                    let drag_region =
+                        hovered.is_some_and(|id| ctx.pointer.widgets.is_drag_region(id));
+                    if button == WinitButton::Left && pressed && drag_region {
+                        // The system takes over the button until it is released
+                        let _ = window.drag_window();
+                        return;
+                    }
+                    if button == WinitButton::Left {
+                        match (pressed, ctx.pointer.position()) {
+                            (true, Some(p)) => {
+                                let target = ctx.pointer.target();
+                                ctx.long_press.press(None, target, p, Instant::now());
+                            }
+                            _ => ctx.long_press.release(None),
+                        }
+                    }
+                    if pressed {
+                        ctx.scroll.stop();
+                        let widgets = &ctx.pointer.widgets;
+                        let focus = widgets.focusable_in(ctx.pointer.hovered());
+                        ctx.set_focus(focus, false);
+                    } else {
+                        finish_drag(&mut app, &mut ctx);
+                    }
+                    let events = ctx.pointer.button(button.into(), pressed, Instant::now());
+                    dispatch_pointer(&mut app, &mut ctx, events);
+                }
+                WindowEvent::MouseWheel { delta, phase, .. } => {
+                    wheel(&mut app, &mut ctx, delta, phase);
+                }
+                WindowEvent::Touch(touch) => touch_input(&mut app, &mut ctx, touch),
+                WindowEvent::ModifiersChanged(state) => ctx.keyboard.modifiers = state.into(),
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    ctx.scale_factor = scale_factor;
+                    update_monitors(&mut ctx, &window);
+                    app.scale_factor_changed(&mut ctx, scale_factor);
+                }
+                WindowEvent::Focused(true) => {
+                    update_monitors(&mut ctx, &window);
+                    // Few systems report a change of preference
+                    if let Some(appearance) = theme::system_appearance(&window) {
+                        ctx.system_appearance = appearance;
+                    }
+                    #[cfg(feature = "system-theme")]
+                    if let Some(watcher) = &ctx.accent_watcher {
+                        watcher.refresh();
+                    }
+                }
+                WindowEvent::ThemeChanged(theme) => {
+                    ctx.system_appearance = theme::appearance_of(theme);
+                }
+                WindowEvent::Focused(false) => {
+                    ctx.edge_resize = None;
+                    ctx.keyboard.release_all();
+                    ctx.pointer.release_all();
+                }
+                WindowEvent::KeyboardInput { input, .. } => {
+                    let event = ctx.keyboard.key(
+                        input.virtual_keycode,
+                        KeyCode(input.scancode),
+                        input.state == ElementState::Pressed,
+                        ctx.focused,
+                        Instant::now(),
+                    );
+                    if let Some(event) = event {
+                        deliver(&mut app, &mut ctx, InputEvent::Key(event));
+                    }
+                }
+                WindowEvent::ReceivedCharacter(c) if keyboard::is_text(c, ctx.modifiers()) => {
+                    let event = TextEvent {
+                        text: c.to_string(),
+                        target: ctx.focused,
+                    };
+                    deliver(&mut app, &mut ctx, InputEvent::Text(event));
+                }
+                WindowEvent::Ime(ime) => dispatch_ime(&mut app, &mut ctx, ime),
+                WindowEvent::HoveredFile(path) => hover_file(&mut app, &mut ctx, path),
+                WindowEvent::HoveredFileCancelled => leave_drag(&mut app, &mut ctx),
+                WindowEvent::DroppedFile(path) => ctx.dropped_files.push(path),
+                _ => {}
+            },
+            Event::MainEventsCleared => {
+                drop_files(&mut app, &mut ctx);
+                if let Some(event) = ctx.scroll.tick(Instant::now()) {
+                    deliver(&mut app, &mut ctx, InputEvent::Scroll(event));
+                }
+                long_press(&mut app, &mut ctx);
+                if let Some(event) = ctx.keyboard.tick(Instant::now()) {
+                    let event = KeyEvent {
+                        target: ctx.focused,
+                        ..event
+                    };
+                    deliver(&mut app, &mut ctx, InputEvent::Key(event));
+                }
+                #[cfg(feature = "gamepad")]
+                gamepad_input(&mut app, &mut ctx);
+                replay(&mut app, &mut ctx);
+                #[cfg(feature = "hotkeys")]
+                for event in ctx.hotkeys.poll() {
+                    app.hotkey(&mut ctx, &event);
+                }
+                #[cfg(feature = "tray")]
+                for event in ctx.tray.poll() {
+                    app.tray_event(&mut ctx, &event);
+                }
+                #[cfg(feature = "notifications")]
+                for event in ctx.notifications.poll() {
+                    app.notification_clicked(&mut ctx, &event);
+                }
+                reload_stylesheets(&mut app, &mut ctx);
+                update_transitions(&mut ctx);
+                #[cfg(feature = "system-theme")]
+                update_accent(&mut ctx);
+                update_appearance(&mut app, &mut ctx);
+                update_cursor(&mut ctx, &window);
+                for request in ctx.window_requests.drain(..) {
+                    request.apply(&window);
+                }
+                if ctx.exit {
+                    *control_flow = ControlFlow::Exit;
+                }
+                #[cfg(feature = "taskbar")]
+                ctx.taskbar.apply(&window);
+                if std::mem::take(&mut ctx.ime.dirty) {
+                    window.set_ime_allowed(ctx.ime.allowed);
+                    if let Some(p) = ctx.ime.position {
+                        let p = ctx.renderer.window_position(p);
+                        window.set_ime_position(PhysicalPosition::new(p.x, p.y));
+                    }
+                }
+                ctx.popups.apply(
+                    target,
+                    options.backend,
+                    ctx.theme.background,
+                    options.present_mode,
+                );
+                let capped = next_frame(&ctx).is_some_and(|next| next > Instant::now());
+                if !ctx.damage.is_empty() && ctx.window_state != WindowState::Minimized && !capped {
+                    window.request_redraw();
+                    for popup in &ctx.popups.open {
+                        popup.request_redraw();
+                    }
+                }
+            }
+            Event::RedrawEventsCleared => {
+                if let Some(wake) = next_wake(&ctx) {
+                    *control_flow = ControlFlow::WaitUntil(wake);
+                }
+            }
+            Event::LoopDestroyed => {
+                if let (Some(path), Some(normal)) = (&geometry_path, normal) {
+                    let maximized = window.is_maximized();
+                    // Nothing is left to report a failure to
+                    let _ = Geometry {
+                        maximized,
+                        ..normal
+                    }
+                    .save(path);
+                }
+            }
+            _ => {}
+        }
+    });
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use crate::bitmap::{Bitmap, FilterMode};
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+
+/// Empty pixels kept around each allocation so filtering never bleeds into neighbours
+pub(crate) const PADDING: u32 = 1;
+
+/// A region of an atlas or sprite sheet, in source pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn to_rect(self) -> Rect {
+        Rect::new(
+            self.x as f32,
+            self.y as f32,
+            self.width as f32,
+            self.height as f32,
+        )
+    }
+
+    /// The part of the region inside a `width` x `height` bitmap
+    pub fn clamp_to(self, width: u32, height: u32) -> Self {
+        let (x, y) = (self.x.min(width), self.y.min(height));
+        Self::new(x, y, self.width.min(width - x), self.height.min(height - y))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    cursor: u32,
+}
+
+/// Shelf packer handing out rectangles of a fixed-size texture
+#[derive(Debug, Clone)]
+pub struct AtlasAllocator {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl AtlasAllocator {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Reserve a `width` x `height` region, or `None` when the atlas is full
+    pub fn allocate(&mut self, width: u32, height: u32) -> Option<Region> {
+        let (w, h) = (width + PADDING, height + PADDING);
+        if w > self.width || h > self.height {
+            return None;
+        }
+        // Best fit: the shortest existing shelf that is tall enough and has room
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|s| s.height >= h && self.width - s.cursor >= w)
+            .min_by_key(|s| s.height);
+        if let Some(shelf) = best {
+            let region = Region::new(shelf.cursor, shelf.y, width, height);
+            shelf.cursor += w;
+            return Some(region);
+        }
+        let y = self.shelves.last().map_or(0, |s| s.y + s.height);
+        if self.height - y < h {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            height: h,
+            cursor: w,
+        });
+        Some(Region::new(0, y, width, height))
+    }
+
+    /// Forget every allocation
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+/// Named regions of a single bitmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteSheet {
+    bitmap: Bitmap,
+    sprites: HashMap<String, Region>,
+}
+
+impl SpriteSheet {
+    pub fn new(bitmap: Bitmap) -> Self {
+        Self {
+            bitmap,
+            sprites: HashMap::new(),
+        }
+    }
+
+    /// Split `bitmap` into equally sized cells named `{prefix}{index}`, row by row
+    pub fn grid(bitmap: Bitmap, cell_width: u32, cell_height: u32, prefix: &str) -> Self {
+        let mut sheet = SpriteSheet::new(bitmap);
+        let cols = sheet.bitmap.width() / cell_width.max(1);
+        let rows = sheet.bitmap.height() / cell_height.max(1);
+        for row in 0..rows {
+            for col in 0..cols {
+                let region =
+                    Region::new(col * cell_width, row * cell_height, cell_width, cell_height);
+                sheet.insert(format!("{prefix}{}", row * cols + col), region);
+            }
+        }
+        sheet
+    }
+
+    /// Pack separate images into one atlas bitmap of at most `max_size` pixels square
+    ///
+    /// Returns `None` when they don't fit.
+    pub fn pack(images: impl IntoIterator<Item = (String, Bitmap)>, max_size: u32) -> Option<Self> {
+        let mut images: Vec<_> = images.into_iter().collect();
+        // Tallest first packs shelves tightly
+        images.sort_by_key(|(_, b)| std::cmp::Reverse(b.height()));
+        let mut allocator = AtlasAllocator::new(max_size, max_size);
+        let mut placed = Vec::with_capacity(images.len());
+        let (mut used_w, mut used_h) = (0, 0);
+        for (name, bitmap) in images {
+            let region = allocator.allocate(bitmap.width(), bitmap.height())?;
+            used_w = used_w.max(region.x + region.width);
+            used_h = used_h.max(region.y + region.height);
+            placed.push((name, bitmap, region));
+        }
+        let mut pixels = vec![0u8; (used_w * used_h * 4) as usize];
+        for (_, bitmap, region) in &placed {
+            for row in 0..region.height {
+                let src = (row * bitmap.width() * 4) as usize;
+                let dst = (((region.y + row) * used_w + region.x) * 4) as usize;
+                let len = (region.width * 4) as usize;
+                pixels[dst..dst + len].copy_from_slice(&bitmap.pixels()[src..src + len]);
+            }
+        }
+        let mut sheet = SpriteSheet::new(Bitmap::from_premultiplied(used_w, used_h, pixels));
+        for (name, _, region) in placed {
+            sheet.insert(name, region);
+        }
+        Some(sheet)
+    }
+
+    /// Name a region of the sheet, clipped to its bitmap
+    pub fn insert(&mut self, name: impl Into<String>, region: Region) {
+        let region = region.clamp_to(self.bitmap.width(), self.bitmap.height());
+        self.sprites.insert(name.into(), region);
+    }
+
+    pub fn region(&self, name: &str) -> Option<Region> {
+        self.sprites.get(name).copied()
+    }
+
+    pub fn bitmap(&self) -> &Bitmap {
+        &self.bitmap
+    }
+}
+
+impl Painter<'_> {
+    /// Draw the part `src` of `bitmap` stretched to fill `dst`; whatever of
+    /// `src` lies past the bitmap is left out, and the part of `dst` it maps to
+    /// left undrawn
+    pub fn draw_image_region(
+        &mut self,
+        bitmap: &Bitmap,
+        src: Region,
+        dst: Rect,
+        filter: FilterMode,
+    ) {
+        if src.width == 0 || src.height == 0 || dst.is_empty() {
+            return;
+        }
+        let inside = src.clamp_to(bitmap.width(), bitmap.height());
+        if inside.width == 0 || inside.height == 0 {
+            return;
+        }
+        let dst = Rect::new(
+            dst.x,
+            dst.y,
+            dst.width * inside.width as f32 / src.width as f32,
+            dst.height * inside.height as f32 / src.height as f32,
+        );
+        let src = inside;
+        let t = self.transform();
+        if t.is_translation() && src.width as f32 == dst.width && src.height as f32 == dst.height {
+            // Unscaled: copy straight from the sheet
+            let (ox, oy) = ((dst.x + t.e).round() as i32, (dst.y + t.f).round() as i32);
+            let area = Rect::new(ox as f32, oy as f32, dst.width, dst.height);
+            if let Some(area) = area.intersect(&self.clip_rect()) {
+                for y in area.y as i32..area.max_y() as i32 {
+                    for x in area.x as i32..area.max_x() as i32 {
+                        let px = bitmap.pixel(src.x + (x - ox) as u32, src.y + (y - oy) as u32);
+                        self.composite_pixel(x, y, px);
+                    }
+                }
+            }
+            return;
+        }
+        let inv = match t.inverse() {
+            Some(inv) => inv,
+            None => return,
+        };
+        let area = match t.map_rect(dst).round_out().intersect(&self.clip_rect()) {
+            Some(area) => area,
+            None => return,
+        };
+        let sx = src.width as f32 / dst.width;
+        let sy = src.height as f32 / dst.height;
+        for y in area.y as i32..area.max_y() as i32 {
+            for x in area.x as i32..area.max_x() as i32 {
+                let p = inv.apply(Point::new(x as f32 + 0.5, y as f32 + 0.5));
+                let (u, v) = ((p.x - dst.x) * sx, (p.y - dst.y) * sy);
+                if u < 0.0 || v < 0.0 || u >= src.width as f32 || v >= src.height as f32 {
+                    continue;
+                }
+                // Clamp inside the region so bilinear taps stay on this sprite
+                let u =
+                    (src.x as f32 + u).clamp(src.x as f32 + 0.5, (src.x + src.width) as f32 - 0.5);
+                let v =
+                    (src.y as f32 + v).clamp(src.y as f32 + 0.5, (src.y + src.height) as f32 - 0.5);
+                self.composite_pixel(x, y, bitmap.sample(u, v, filter));
+            }
+        }
+    }
+
+    /// Draw a named sprite stretched to fill `dst`; unknown names draw nothing
+    pub fn draw_sprite(&mut self, sheet: &SpriteSheet, name: &str, dst: Rect) {
+        if let Some(region) = sheet.region(name) {
+            self.draw_image_region(&sheet.bitmap, region, dst, FilterMode::Bilinear);
+        }
+    }
+}
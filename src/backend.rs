@@ -0,0 +1,684 @@
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture, wgpu};
+use winit::window::Window;
+
+use crate::bitmap::Bitmap;
+#[cfg(any(feature = "gpu", feature = "skia"))]
+use crate::blend::BlendMode;
+use crate::color::Color;
+#[cfg(any(feature = "gpu", feature = "skia"))]
+use crate::draw_list::DrawCmd;
+use crate::draw_list::DrawList;
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::transform::Transform;
+
+/// Which renderer presents the window's frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Backend {
+    /// Rasterize on the CPU into a pixel buffer
+    #[default]
+    Software,
+    /// Draw quads with wgpu, rasterizing on the CPU only what has no GPU primitive
+    #[cfg(feature = "gpu")]
+    Gpu,
+    /// Rasterize shapes with tiny-skia, everything else like `Software`
+    #[cfg(feature = "skia")]
+    Skia,
+}
+
+/// How a frame buffer is fitted into a window of another size
+///
+/// The frame keeps its aspect ratio and is centered, with bars around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Scaling {
+    /// As large as fits the window
+    #[default]
+    Fit,
+    /// The largest whole multiple of the frame size that fits, for crisp pixel art
+    ///
+    /// A window smaller than the frame still shrinks it to fit.
+    Integer,
+}
+
+/// How finished frames are handed to the display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PresentMode {
+    /// Wait for the display to refresh, so frames never tear and at most one
+    /// is shown per refresh
+    #[default]
+    Vsync,
+    /// Show each frame as soon as it is done, for the lowest input latency at
+    /// the cost of tearing; where the platform cannot, the same as `Vsync`
+    Immediate,
+}
+
+impl PresentMode {
+    pub(crate) fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Vsync => wgpu::PresentMode::Fifo,
+            // Falls back to tear-free modes where tearing is not offered
+            PresentMode::Immediate => wgpu::PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+/// Highest supported factor for `RenderBackend::set_render_scale`
+pub const MAX_RENDER_SCALE: f32 = 4.0;
+
+/// Callback run on every finished frame just before it is presented
+///
+/// It gets the premultiplied RGBA pixels, row-major, and the frame's width and
+/// height, and may change them, e.g. for gamma or color-blindness filters, or
+/// copy them elsewhere for streaming and recording.
+pub type PostProcess = Box<dyn FnMut(&mut [u8], u32, u32)>;
+
+/// Turns a draw list into pixels on screen
+pub trait RenderBackend {
+    /// Follow a change of the window's size in physical pixels
+    fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), Error>;
+
+    /// Change the size of the frame drawn into; its contents are lost
+    fn resize_buffer(&mut self, width: u32, height: u32) -> Result<(), Error>;
+
+    /// Repaint `damage` from `list` and present the frame
+    ///
+    /// Backends that cannot keep the previous frame may repaint everything.
+    fn render(&mut self, list: &DrawList, damage: &[Rect]) -> Result<(), Error>;
+
+    /// Frame position shown at `x`, `y` physical pixels into the window
+    ///
+    /// Positions over the bars around the frame map outside of it.
+    fn frame_position(&self, x: f32, y: f32) -> Point;
+
+    /// Physical window position showing the frame position `p`
+    ///
+    /// The default inverts `frame_position`, which must scale and offset
+    /// positions alike everywhere.
+    fn window_position(&self, p: Point) -> Point {
+        const FAR: f32 = 4096.0;
+        let origin = self.frame_position(0.0, 0.0);
+        let far = self.frame_position(FAR, FAR);
+        Point::new(
+            (p.x - origin.x) * FAR / (far.x - origin.x),
+            (p.y - origin.y) * FAR / (far.y - origin.y),
+        )
+    }
+
+    /// Copy of the frame last presented, at the frame buffer's size
+    fn capture(&mut self) -> Bitmap;
+
+    /// Run `hook` on every frame from now on, or stop post-processing with `None`
+    fn set_post_process(&mut self, hook: Option<PostProcess>);
+
+    /// Clear to `color` before drawing from the next frame on
+    fn set_clear_color(&mut self, color: Color);
+
+    /// Choose how the frame is fitted into the window
+    fn set_scaling(&mut self, scaling: Scaling);
+
+    /// Draw at `scale` times the frame's resolution and filter it down, for
+    /// smoother edges; backends that cannot supersample ignore this
+    fn set_render_scale(&mut self, _scale: f32) {}
+}
+
+/// Post-processing for backends that draw into a persistent CPU frame
+///
+/// The hook changes the presented pixels, so while one is installed drawing
+/// goes to an unprocessed copy instead. Otherwise undamaged areas would be
+/// processed again on every frame.
+#[derive(Default)]
+pub(crate) struct PostProcessor {
+    hook: Option<PostProcess>,
+    clean: Vec<u8>,
+}
+
+impl PostProcessor {
+    pub(crate) fn set(&mut self, hook: Option<PostProcess>, frame: &mut [u8]) {
+        match (&hook, &self.hook) {
+            (Some(_), None) => self.clean = frame.to_vec(),
+            (None, Some(_)) => frame.copy_from_slice(&std::mem::take(&mut self.clean)),
+            _ => {}
+        }
+        self.hook = hook;
+    }
+
+    /// Buffer to draw the frame into
+    pub(crate) fn target<'a>(&'a mut self, frame: &'a mut [u8]) -> &'a mut [u8] {
+        match self.hook {
+            Some(_) => &mut self.clean,
+            None => frame,
+        }
+    }
+
+    /// Follow a resized frame, whose contents are repainted from scratch
+    pub(crate) fn resize(&mut self, len: usize) {
+        if self.hook.is_some() {
+            self.clean = vec![0; len];
+        }
+    }
+
+    /// Fill `frame` with the processed drawing, once it is complete
+    pub(crate) fn finish(&mut self, frame: &mut [u8], width: u32, height: u32) {
+        if let Some(hook) = &mut self.hook {
+            frame.copy_from_slice(&self.clean);
+            hook(frame, width, height);
+        }
+    }
+}
+
+/// Open the renderer selected by `backend` for `window`
+pub(crate) fn create(
+    backend: Backend,
+    window: &Window,
+    width: u32,
+    height: u32,
+    clear_color: Color,
+    present_mode: PresentMode,
+) -> Result<Box<dyn RenderBackend>, Error> {
+    Ok(match backend {
+        Backend::Software => Box::new(SoftwareBackend::new(
+            window,
+            width,
+            height,
+            clear_color,
+            present_mode,
+        )?),
+        #[cfg(feature = "gpu")]
+        Backend::Gpu => Box::new(crate::gpu::GpuBackend::new(
+            window,
+            width,
+            height,
+            clear_color,
+            present_mode,
+        )?),
+        #[cfg(feature = "skia")]
+        Backend::Skia => Box::new(crate::skia::SkiaBackend::new(
+            window,
+            width,
+            height,
+            clear_color,
+            present_mode,
+        )?),
+    })
+}
+
+/// A `pixels` frame buffer of `width` by `height` for `window`
+pub(crate) fn open_pixels(
+    window: &Window,
+    width: u32,
+    height: u32,
+    present_mode: PresentMode,
+) -> Result<Pixels, Error> {
+    let window_size = window.inner_size();
+    let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, window);
+    let builder =
+        PixelsBuilder::new(width, height, surface_texture).present_mode(present_mode.to_wgpu());
+    Ok(builder.build()?)
+}
+
+/// Surface area a frame of `size` is shown in, centered with bars around it
+pub(crate) fn frame_viewport(size: (u32, u32), surface: (u32, u32), scaling: Scaling) -> Rect {
+    let (fw, fh) = (size.0.max(1) as f32, size.1.max(1) as f32);
+    let (sw, sh) = (surface.0 as f32, surface.1 as f32);
+    let fit = (sw / fw).min(sh / fh);
+    let scale = match scaling {
+        Scaling::Integer if fit >= 1.0 => fit.floor(),
+        _ => fit,
+    };
+    let (w, h) = ((fw * scale).round(), (fh * scale).round());
+    Rect::new(((sw - w) / 2.0).floor(), ((sh - h) / 2.0).floor(), w, h)
+}
+
+/// Frame position shown at `x`, `y` on the surface when the frame fills `viewport`
+pub(crate) fn viewport_position(viewport: Rect, size: (u32, u32), x: f32, y: f32) -> Point {
+    Point::new(
+        (x - viewport.x) * size.0 as f32 / viewport.width,
+        (y - viewport.y) * size.1 as f32 / viewport.height,
+    )
+}
+
+/// Shows a `pixels` frame buffer in the window as `Scaling` asks, in place of
+/// the `pixels` scaler, which only scales by whole multiples
+///
+/// The bars around the frame are black, or transparent with a clear color that
+/// is not opaque, so a transparent window shows what is behind it there.
+pub(crate) struct FramePresenter {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    linear: wgpu::Sampler,
+    nearest: wgpu::Sampler,
+    /// Binds the frame texture, which `pixels` replaces when the buffer is resized
+    bind_group: Option<wgpu::BindGroup>,
+    surface: (u32, u32),
+    scaling: Scaling,
+}
+
+impl FramePresenter {
+    pub(crate) fn new(pixels: &Pixels, window: &Window) -> Self {
+        let device = pixels.device();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("wixe present shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("present.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wixe present bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wixe present pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("wixe present pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: pixels.surface_texture_format(),
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = |filter| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("wixe present sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+        let size = window.inner_size();
+        Self {
+            pipeline,
+            layout,
+            linear: sampler(wgpu::FilterMode::Linear),
+            nearest: sampler(wgpu::FilterMode::Nearest),
+            bind_group: None,
+            surface: (size.width, size.height),
+            scaling: Scaling::default(),
+        }
+    }
+
+    pub(crate) fn resize_surface(&mut self, width: u32, height: u32) {
+        self.surface = (width, height);
+    }
+
+    /// Follow a resize of the frame buffer, which comes with a new texture
+    pub(crate) fn resize_buffer(&mut self) {
+        self.bind_group = None;
+    }
+
+    pub(crate) fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+        self.bind_group = None;
+    }
+
+    pub(crate) fn frame_position(&self, size: (u32, u32), x: f32, y: f32) -> Point {
+        let viewport = frame_viewport(size, self.surface, self.scaling);
+        viewport_position(viewport, size, x, y)
+    }
+
+    /// Upload the frame and draw it into its viewport
+    pub(crate) fn present(
+        &mut self,
+        pixels: &Pixels,
+        size: (u32, u32),
+        clear_color: Color,
+    ) -> Result<(), Error> {
+        // Whole multiples keep pixels crisp, anything else is smoothed
+        let sampler = match self.scaling {
+            Scaling::Integer => &self.nearest,
+            Scaling::Fit => &self.linear,
+        };
+        let bind_group = self.bind_group.get_or_insert_with(|| {
+            let view = pixels
+                .texture()
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            pixels
+                .device()
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("wixe present bind group"),
+                    layout: &self.layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(sampler),
+                        },
+                    ],
+                })
+        });
+        let viewport = frame_viewport(size, self.surface, self.scaling);
+        let bars = match clear_color.a {
+            255 => wgpu::Color::BLACK,
+            _ => wgpu::Color::TRANSPARENT,
+        };
+        let pipeline = &self.pipeline;
+        pixels.render_with(|encoder, target, _| {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("wixe present pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(bars),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            if viewport.width >= 1.0 && viewport.height >= 1.0 {
+                let v = viewport;
+                pass.set_viewport(v.x, v.y, v.width, v.height, 0.0, 1.0);
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.draw(0..3, 0..1);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+/// Clip, transform and blend mode tracking for backends that walk a draw list themselves
+#[cfg(any(feature = "gpu", feature = "skia"))]
+pub(crate) struct CommandState {
+    clips: Vec<Option<Rect>>,
+    transforms: Vec<Transform>,
+    modes: Vec<BlendMode>,
+}
+
+#[cfg(any(feature = "gpu", feature = "skia"))]
+impl CommandState {
+    pub(crate) fn new(clip: Rect) -> Self {
+        Self {
+            clips: vec![Some(clip)],
+            transforms: vec![Transform::IDENTITY],
+            modes: Vec::new(),
+        }
+    }
+
+    /// Apply a clip, transform or blend mode command, `false` for anything else
+    pub(crate) fn update(&mut self, cmd: &DrawCmd) -> bool {
+        let t = self.transform();
+        match cmd {
+            DrawCmd::PushClip(rect) => {
+                // Snapped to whole pixels the way the painter does
+                let r = t.map_rect(*rect);
+                let r = Rect::from_points(
+                    Point::new(r.min_x().round(), r.min_y().round()),
+                    Point::new(r.max_x().round(), r.max_y().round()),
+                );
+                let clip = self.clip().and_then(|c| c.intersect(&r));
+                self.clips.push(clip);
+            }
+            DrawCmd::PopClip => {
+                if self.clips.len() > 1 {
+                    self.clips.pop();
+                }
+            }
+            DrawCmd::PushTransform(local) => self.transforms.push(local.then(t)),
+            DrawCmd::PopTransform => {
+                if self.transforms.len() > 1 {
+                    self.transforms.pop();
+                }
+            }
+            DrawCmd::PushBlendMode(mode) => self.modes.push(*mode),
+            DrawCmd::PopBlendMode => {
+                self.modes.pop();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub(crate) fn transform(&self) -> Transform {
+        *self.transforms.last().unwrap_or(&Transform::IDENTITY)
+    }
+
+    pub(crate) fn blend_mode(&self) -> BlendMode {
+        self.modes.last().copied().unwrap_or_default()
+    }
+
+    /// Current clip in frame pixels, `None` once nothing is left to draw into
+    pub(crate) fn clip(&self) -> Option<Rect> {
+        *self.clips.last().unwrap_or(&None)
+    }
+
+    /// Pixel-aligned frame area `cmd` can touch, `None` when it is clipped out
+    pub(crate) fn visible_area(&self, cmd: &DrawCmd) -> Option<Rect> {
+        let bounds = self.transform().map_rect(cmd.bounds()?);
+        bounds.round_out().intersect(&self.clip()?)
+    }
+}
+
+/// An offscreen copy of the frame at a higher resolution, averaged down into it
+///
+/// Drawing at twice the resolution and filtering it down smooths edges and
+/// small text on low-DPI displays, at four times the cost.
+pub(crate) struct Supersampler {
+    scale: f32,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Supersampler {
+    /// `None` when `scale` does not add resolution
+    pub(crate) fn new(scale: f32, frame_width: u32, frame_height: u32) -> Option<Self> {
+        let scale = scale.min(MAX_RENDER_SCALE);
+        if scale.is_nan() || scale <= 1.0 {
+            return None;
+        }
+        let width = (frame_width as f32 * scale).ceil() as u32;
+        let height = (frame_height as f32 * scale).ceil() as u32;
+        Some(Self {
+            scale,
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        })
+    }
+
+    pub(crate) fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub(crate) fn painter(&mut self) -> Painter<'_> {
+        Painter::new(&mut self.pixels, self.width, self.height)
+    }
+
+    #[cfg(feature = "skia")]
+    pub(crate) fn buffer(&mut self) -> (&mut [u8], u32, u32) {
+        (&mut self.pixels, self.width, self.height)
+    }
+
+    /// Area of the buffer that `resolve` reads for a frame region
+    pub(crate) fn region(&self, region: Rect) -> Rect {
+        let r = region.round_out();
+        let (x0, y0) = (
+            (r.min_x() * self.scale).floor(),
+            (r.min_y() * self.scale).floor(),
+        );
+        let x1 = (r.max_x() * self.scale).ceil().min(self.width as f32);
+        let y1 = (r.max_y() * self.scale).ceil().min(self.height as f32);
+        Rect::new(x0, y0, (x1 - x0).max(0.0), (y1 - y0).max(0.0))
+    }
+
+    /// Box-filter `region` of the buffer down into the frame
+    pub(crate) fn resolve(&self, region: Rect, frame: &mut [u8], width: u32, height: u32) {
+        let frame_rect = Rect::new(0.0, 0.0, width as f32, height as f32);
+        let Some(r) = region.round_out().intersect(&frame_rect) else {
+            return;
+        };
+        let span = |p: u32, limit: u32| {
+            let start = (p as f32 * self.scale).floor() as u32;
+            let end = (((p + 1) as f32 * self.scale).ceil() as u32).clamp(start + 1, limit);
+            start.min(limit - 1)..end
+        };
+        for y in r.min_y() as u32..r.max_y() as u32 {
+            let rows = span(y, self.height);
+            for x in r.min_x() as u32..r.max_x() as u32 {
+                let cols = span(x, self.width);
+                let mut sum = [0u32; 4];
+                for sy in rows.clone() {
+                    for sx in cols.clone() {
+                        let idx = ((sy * self.width + sx) * 4) as usize;
+                        for (c, v) in sum.iter_mut().zip(&self.pixels[idx..idx + 4]) {
+                            *c += *v as u32;
+                        }
+                    }
+                }
+                let n = rows.len() as u32 * cols.len() as u32;
+                let idx = ((y * width + x) * 4) as usize;
+                for (dst, c) in frame[idx..idx + 4].iter_mut().zip(sum) {
+                    *dst = ((c + n / 2) / n) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// CPU rendering into a `pixels` frame buffer, scaled to the window
+pub struct SoftwareBackend {
+    pixels: Pixels,
+    presenter: FramePresenter,
+    width: u32,
+    height: u32,
+    clear_color: Color,
+    post: PostProcessor,
+    supersampler: Option<Supersampler>,
+    render_scale: f32,
+}
+
+impl SoftwareBackend {
+    pub fn new(
+        window: &Window,
+        width: u32,
+        height: u32,
+        clear_color: Color,
+        present_mode: PresentMode,
+    ) -> Result<Self, Error> {
+        let pixels = open_pixels(window, width, height, present_mode)?;
+        Ok(Self {
+            presenter: FramePresenter::new(&pixels, window),
+            pixels,
+            width,
+            height,
+            clear_color,
+            post: PostProcessor::default(),
+            supersampler: None,
+            render_scale: 1.0,
+        })
+    }
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.pixels.resize_surface(width, height);
+        self.presenter.resize_surface(width, height);
+        Ok(())
+    }
+
+    fn resize_buffer(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.pixels.resize_buffer(width, height);
+        self.presenter.resize_buffer();
+        (self.width, self.height) = (width, height);
+        self.post.resize(self.pixels.get_frame().len());
+        self.supersampler = Supersampler::new(self.render_scale, width, height);
+        Ok(())
+    }
+
+    fn render(&mut self, list: &DrawList, damage: &[Rect]) -> Result<(), Error> {
+        let frame = self.pixels.get_frame_mut();
+        let target = self.post.target(frame);
+        match &mut self.supersampler {
+            Some(ss) => {
+                for region in damage {
+                    let (area, scale) = (ss.region(*region), ss.scale());
+                    let mut painter = ss.painter();
+                    painter.push_clip(area);
+                    painter.clear(self.clear_color);
+                    painter.push_transform(Transform::scale(scale, scale));
+                    list.replay(&mut painter);
+                    ss.resolve(*region, target, self.width, self.height);
+                }
+            }
+            None => {
+                let mut painter = Painter::new(target, self.width, self.height);
+                for region in damage {
+                    painter.push_clip(*region);
+                    painter.clear(self.clear_color);
+                    list.replay(&mut painter);
+                    painter.pop_clip();
+                }
+            }
+        }
+        self.post.finish(frame, self.width, self.height);
+        let size = (self.width, self.height);
+        self.presenter.present(&self.pixels, size, self.clear_color)
+    }
+
+    fn frame_position(&self, x: f32, y: f32) -> Point {
+        self.presenter
+            .frame_position((self.width, self.height), x, y)
+    }
+
+    fn capture(&mut self) -> Bitmap {
+        let frame = self.pixels.get_frame().to_vec();
+        Bitmap::from_premultiplied(self.width, self.height, frame)
+    }
+
+    fn set_post_process(&mut self, hook: Option<PostProcess>) {
+        self.post.set(hook, self.pixels.get_frame_mut());
+    }
+
+    fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    fn set_scaling(&mut self, scaling: Scaling) {
+        self.presenter.set_scaling(scaling);
+    }
+
+    fn set_render_scale(&mut self, scale: f32) {
+        self.render_scale = scale;
+        self.supersampler = Supersampler::new(scale, self.width, self.height);
+    }
+}
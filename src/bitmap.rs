@@ -0,0 +1,347 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::blend;
+use crate::error::Error;
+use crate::geometry::{Point, Rect, Size};
+use crate::painter::Painter;
+
+/// Number of resized copies kept per bitmap
+const SCALE_CACHE_SIZE: usize = 4;
+
+/// How pixels are sampled when an image is drawn at a different size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FilterMode {
+    /// Blocky, for pixel art
+    Nearest,
+    /// Smooth, for photos
+    #[default]
+    Bilinear,
+}
+
+type ScaleCache = Vec<((u32, u32, FilterMode), Bitmap)>;
+
+/// Decoded RGBA pixels, stored premultiplied and shared between clones
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Arc<Vec<u8>>,
+    scaled: Arc<Mutex<ScaleCache>>,
+}
+
+impl PartialEq for Bitmap {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.pixels == other.pixels
+    }
+}
+
+impl Bitmap {
+    /// Wrap straight-alpha RGBA bytes
+    pub fn from_rgba(width: u32, height: u32, mut rgba: Vec<u8>) -> Self {
+        assert_eq!(
+            rgba.len(),
+            (width * height * 4) as usize,
+            "RGBA data does not match size"
+        );
+        for px in rgba.chunks_exact_mut(4) {
+            let p = blend::premultiply([px[0], px[1], px[2], px[3]]);
+            px.copy_from_slice(&p);
+        }
+        Self::from_premultiplied(width, height, rgba)
+    }
+
+    /// Wrap RGBA bytes that are already premultiplied
+    pub fn from_premultiplied(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (width * height * 4) as usize,
+            "RGBA data does not match size"
+        );
+        Self {
+            width,
+            height,
+            pixels: Arc::new(pixels),
+            scaled: Arc::default(),
+        }
+    }
+
+    /// Share pixel storage that is already premultiplied, without copying it
+    pub(crate) fn from_shared(width: u32, height: u32, pixels: Arc<Vec<u8>>) -> Self {
+        debug_assert_eq!(pixels.len(), (width * height * 4) as usize);
+        Self {
+            width,
+            height,
+            pixels,
+            scaled: Arc::default(),
+        }
+    }
+
+    /// Load a PNG or JPEG file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(image::open(path)?.into_rgba8().into())
+    }
+
+    /// Decode PNG or JPEG data held in memory
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(image::load_from_memory(bytes)?.into_rgba8().into())
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width as f32, self.height as f32)
+    }
+
+    /// Premultiplied RGBA bytes, row-major
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Pixels to change in place, copied first if the storage is shared
+    #[cfg(feature = "svg")]
+    pub(crate) fn pixels_mut(&mut self) -> &mut [u8] {
+        // Resampled copies would show the old pixels
+        self.scaled = Arc::default();
+        Arc::make_mut(&mut self.pixels).as_mut_slice()
+    }
+
+    /// Identity of the shared pixel storage, equal for clones of one bitmap
+    #[cfg(feature = "gpu")]
+    pub(crate) fn id(&self) -> usize {
+        Arc::as_ptr(&self.pixels) as usize
+    }
+
+    /// Premultiplied color at (x, y)
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let idx = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[idx],
+            self.pixels[idx + 1],
+            self.pixels[idx + 2],
+            self.pixels[idx + 3],
+        ]
+    }
+
+    /// Copy of the pixels inside a sub-rectangle, clamped to the bitmap
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Bitmap {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in y..y + height {
+            let start = ((row * self.width + x) * 4) as usize;
+            pixels.extend_from_slice(&self.pixels[start..start + (width * 4) as usize]);
+        }
+        Bitmap::from_premultiplied(width, height, pixels)
+    }
+
+    /// Premultiplied color at a position in pixel units, clamped to the edges
+    pub fn sample(&self, u: f32, v: f32, filter: FilterMode) -> [u8; 4] {
+        let (max_x, max_y) = (self.width as i32 - 1, self.height as i32 - 1);
+        match filter {
+            FilterMode::Nearest => {
+                let x = (u.floor() as i32).clamp(0, max_x);
+                let y = (v.floor() as i32).clamp(0, max_y);
+                self.pixel(x as u32, y as u32)
+            }
+            FilterMode::Bilinear => {
+                let (x, y) = (u - 0.5, v - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let at = |xi: i32, yi: i32| {
+                    self.pixel(xi.clamp(0, max_x) as u32, yi.clamp(0, max_y) as u32)
+                };
+                let (xi, yi) = (x0 as i32, y0 as i32);
+                let (p00, p10, p01, p11) = (
+                    at(xi, yi),
+                    at(xi + 1, yi),
+                    at(xi, yi + 1),
+                    at(xi + 1, yi + 1),
+                );
+                std::array::from_fn(|c| {
+                    let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+                    let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+                    (top * (1.0 - fy) + bottom * fy).round() as u8
+                })
+            }
+        }
+    }
+
+    /// Copy resampled to `width` x `height`, cached for repeated draws at the same size
+    pub fn scaled(&self, width: u32, height: u32, filter: FilterMode) -> Bitmap {
+        if width == self.width && height == self.height {
+            return self.clone();
+        }
+        let key = (width, height, filter);
+        let mut cache = self.scaled.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, bitmap)) = cache.iter().find(|(k, _)| *k == key) {
+            return bitmap.clone();
+        }
+        let (sx, sy) = (
+            self.width as f32 / width as f32,
+            self.height as f32 / height as f32,
+        );
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let px = self.sample((x as f32 + 0.5) * sx, (y as f32 + 0.5) * sy, filter);
+                pixels.extend_from_slice(&px);
+            }
+        }
+        let bitmap = Bitmap::from_premultiplied(width, height, pixels);
+        if cache.len() >= SCALE_CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push((key, bitmap.clone()));
+        bitmap
+    }
+}
+
+impl Bitmap {
+    /// Straight-alpha copy for saving or handing to the `image` crate
+    pub fn to_rgba_image(&self) -> image::RgbaImage {
+        let rgba = self
+            .pixels
+            .chunks_exact(4)
+            .flat_map(|px| blend::unpremultiply([px[0], px[1], px[2], px[3]]))
+            .collect();
+        image::RgbaImage::from_raw(self.width, self.height, rgba).expect("RGBA data matches size")
+    }
+
+    /// Write to a file, in the format its extension names
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        Ok(self.to_rgba_image().save(path)?)
+    }
+}
+
+impl From<image::RgbaImage> for Bitmap {
+    fn from(img: image::RgbaImage) -> Self {
+        let (width, height) = (img.width(), img.height());
+        Bitmap::from_rgba(width, height, img.into_raw())
+    }
+}
+
+/// Where an image comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    File(PathBuf),
+    Bytes(Cow<'static, [u8]>),
+    Bitmap(Bitmap),
+}
+
+impl ImageSource {
+    pub fn load(&self) -> Result<Bitmap, Error> {
+        match self {
+            ImageSource::File(path) => Bitmap::open(path),
+            ImageSource::Bytes(bytes) => Bitmap::decode(bytes),
+            ImageSource::Bitmap(bitmap) => Ok(bitmap.clone()),
+        }
+    }
+}
+
+impl From<&str> for ImageSource {
+    fn from(path: &str) -> Self {
+        ImageSource::File(path.into())
+    }
+}
+
+impl From<PathBuf> for ImageSource {
+    fn from(path: PathBuf) -> Self {
+        ImageSource::File(path)
+    }
+}
+
+impl From<&'static [u8]> for ImageSource {
+    fn from(bytes: &'static [u8]) -> Self {
+        ImageSource::Bytes(Cow::Borrowed(bytes))
+    }
+}
+
+impl From<Vec<u8>> for ImageSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        ImageSource::Bytes(Cow::Owned(bytes))
+    }
+}
+
+impl From<Bitmap> for ImageSource {
+    fn from(bitmap: Bitmap) -> Self {
+        ImageSource::Bitmap(bitmap)
+    }
+}
+
+impl Painter<'_> {
+    /// Draw `bitmap` stretched to fill `dst` with bilinear filtering
+    pub fn draw_image(&mut self, bitmap: &Bitmap, dst: Rect) {
+        self.draw_image_filtered(bitmap, dst, FilterMode::Bilinear);
+    }
+
+    /// Draw `bitmap` stretched to fill `dst`, alpha blended over the frame
+    pub fn draw_image_filtered(&mut self, bitmap: &Bitmap, dst: Rect, filter: FilterMode) {
+        if bitmap.width == 0 || bitmap.height == 0 || dst.is_empty() {
+            return;
+        }
+        let t = self.transform();
+        if t.is_translation() {
+            // Resample once per size, then copy pixels straight across. Edges are
+            // rounded rather than the size, so adjacent images never leave gaps.
+            let (x0, y0) = ((dst.x + t.e).round() as i32, (dst.y + t.f).round() as i32);
+            let (x1, y1) = (
+                (dst.max_x() + t.e).round() as i32,
+                (dst.max_y() + t.f).round() as i32,
+            );
+            if x1 > x0 && y1 > y0 {
+                let scaled = bitmap.scaled((x1 - x0) as u32, (y1 - y0) as u32, filter);
+                self.blit(&scaled, x0, y0);
+            }
+            return;
+        }
+        let inv = match t.inverse() {
+            Some(inv) => inv,
+            None => return,
+        };
+        let area = match t.map_rect(dst).round_out().intersect(&self.clip_rect()) {
+            Some(area) => area,
+            None => return,
+        };
+        let sx = bitmap.width as f32 / dst.width;
+        let sy = bitmap.height as f32 / dst.height;
+        for y in area.y as i32..area.max_y() as i32 {
+            for x in area.x as i32..area.max_x() as i32 {
+                let p = inv.apply(Point::new(x as f32 + 0.5, y as f32 + 0.5));
+                let (u, v) = ((p.x - dst.x) * sx, (p.y - dst.y) * sy);
+                if u < 0.0 || v < 0.0 || u >= bitmap.width as f32 || v >= bitmap.height as f32 {
+                    continue;
+                }
+                self.composite_pixel(x, y, bitmap.sample(u, v, filter));
+            }
+        }
+    }
+
+    /// Composite `bitmap` unscaled with its top-left corner at frame pixel (x, y)
+    pub fn blit(&mut self, bitmap: &Bitmap, x: i32, y: i32) {
+        let area = Rect::new(
+            x as f32,
+            y as f32,
+            bitmap.width as f32,
+            bitmap.height as f32,
+        );
+        let area = match area.intersect(&self.clip_rect()) {
+            Some(area) => area,
+            None => return,
+        };
+        for py in area.y as i32..area.max_y() as i32 {
+            for px in area.x as i32..area.max_x() as i32 {
+                self.composite_pixel(px, py, bitmap.pixel((px - x) as u32, (py - y) as u32));
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Pixel compositing on premultiplied RGBA
+
+/// How drawn pixels combine with the pixels below them
+///
+/// Only modes that leave the destination alone where nothing is drawn are
+/// offered, so a shape never affects pixels outside of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Paint over what is already there
+    #[default]
+    SourceOver,
+    /// Paint behind what is already there
+    DestinationOver,
+    /// Paint only where something was already drawn
+    SourceAtop,
+    /// Erase where the source is opaque
+    DestinationOut,
+    /// Keep only where exactly one of source and destination is drawn
+    Xor,
+    /// Add colors together, for glows and light effects
+    Plus,
+    /// Darken by multiplying colors, for shading and heatmaps
+    Multiply,
+    /// Lighten by inverting, multiplying and inverting again, for highlights
+    Screen,
+    /// Keep the darker of both colors
+    Darken,
+    /// Keep the lighter of both colors
+    Lighten,
+}
+
+/// Multiply two 8-bit channel values, treating 255 as 1.0
+#[inline]
+pub fn mul_255(a: u8, b: u8) -> u8 {
+    let t = a as u32 * b as u32 + 128;
+    ((t + (t >> 8)) >> 8) as u8
+}
+
+/// Convert a straight-alpha color to premultiplied alpha
+#[inline]
+pub fn premultiply(c: [u8; 4]) -> [u8; 4] {
+    [
+        mul_255(c[0], c[3]),
+        mul_255(c[1], c[3]),
+        mul_255(c[2], c[3]),
+        c[3],
+    ]
+}
+
+/// Convert a premultiplied color back to straight alpha
+#[inline]
+pub fn unpremultiply(c: [u8; 4]) -> [u8; 4] {
+    match c[3] {
+        0 => [0, 0, 0, 0],
+        255 => c,
+        a => {
+            let un = |v: u8| ((v as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+            [un(c[0]), un(c[1]), un(c[2]), a]
+        }
+    }
+}
+
+/// Scale every channel of a premultiplied color, e.g. by coverage or opacity
+#[inline]
+pub fn scale(c: [u8; 4], factor: f32) -> [u8; 4] {
+    if factor >= 1.0 {
+        return c;
+    }
+    let f = (factor.max(0.0) * 255.0).round() as u8;
+    [
+        mul_255(c[0], f),
+        mul_255(c[1], f),
+        mul_255(c[2], f),
+        mul_255(c[3], f),
+    ]
+}
+
+/// Porter-Duff source-over: `dst = src + dst * (1 - src.a)`
+#[inline]
+pub fn source_over(dst: &mut [u8], src: [u8; 4]) {
+    match src[3] {
+        0 if src == [0, 0, 0, 0] => {}
+        255 => dst[..4].copy_from_slice(&src),
+        a => {
+            let inv = 255 - a;
+            for i in 0..4 {
+                dst[i] = src[i].saturating_add(mul_255(dst[i], inv));
+            }
+        }
+    }
+}
+
+/// Composite `src` onto `dst` with `mode`, both premultiplied
+///
+/// Every mode leaves `dst` unchanged for a fully transparent `src`, so
+/// coverage can be folded into `src` beforehand just as for source-over.
+#[inline]
+pub fn composite(dst: &mut [u8], src: [u8; 4], mode: BlendMode) {
+    if mode == BlendMode::SourceOver {
+        return source_over(dst, src);
+    }
+    if src == [0, 0, 0, 0] {
+        return;
+    }
+    let (sa, da) = (src[3] as f32 / 255.0, dst[3] as f32 / 255.0);
+    for i in 0..4 {
+        let (s, d) = (src[i] as f32 / 255.0, dst[i] as f32 / 255.0);
+        // Applied to alpha as well, the same formulas give the right coverage
+        let v = match mode {
+            BlendMode::SourceOver => s + d * (1.0 - sa),
+            BlendMode::DestinationOver => s * (1.0 - da) + d,
+            BlendMode::SourceAtop => s * da + d * (1.0 - sa),
+            BlendMode::DestinationOut => d * (1.0 - sa),
+            BlendMode::Xor => s * (1.0 - da) + d * (1.0 - sa),
+            BlendMode::Plus => s + d,
+            BlendMode::Multiply => s * (1.0 - da) + d * (1.0 - sa) + s * d,
+            BlendMode::Screen => s + d - s * d,
+            BlendMode::Darken => s + d - (s * da).max(d * sa),
+            BlendMode::Lighten => s + d - (s * da).min(d * sa),
+        };
+        dst[i] = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
@@ -0,0 +1,248 @@
+use crate::bitmap::{Bitmap, FilterMode};
+use crate::blend;
+use crate::color::Color;
+use crate::geometry::Point;
+
+/// A color position along a gradient, `offset` in `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub color: Color,
+}
+
+/// Gradient along the line from `start` to `end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearGradient {
+    pub start: Point,
+    pub end: Point,
+    pub stops: Vec<ColorStop>,
+}
+
+impl LinearGradient {
+    pub fn new(start: Point, end: Point) -> Self {
+        Self {
+            start,
+            end,
+            stops: Vec::new(),
+        }
+    }
+
+    pub fn stop(mut self, offset: f32, color: impl Into<Color>) -> Self {
+        insert_stop(&mut self.stops, offset, color.into());
+        self
+    }
+}
+
+/// Gradient radiating from `center` out to `radius`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialGradient {
+    pub center: Point,
+    pub radius: f32,
+    pub stops: Vec<ColorStop>,
+}
+
+impl RadialGradient {
+    pub fn new(center: Point, radius: f32) -> Self {
+        Self {
+            center,
+            radius,
+            stops: Vec::new(),
+        }
+    }
+
+    pub fn stop(mut self, offset: f32, color: impl Into<Color>) -> Self {
+        insert_stop(&mut self.stops, offset, color.into());
+        self
+    }
+}
+
+/// Which directions a pattern repeats in, transparent beyond a single tile otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Tiling {
+    #[default]
+    Repeat,
+    RepeatX,
+    RepeatY,
+}
+
+/// A bitmap tiled across the shape, e.g. a texture or checkerboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub bitmap: Bitmap,
+    /// Local position of the top-left corner of one tile
+    pub offset: Point,
+    /// Size of a bitmap pixel in local units
+    pub scale: f32,
+    pub tiling: Tiling,
+    pub filter: FilterMode,
+}
+
+impl Pattern {
+    pub fn new(bitmap: Bitmap) -> Self {
+        Self {
+            bitmap,
+            offset: Point::ZERO,
+            scale: 1.0,
+            tiling: Tiling::Repeat,
+            filter: FilterMode::Bilinear,
+        }
+    }
+
+    /// Alternating squares of `cell` units, as drawn behind transparent content
+    pub fn checkerboard(cell: f32, a: impl Into<Color>, b: impl Into<Color>) -> Self {
+        let (a, b) = (a.into().premultiplied(), b.into().premultiplied());
+        let pixels = [a, b, b, a].concat();
+        Self::new(Bitmap::from_premultiplied(2, 2, pixels))
+            .scale(cell)
+            .filter(FilterMode::Nearest)
+    }
+
+    pub fn offset(mut self, offset: Point) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn tiling(mut self, tiling: Tiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    pub fn filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    fn color_at(&self, p: Point) -> Color {
+        let (w, h) = (self.bitmap.width() as f32, self.bitmap.height() as f32);
+        if w == 0.0 || h == 0.0 || self.scale <= 0.0 {
+            return Color::TRANSPARENT;
+        }
+        let u = (p.x - self.offset.x) / self.scale;
+        let v = (p.y - self.offset.y) / self.scale;
+        let (repeat_x, repeat_y) = match self.tiling {
+            Tiling::Repeat => (true, true),
+            Tiling::RepeatX => (true, false),
+            Tiling::RepeatY => (false, true),
+        };
+        if (!repeat_x && !(0.0..w).contains(&u)) || (!repeat_y && !(0.0..h).contains(&v)) {
+            return Color::TRANSPARENT;
+        }
+        let u = if repeat_x { u.rem_euclid(w) } else { u };
+        let v = if repeat_y { v.rem_euclid(h) } else { v };
+        blend::unpremultiply(self.bitmap.sample(u, v, self.filter)).into()
+    }
+}
+
+/// How a shape is filled
+#[derive(Debug, Clone, PartialEq)]
+pub enum Brush {
+    Solid(Color),
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+    Pattern(Pattern),
+}
+
+impl Brush {
+    /// Color of the brush at a frame position
+    pub fn color_at(&self, p: Point) -> Color {
+        match self {
+            Brush::Solid(color) => *color,
+            Brush::Linear(g) => {
+                let (dx, dy) = (g.end.x - g.start.x, g.end.y - g.start.y);
+                let len_sq = dx * dx + dy * dy;
+                let t = if len_sq > 0.0 {
+                    ((p.x - g.start.x) * dx + (p.y - g.start.y) * dy) / len_sq
+                } else {
+                    0.0
+                };
+                sample_stops(&g.stops, t)
+            }
+            Brush::Radial(g) => {
+                let t = if g.radius > 0.0 {
+                    p.distance(g.center) / g.radius
+                } else {
+                    0.0
+                };
+                sample_stops(&g.stops, t)
+            }
+            Brush::Pattern(pattern) => pattern.color_at(p),
+        }
+    }
+}
+
+impl From<Color> for Brush {
+    fn from(color: Color) -> Self {
+        Brush::Solid(color)
+    }
+}
+
+impl From<[u8; 4]> for Brush {
+    fn from(color: [u8; 4]) -> Self {
+        Brush::Solid(color.into())
+    }
+}
+
+impl From<LinearGradient> for Brush {
+    fn from(g: LinearGradient) -> Self {
+        Brush::Linear(g)
+    }
+}
+
+impl From<RadialGradient> for Brush {
+    fn from(g: RadialGradient) -> Self {
+        Brush::Radial(g)
+    }
+}
+
+impl From<Pattern> for Brush {
+    fn from(pattern: Pattern) -> Self {
+        Brush::Pattern(pattern)
+    }
+}
+
+impl From<&Brush> for Brush {
+    fn from(brush: &Brush) -> Self {
+        brush.clone()
+    }
+}
+
+fn insert_stop(stops: &mut Vec<ColorStop>, offset: f32, color: Color) {
+    let offset = offset.clamp(0.0, 1.0);
+    let idx = stops
+        .iter()
+        .position(|s| s.offset > offset)
+        .unwrap_or(stops.len());
+    stops.insert(idx, ColorStop { offset, color });
+}
+
+/// Interpolate the stops at `t`, padding with the end colors outside the range
+fn sample_stops(stops: &[ColorStop], t: f32) -> Color {
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Color::TRANSPARENT,
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+    if t >= last.offset {
+        return last.color;
+    }
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if t <= b.offset {
+            let span = b.offset - a.offset;
+            let f = if span > 0.0 {
+                (t - a.offset) / span
+            } else {
+                1.0
+            };
+            return a.color.lerp(b.color, f);
+        }
+    }
+    last.color
+}
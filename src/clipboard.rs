@@ -0,0 +1,39 @@
+use crate::error::Error;
+
+/// The system clipboard, shared through `Context::clipboard`
+///
+/// It is opened on first use. Only text is supported for now.
+#[derive(Default)]
+pub struct Clipboard {
+    // Kept open for the whole run: on X11 and Wayland copied text is served
+    // from this process and would vanish with it
+    system: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    fn system(&mut self) -> Result<&mut arboard::Clipboard, Error> {
+        let system = match self.system.take() {
+            Some(system) => system,
+            None => arboard::Clipboard::new()?,
+        };
+        Ok(self.system.insert(system))
+    }
+
+    /// Text on the clipboard, `None` when it holds none, e.g. only an image
+    pub fn text(&mut self) -> Result<Option<String>, Error> {
+        match self.system()?.get_text() {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace what is on the clipboard with `text`
+    pub fn set_text(&mut self, text: impl Into<String>) -> Result<(), Error> {
+        Ok(self.system()?.set_text(text.into())?)
+    }
+
+    pub fn clear(&mut self) -> Result<(), Error> {
+        Ok(self.system()?.clear()?)
+    }
+}
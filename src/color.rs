@@ -0,0 +1,212 @@
+use crate::blend;
+
+/// A straight-alpha sRGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 255)
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Parse `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` being optional
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.is_ascii() {
+            return None;
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        match hex.len() {
+            3 | 4 => {
+                let mut c = [255; 4];
+                for (i, v) in c.iter_mut().enumerate().take(hex.len()) {
+                    *v = digit(i)? * 17;
+                }
+                Some(c.into())
+            }
+            6 | 8 => {
+                let mut c = [255; 4];
+                for (i, v) in c.iter_mut().enumerate().take(hex.len() / 2) {
+                    *v = byte(i * 2)?;
+                }
+                Some(c.into())
+            }
+            _ => None,
+        }
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` when not opaque
+    pub fn to_hex(self) -> String {
+        if self.a == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// Opaque color from hue in degrees, saturation and lightness in `0.0..=1.0`
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Self::from_chroma(h, c, l - c / 2.0)
+    }
+
+    /// Hue in degrees, saturation and lightness in `0.0..=1.0`
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue();
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (h, s, l)
+    }
+
+    /// Opaque color from hue in degrees, saturation and value in `0.0..=1.0`
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let c = v * s;
+        Self::from_chroma(h, c, v - c)
+    }
+
+    /// Hue in degrees, saturation and value in `0.0..=1.0`
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue();
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (h, s, max)
+    }
+
+    pub fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Raise the HSL lightness by `amount`, keeping alpha
+    pub fn lighten(self, amount: f32) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, l + amount).with_alpha(self.a)
+    }
+
+    /// Lower the HSL lightness by `amount`, keeping alpha
+    pub fn darken(self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Mix towards `other` by `t`, in linear light so midpoints do not turn muddy
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let (a, b) = (self.to_linear(), other.to_linear());
+        // Mix premultiplied so a fade to transparent does not pick up its color
+        let alpha = a[3] + (b[3] - a[3]) * t;
+        let mix = |i: usize| a[i] * a[3] + (b[i] * b[3] - a[i] * a[3]) * t;
+        let un = |v: f32| if alpha > 0.0 { v / alpha } else { 0.0 };
+        Self::from_linear([un(mix(0)), un(mix(1)), un(mix(2)), alpha])
+    }
+
+    /// Channels in linear light, `0.0..=1.0`, alpha unchanged
+    pub fn to_linear(self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a as f32 / 255.0,
+        ]
+    }
+
+    pub fn from_linear([r, g, b, a]: [f32; 4]) -> Self {
+        Self::rgba(
+            linear_to_srgb(r),
+            linear_to_srgb(g),
+            linear_to_srgb(b),
+            (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+
+    /// Premultiplied bytes, as stored in the frame
+    pub fn premultiplied(self) -> [u8; 4] {
+        blend::premultiply(self.into())
+    }
+
+    fn from_chroma(h: f32, c: f32, m: f32) -> Self {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let byte = |v: f32| ((v + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::rgb(byte(r), byte(g), byte(b))
+    }
+
+    /// Hue in degrees plus the largest and smallest channel in `0.0..=1.0`
+    fn hue(self) -> (f32, f32, f32) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|v| v as f32 / 255.0);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let d = max - min;
+        let h = if d == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / d).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / d + 2.0)
+        } else {
+            60.0 * ((r - g) / d + 4.0)
+        };
+        (h, max, min)
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    fn from([r, g, b, a]: [u8; 4]) -> Self {
+        Self::rgba(r, g, b, a)
+    }
+}
+
+impl From<[u8; 3]> for Color {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<Color> for [u8; 4] {
+    fn from(c: Color) -> Self {
+        [c.r, c.g, c.b, c.a]
+    }
+}
+
+fn srgb_to_linear(v: u8) -> f32 {
+    let v = v as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let s = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (s * 255.0).round() as u8
+}
@@ -0,0 +1,125 @@
+use winit::window::CursorIcon as WinitIcon;
+
+use crate::bitmap::Bitmap;
+use crate::draw_list::{DrawList, Layer};
+use crate::geometry::{Point, Rect};
+
+/// Mouse cursor shown over a widget, set with `Context::set_cursor`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+    /// One of the platform's cursors
+    Icon(CursorIcon),
+    Custom(CustomCursor),
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self::Icon(CursorIcon::Default)
+    }
+}
+
+impl From<CursorIcon> for Cursor {
+    fn from(icon: CursorIcon) -> Self {
+        Self::Icon(icon)
+    }
+}
+
+impl From<CustomCursor> for Cursor {
+    fn from(cursor: CustomCursor) -> Self {
+        Self::Custom(cursor)
+    }
+}
+
+/// A cursor drawn from an image, e.g. a brush outline in a drawing tool
+///
+/// The window has no way to take an image as its cursor, so the system cursor
+/// is hidden and the image drawn into the frame above everything else. Moving
+/// it repaints the area it covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomCursor {
+    pub image: Bitmap,
+    /// Pixel of the image at the pointer position, e.g. the tip of an arrow
+    pub hotspot: Point,
+}
+
+impl CustomCursor {
+    pub fn new(image: Bitmap, hotspot: Point) -> Self {
+        Self { image, hotspot }
+    }
+
+    /// Area covered with the pointer at `p`
+    pub(crate) fn rect(&self, p: Point) -> Rect {
+        Rect::new(
+            p.x - self.hotspot.x,
+            p.y - self.hotspot.y,
+            self.image.width() as f32,
+            self.image.height() as f32,
+        )
+    }
+
+    pub(crate) fn record(&self, p: Point, list: &mut DrawList) {
+        list.layer(Layer::Cursor)
+            .draw_image(&self.image, self.rect(p));
+    }
+}
+
+/// One of the platform's cursors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorIcon {
+    /// The platform's arrow
+    #[default]
+    Default,
+    /// A pointing hand, over links and buttons
+    Pointer,
+    /// An I-beam, over editable or selectable text
+    Text,
+    Crosshair,
+    Move,
+    Grab,
+    Grabbing,
+    Wait,
+    /// Busy, but still responding to input
+    Progress,
+    Help,
+    NotAllowed,
+    /// Resizing left or right, e.g. over a vertical split divider
+    ResizeHorizontal,
+    /// Resizing up or down, e.g. over a horizontal split divider
+    ResizeVertical,
+    /// Resizing towards the top right or bottom left corner
+    ResizeNeSw,
+    /// Resizing towards the top left or bottom right corner
+    ResizeNwSe,
+    /// Resizing a table column
+    ResizeColumn,
+    /// Resizing a table row
+    ResizeRow,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl From<CursorIcon> for WinitIcon {
+    fn from(icon: CursorIcon) -> Self {
+        match icon {
+            CursorIcon::Default => WinitIcon::Default,
+            CursorIcon::Pointer => WinitIcon::Hand,
+            CursorIcon::Text => WinitIcon::Text,
+            CursorIcon::Crosshair => WinitIcon::Crosshair,
+            CursorIcon::Move => WinitIcon::Move,
+            CursorIcon::Grab => WinitIcon::Grab,
+            CursorIcon::Grabbing => WinitIcon::Grabbing,
+            CursorIcon::Wait => WinitIcon::Wait,
+            CursorIcon::Progress => WinitIcon::Progress,
+            CursorIcon::Help => WinitIcon::Help,
+            CursorIcon::NotAllowed => WinitIcon::NotAllowed,
+            CursorIcon::ResizeHorizontal => WinitIcon::EwResize,
+            CursorIcon::ResizeVertical => WinitIcon::NsResize,
+            CursorIcon::ResizeNeSw => WinitIcon::NeswResize,
+            CursorIcon::ResizeNwSe => WinitIcon::NwseResize,
+            CursorIcon::ResizeColumn => WinitIcon::ColResize,
+            CursorIcon::ResizeRow => WinitIcon::RowResize,
+            CursorIcon::ZoomIn => WinitIcon::ZoomIn,
+            CursorIcon::ZoomOut => WinitIcon::ZoomOut,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::geometry::Rect;
+
+/// Above this many separate regions they are merged into one bounding box
+const MAX_REGIONS: usize = 8;
+
+/// Collects the parts of the frame that need repainting
+#[derive(Debug, Clone, Default)]
+pub struct DamageTracker {
+    regions: Vec<Rect>,
+    bounds: Rect,
+}
+
+impl DamageTracker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            regions: Vec::new(),
+            bounds: Rect::new(0.0, 0.0, width as f32, height as f32),
+        }
+    }
+
+    /// Change the frame size; everything becomes damaged
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+        self.invalidate_all();
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.regions = vec![self.bounds];
+    }
+
+    /// Mark `rect` (in frame pixels) as needing a repaint
+    pub fn add(&mut self, rect: Rect) {
+        let mut rect = match rect.round_out().intersect(&self.bounds) {
+            Some(rect) => rect,
+            None => return,
+        };
+        // Fold in every region the new one touches, repeating as the union grows
+        while let Some(i) = self
+            .regions
+            .iter()
+            .position(|r| r.intersect(&rect.inset(-1.0)).is_some())
+        {
+            rect = rect.union(&self.regions.swap_remove(i));
+        }
+        self.regions.push(rect);
+        if self.regions.len() > MAX_REGIONS {
+            let all = self
+                .regions
+                .drain(..)
+                .fold(Rect::default(), |acc, r| acc.union(&r));
+            self.regions.push(all);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn regions(&self) -> &[Rect] {
+        &self.regions
+    }
+
+    /// Take the damaged regions, leaving the tracker clean
+    pub fn take(&mut self) -> Vec<Rect> {
+        std::mem::take(&mut self.regions)
+    }
+}
@@ -0,0 +1,111 @@
+use std::any::Any;
+
+use crate::bitmap::Bitmap;
+use crate::draw_list::{DrawList, Layer};
+use crate::geometry::{Point, Rect};
+use crate::widget::WidgetId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DragEventKind {
+    /// The drag moved over the widget
+    Enter,
+    /// The drag moved while over the widget
+    Over,
+    /// The drag moved off the widget or was cancelled
+    Leave,
+    /// The button was released over the widget; the drag ends after this
+    Drop,
+}
+
+/// A drag passing over a widget, delivered through `Application::drag_event`
+///
+/// The dragged value is read with `Context::drag_payload`. Files dragged in
+/// from outside the application arrive the same way, as a `Vec<PathBuf>`
+/// also returned by `Context::dragged_files`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragEvent {
+    pub kind: DragEventKind,
+    /// Innermost widget under the pointer
+    pub target: WidgetId,
+    /// Pointer position in frame pixels
+    pub position: Point,
+}
+
+/// A drag in progress, started with `Context::start_drag`
+pub(crate) struct Drag {
+    payload: Box<dyn Any>,
+    preview: Option<Bitmap>,
+    position: Option<Point>,
+    target: Option<WidgetId>,
+}
+
+impl Drag {
+    pub(crate) fn new(
+        payload: Box<dyn Any>,
+        preview: Option<Bitmap>,
+        position: Option<Point>,
+    ) -> Self {
+        Self {
+            payload,
+            preview,
+            position,
+            target: None,
+        }
+    }
+
+    pub(crate) fn payload(&self) -> &dyn Any {
+        self.payload.as_ref()
+    }
+
+    pub(crate) fn payload_mut(&mut self) -> &mut dyn Any {
+        self.payload.as_mut()
+    }
+
+    pub(crate) fn set_payload(&mut self, payload: Box<dyn Any>) {
+        self.payload = payload;
+    }
+
+    /// Area covered by the preview, centered on the pointer
+    pub(crate) fn preview_rect(&self) -> Option<Rect> {
+        let (preview, p) = (self.preview.as_ref()?, self.position?);
+        let (w, h) = (preview.width() as f32, preview.height() as f32);
+        Some(Rect::new(p.x - w / 2.0, p.y - h / 2.0, w, h))
+    }
+
+    pub(crate) fn record_preview(&self, list: &mut DrawList) {
+        if let (Some(preview), Some(rect)) = (&self.preview, self.preview_rect()) {
+            list.layer(Layer::DragPreview).draw_image(preview, rect);
+        }
+    }
+
+    fn event(&self, kind: DragEventKind, target: WidgetId) -> DragEvent {
+        DragEvent {
+            kind,
+            target,
+            position: self.position.unwrap_or_default(),
+        }
+    }
+
+    /// Follow the pointer to `position` over `target`
+    pub(crate) fn moved(&mut self, position: Point, target: Option<WidgetId>) -> Vec<DragEvent> {
+        self.position = Some(position);
+        let mut events = Vec::new();
+        if self.target != target {
+            events.extend(self.leave());
+            events.extend(target.map(|id| self.event(DragEventKind::Enter, id)));
+            self.target = target;
+        }
+        events.extend(target.map(|id| self.event(DragEventKind::Over, id)));
+        events
+    }
+
+    /// Leave the current target, as when the drag is cancelled
+    pub(crate) fn leave(&mut self) -> Option<DragEvent> {
+        let target = self.target.take()?;
+        Some(self.event(DragEventKind::Leave, target))
+    }
+
+    pub(crate) fn drop_event(&self) -> Option<DragEvent> {
+        Some(self.event(DragEventKind::Drop, self.target?))
+    }
+}
@@ -0,0 +1,608 @@
+use std::collections::BTreeMap;
+
+use crate::atlas::{Region, SpriteSheet};
+use crate::bitmap::{Bitmap, FilterMode};
+use crate::blend::BlendMode;
+use crate::brush::Brush;
+use crate::color::Color;
+use crate::damage::DamageTracker;
+use crate::effects::{self, Shadow};
+use crate::font::Font;
+use crate::geometry::{Point, Rect};
+use crate::nine_patch::NinePatch;
+use crate::painter::Painter;
+use crate::path::Path;
+use crate::stroke::Stroke;
+#[cfg(feature = "svg")]
+use crate::svg::Svg;
+use crate::target::RenderTarget;
+use crate::text::{self, TextLayout};
+use crate::transform::Transform;
+
+/// Paint order of a group of draw commands, lowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Layer {
+    Background,
+    #[default]
+    Content,
+    Popup,
+    Modal,
+    Tooltip,
+    DragPreview,
+    /// A custom mouse cursor drawn into the frame
+    Cursor,
+}
+
+/// A recorded painter operation
+#[derive(Clone)]
+pub enum DrawCmd {
+    FillRect {
+        rect: Rect,
+        brush: Brush,
+    },
+    FillRoundedRect {
+        rect: Rect,
+        radius: f32,
+        brush: Brush,
+    },
+    FillPath {
+        path: Path,
+        brush: Brush,
+    },
+    StrokePath {
+        path: Path,
+        stroke: Stroke,
+        brush: Brush,
+    },
+    Shadow {
+        rect: Rect,
+        radius: f32,
+        shadow: Shadow,
+    },
+    BlurRegion {
+        rect: Rect,
+        blur: f32,
+    },
+    BackdropBlur {
+        rect: Rect,
+        radius: f32,
+        blur: f32,
+        tint: Color,
+    },
+    Image {
+        bitmap: Bitmap,
+        dst: Rect,
+        filter: FilterMode,
+        opacity: f32,
+    },
+    ImageRegion {
+        bitmap: Bitmap,
+        src: Region,
+        dst: Rect,
+        filter: FilterMode,
+    },
+    NinePatch {
+        patch: NinePatch,
+        dst: Rect,
+    },
+    #[cfg(feature = "svg")]
+    Svg {
+        svg: Svg,
+        dst: Rect,
+    },
+    Text {
+        text: String,
+        font: Font,
+        size: f32,
+        center: Point,
+        color: Color,
+    },
+    /// Wrapped text aligned within `rect`
+    TextLayout {
+        layout: TextLayout,
+        rect: Rect,
+        color: Color,
+    },
+    PushClip(Rect),
+    PopClip,
+    PushTransform(Transform),
+    PopTransform,
+    /// Start a group composited as one picture at the given opacity
+    PushOpacity(f32),
+    PopOpacity,
+    PushBlendMode(BlendMode),
+    PopBlendMode,
+}
+
+impl DrawCmd {
+    /// Local-coordinate area the command can touch, `None` for state changes
+    pub fn bounds(&self) -> Option<Rect> {
+        match self {
+            DrawCmd::FillRect { rect, .. } | DrawCmd::FillRoundedRect { rect, .. } => Some(*rect),
+            DrawCmd::FillPath { path, .. } => Some(path.bounds()),
+            DrawCmd::StrokePath { path, stroke, .. } => {
+                Some(path.bounds().inset(-(stroke.extent() + 1.0)))
+            }
+            DrawCmd::Shadow { rect, shadow, .. } => {
+                let pad = effects::blur_extent(shadow.blur) + 1.0;
+                Some(rect.translate(shadow.offset.x, shadow.offset.y).inset(-pad))
+            }
+            DrawCmd::BlurRegion { rect, .. } | DrawCmd::BackdropBlur { rect, .. } => Some(*rect),
+            DrawCmd::Image { dst, .. }
+            | DrawCmd::ImageRegion { dst, .. }
+            | DrawCmd::NinePatch { dst, .. } => Some(*dst),
+            #[cfg(feature = "svg")]
+            DrawCmd::Svg { dst, .. } => Some(*dst),
+            DrawCmd::Text {
+                text,
+                font,
+                size,
+                center,
+                ..
+            } => {
+                let width = text::text_width(font, *size, text);
+                Some(Rect::new(
+                    center.x - width / 2.0 - 2.0,
+                    center.y - size,
+                    width + 4.0,
+                    size * 3.0,
+                ))
+            }
+            DrawCmd::TextLayout { layout, rect, .. } => {
+                Some(layout.block_rect(*rect).inset(-layout.overhang()))
+            }
+            DrawCmd::PushClip(_)
+            | DrawCmd::PopClip
+            | DrawCmd::PushTransform(_)
+            | DrawCmd::PopTransform
+            | DrawCmd::PushOpacity(_)
+            | DrawCmd::PopOpacity
+            | DrawCmd::PushBlendMode(_)
+            | DrawCmd::PopBlendMode => None,
+        }
+    }
+
+    pub(crate) fn apply(&self, painter: &mut Painter) {
+        match self {
+            DrawCmd::FillRect { rect, brush } => painter.fill_rect(*rect, brush),
+            DrawCmd::FillRoundedRect {
+                rect,
+                radius,
+                brush,
+            } => painter.fill_rounded_rect(*rect, *radius, brush),
+            DrawCmd::FillPath { path, brush } => painter.fill_path(path, brush),
+            DrawCmd::StrokePath {
+                path,
+                stroke,
+                brush,
+            } => painter.stroke_path(path, stroke, brush),
+            DrawCmd::Shadow {
+                rect,
+                radius,
+                shadow,
+            } => painter.draw_shadow(*rect, *radius, shadow),
+            DrawCmd::BlurRegion { rect, blur } => painter.blur_region(*rect, *blur),
+            DrawCmd::BackdropBlur {
+                rect,
+                radius,
+                blur,
+                tint,
+            } => painter.backdrop_blur(*rect, *radius, *blur, *tint),
+            DrawCmd::Image {
+                bitmap,
+                dst,
+                filter,
+                opacity,
+            } => painter.with_opacity(*opacity, |p| p.draw_image_filtered(bitmap, *dst, *filter)),
+            DrawCmd::ImageRegion {
+                bitmap,
+                src,
+                dst,
+                filter,
+            } => painter.draw_image_region(bitmap, *src, *dst, *filter),
+            DrawCmd::NinePatch { patch, dst } => painter.draw_nine_patch(patch, *dst),
+            #[cfg(feature = "svg")]
+            DrawCmd::Svg { svg, dst } => painter.draw_svg(svg, *dst),
+            DrawCmd::Text {
+                text,
+                font,
+                size,
+                center,
+                color,
+            } => painter.draw_text(text, font, *size, *center, *color),
+            DrawCmd::TextLayout {
+                layout,
+                rect,
+                color,
+            } => painter.draw_text_in(layout, *rect, *color),
+            DrawCmd::PushClip(rect) => painter.push_clip(*rect),
+            DrawCmd::PopClip => painter.pop_clip(),
+            DrawCmd::PushTransform(t) => painter.push_transform(*t),
+            DrawCmd::PopTransform => painter.pop_transform(),
+            DrawCmd::PushOpacity(opacity) => painter.push_group(*opacity),
+            DrawCmd::PopOpacity => painter.pop_group(),
+            DrawCmd::PushBlendMode(mode) => painter.push_blend_mode(*mode),
+            DrawCmd::PopBlendMode => painter.pop_blend_mode(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct LayerCmds {
+    clip: Option<Rect>,
+    cmds: Vec<DrawCmd>,
+}
+
+/// Draw commands grouped into layers, so overlays paint above content
+/// regardless of the order they were recorded in
+#[derive(Clone, Default)]
+pub struct DrawList {
+    layers: BTreeMap<Layer, LayerCmds>,
+}
+
+impl DrawList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record commands into `layer`
+    pub fn layer(&mut self, layer: Layer) -> LayerRecorder<'_> {
+        LayerRecorder {
+            cmds: &mut self.layers.entry(layer).or_default().cmds,
+        }
+    }
+
+    /// Clip every command of `layer` to `rect`
+    pub fn set_layer_clip(&mut self, layer: Layer, rect: Option<Rect>) {
+        self.layers.entry(layer).or_default().clip = rect;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.values().all(|l| l.cmds.is_empty())
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Grow the damage to cover every blur touching it
+    ///
+    /// Blurs read the pixels around them, so they must be repainted as a whole
+    /// or the edge of the damaged area would get blurred twice.
+    pub fn expand_damage(&self, damage: &mut DamageTracker) {
+        for layer in self.layers.values() {
+            let mut transforms = vec![Transform::IDENTITY];
+            for cmd in &layer.cmds {
+                let t = *transforms.last().unwrap_or(&Transform::IDENTITY);
+                match cmd {
+                    DrawCmd::PushTransform(local) => transforms.push(local.then(t)),
+                    DrawCmd::PopTransform if transforms.len() > 1 => {
+                        transforms.pop();
+                    }
+                    DrawCmd::BlurRegion { rect, blur }
+                    | DrawCmd::BackdropBlur { rect, blur, .. } => {
+                        let pad = effects::blur_extent(*blur);
+                        let area = t.map_rect(*rect).inset(-pad);
+                        if damage
+                            .regions()
+                            .iter()
+                            .any(|r| r.intersect(&area).is_some())
+                        {
+                            damage.add(area);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Walk every command in paint order
+    ///
+    /// Each layer's clip is pushed around its commands, unmatched pops are dropped
+    /// and clips, transforms, opacity groups and blend modes left open are
+    /// closed, so nothing leaks into the next layer.
+    pub fn visit(&self, mut f: impl FnMut(&DrawCmd)) {
+        for layer in self.layers.values() {
+            let (mut clips, mut transforms, mut groups, mut modes) = (0usize, 0, 0, 0);
+            if let Some(rect) = layer.clip {
+                f(&DrawCmd::PushClip(rect));
+            }
+            for cmd in &layer.cmds {
+                match cmd {
+                    DrawCmd::PushClip(_) => clips += 1,
+                    DrawCmd::PopClip if clips == 0 => continue,
+                    DrawCmd::PopClip => clips -= 1,
+                    DrawCmd::PushTransform(_) => transforms += 1,
+                    DrawCmd::PopTransform if transforms == 0 => continue,
+                    DrawCmd::PopTransform => transforms -= 1,
+                    DrawCmd::PushOpacity(_) => groups += 1,
+                    DrawCmd::PopOpacity if groups == 0 => continue,
+                    DrawCmd::PopOpacity => groups -= 1,
+                    DrawCmd::PushBlendMode(_) => modes += 1,
+                    DrawCmd::PopBlendMode if modes == 0 => continue,
+                    DrawCmd::PopBlendMode => modes -= 1,
+                    _ => {}
+                }
+                f(cmd);
+            }
+            for _ in 0..modes {
+                f(&DrawCmd::PopBlendMode);
+            }
+            for _ in 0..groups {
+                f(&DrawCmd::PopOpacity);
+            }
+            for _ in 0..clips {
+                f(&DrawCmd::PopClip);
+            }
+            for _ in 0..transforms {
+                f(&DrawCmd::PopTransform);
+            }
+            if layer.clip.is_some() {
+                f(&DrawCmd::PopClip);
+            }
+        }
+    }
+
+    /// Paint all layers in order
+    pub fn replay(&self, painter: &mut Painter) {
+        self.visit(|cmd| {
+            // Skip work that cannot reach the clipped area, e.g. outside the damaged region
+            if let Some(bounds) = cmd.bounds()
+                && painter
+                    .transform()
+                    .map_rect(bounds)
+                    .intersect(&painter.clip_rect())
+                    .is_none()
+            {
+                return;
+            }
+            cmd.apply(painter);
+        });
+    }
+}
+
+/// Records commands into a single layer of a `DrawList`
+pub struct LayerRecorder<'a> {
+    cmds: &'a mut Vec<DrawCmd>,
+}
+
+impl LayerRecorder<'_> {
+    pub fn push(&mut self, cmd: DrawCmd) -> &mut Self {
+        self.cmds.push(cmd);
+        self
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, brush: impl Into<Brush>) -> &mut Self {
+        self.push(DrawCmd::FillRect {
+            rect,
+            brush: brush.into(),
+        })
+    }
+
+    pub fn fill_rounded_rect(
+        &mut self,
+        rect: Rect,
+        radius: f32,
+        brush: impl Into<Brush>,
+    ) -> &mut Self {
+        self.push(DrawCmd::FillRoundedRect {
+            rect,
+            radius,
+            brush: brush.into(),
+        })
+    }
+
+    pub fn fill_path(&mut self, path: Path, brush: impl Into<Brush>) -> &mut Self {
+        self.push(DrawCmd::FillPath {
+            path,
+            brush: brush.into(),
+        })
+    }
+
+    pub fn stroke_path(
+        &mut self,
+        path: Path,
+        stroke: Stroke,
+        brush: impl Into<Brush>,
+    ) -> &mut Self {
+        self.push(DrawCmd::StrokePath {
+            path,
+            stroke,
+            brush: brush.into(),
+        })
+    }
+
+    pub fn stroke_rect(
+        &mut self,
+        rect: Rect,
+        stroke: Stroke,
+        brush: impl Into<Brush>,
+    ) -> &mut Self {
+        self.stroke_path(Path::rect(rect), stroke, brush)
+    }
+
+    pub fn draw_line(
+        &mut self,
+        from: Point,
+        to: Point,
+        stroke: Stroke,
+        brush: impl Into<Brush>,
+    ) -> &mut Self {
+        self.stroke_path(Path::line(from, to), stroke, brush)
+    }
+
+    pub fn draw_shadow(&mut self, rect: Rect, radius: f32, shadow: Shadow) -> &mut Self {
+        self.push(DrawCmd::Shadow {
+            rect,
+            radius,
+            shadow,
+        })
+    }
+
+    pub fn blur_region(&mut self, rect: Rect, blur: f32) -> &mut Self {
+        self.push(DrawCmd::BlurRegion { rect, blur })
+    }
+
+    pub fn backdrop_blur(
+        &mut self,
+        rect: Rect,
+        radius: f32,
+        blur: f32,
+        tint: impl Into<Color>,
+    ) -> &mut Self {
+        self.push(DrawCmd::BackdropBlur {
+            rect,
+            radius,
+            blur,
+            tint: tint.into(),
+        })
+    }
+
+    pub fn draw_image(&mut self, bitmap: &Bitmap, dst: Rect) -> &mut Self {
+        self.draw_image_filtered(bitmap, dst, FilterMode::Bilinear)
+    }
+
+    pub fn draw_image_filtered(
+        &mut self,
+        bitmap: &Bitmap,
+        dst: Rect,
+        filter: FilterMode,
+    ) -> &mut Self {
+        self.push(DrawCmd::Image {
+            bitmap: bitmap.clone(),
+            dst,
+            filter,
+            opacity: 1.0,
+        })
+    }
+
+    /// Record the current contents of `target`, faded by `opacity`
+    pub fn draw_target(&mut self, target: &RenderTarget, dst: Rect, opacity: f32) -> &mut Self {
+        self.push(DrawCmd::Image {
+            bitmap: target.to_bitmap(),
+            dst,
+            filter: FilterMode::Bilinear,
+            opacity: opacity.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Record a named sprite; unknown names record nothing
+    pub fn draw_sprite(&mut self, sheet: &SpriteSheet, name: &str, dst: Rect) -> &mut Self {
+        match sheet.region(name) {
+            Some(src) => self.push(DrawCmd::ImageRegion {
+                bitmap: sheet.bitmap().clone(),
+                src,
+                dst,
+                filter: FilterMode::Bilinear,
+            }),
+            None => self,
+        }
+    }
+
+    pub fn draw_nine_patch(&mut self, patch: &NinePatch, dst: Rect) -> &mut Self {
+        self.push(DrawCmd::NinePatch {
+            patch: patch.clone(),
+            dst,
+        })
+    }
+
+    #[cfg(feature = "svg")]
+    pub fn draw_svg(&mut self, svg: &Svg, dst: Rect) -> &mut Self {
+        self.push(DrawCmd::Svg {
+            svg: svg.clone(),
+            dst,
+        })
+    }
+
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        font: &Font,
+        size: f32,
+        center: Point,
+        color: impl Into<Color>,
+    ) -> &mut Self {
+        self.push(DrawCmd::Text {
+            text: text.to_string(),
+            font: font.clone(),
+            size,
+            center,
+            color: color.into(),
+        })
+    }
+
+    /// Record wrapped text with its top-left corner at `origin`
+    pub fn draw_text_layout(
+        &mut self,
+        layout: &TextLayout,
+        origin: Point,
+        color: impl Into<Color>,
+    ) -> &mut Self {
+        let size = layout.size();
+        let rect = Rect::new(origin.x, origin.y, size.width, size.height);
+        self.draw_text_in(layout, rect, color)
+    }
+
+    /// Record wrapped text aligned within `rect` as its style says
+    pub fn draw_text_in(
+        &mut self,
+        layout: &TextLayout,
+        rect: Rect,
+        color: impl Into<Color>,
+    ) -> &mut Self {
+        self.push(DrawCmd::TextLayout {
+            layout: layout.clone(),
+            rect,
+            color: color.into(),
+        })
+    }
+
+    pub fn push_clip(&mut self, rect: Rect) -> &mut Self {
+        self.push(DrawCmd::PushClip(rect))
+    }
+
+    pub fn pop_clip(&mut self) -> &mut Self {
+        self.push(DrawCmd::PopClip)
+    }
+
+    pub fn push_transform(&mut self, t: Transform) -> &mut Self {
+        self.push(DrawCmd::PushTransform(t))
+    }
+
+    pub fn pop_transform(&mut self) -> &mut Self {
+        self.push(DrawCmd::PopTransform)
+    }
+
+    /// Composite everything recorded until the matching `pop_opacity` as one
+    /// picture faded to `opacity`
+    pub fn push_opacity(&mut self, opacity: f32) -> &mut Self {
+        self.push(DrawCmd::PushOpacity(opacity))
+    }
+
+    pub fn pop_opacity(&mut self) -> &mut Self {
+        self.push(DrawCmd::PopOpacity)
+    }
+
+    /// Record `f` as a group faded to `opacity`, e.g. for a disabled or fading widget
+    pub fn opacity(&mut self, opacity: f32, f: impl FnOnce(&mut Self)) -> &mut Self {
+        self.push_opacity(opacity);
+        f(self);
+        self.pop_opacity()
+    }
+
+    pub fn push_blend_mode(&mut self, mode: BlendMode) -> &mut Self {
+        self.push(DrawCmd::PushBlendMode(mode))
+    }
+
+    pub fn pop_blend_mode(&mut self) -> &mut Self {
+        self.push(DrawCmd::PopBlendMode)
+    }
+
+    /// Record `f` composited with `mode`
+    pub fn blend_mode(&mut self, mode: BlendMode, f: impl FnOnce(&mut Self)) -> &mut Self {
+        self.push_blend_mode(mode);
+        f(self);
+        self.pop_blend_mode()
+    }
+}
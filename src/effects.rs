@@ -0,0 +1,228 @@
+use crate::brush::Brush;
+use crate::color::Color;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::path::Path;
+use crate::raster::{self, Mask};
+
+/// Number of box blur passes; three approximate a Gaussian closely
+const BLUR_PASSES: usize = 3;
+
+/// A blurred copy of a shape drawn beneath it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub offset: Point,
+    /// Blur radius in pixels, like CSS `box-shadow`
+    pub blur: f32,
+    pub color: Color,
+}
+
+impl Shadow {
+    pub fn new(offset: Point, blur: f32, color: impl Into<Color>) -> Self {
+        Self {
+            offset,
+            blur,
+            color: color.into(),
+        }
+    }
+}
+
+/// Box radius whose repeated passes approximate a Gaussian for a CSS-style blur radius
+pub fn box_radius(blur: f32) -> usize {
+    (blur / 2.0).max(0.0).round() as usize
+}
+
+/// How far a blur of radius `blur` spreads content, in pixels
+pub fn blur_extent(blur: f32) -> f32 {
+    (BLUR_PASSES * box_radius(blur)) as f32
+}
+
+/// Blur a row-major plane in place with repeated separable box blurs, treating
+/// everything outside the plane as zero
+pub fn box_blur(data: &mut [f32], width: usize, height: usize, radius: usize) {
+    if radius == 0 || width == 0 || height == 0 {
+        return;
+    }
+    let mut tmp = vec![0.0; data.len()];
+    for _ in 0..BLUR_PASSES {
+        blur_lines(data, &mut tmp, height, width, 1, width, radius);
+        blur_lines(&tmp, data, width, height, width, 1, radius);
+    }
+}
+
+/// Box blur `lines` runs of `len` samples each, `step` apart, lines `line_step` apart
+fn blur_lines(
+    src: &[f32],
+    dst: &mut [f32],
+    lines: usize,
+    len: usize,
+    step: usize,
+    line_step: usize,
+    r: usize,
+) {
+    let norm = 1.0 / (2 * r + 1) as f32;
+    for line in 0..lines {
+        let base = line * line_step;
+        let at = |i: usize| src[base + i * step];
+        let mut sum: f32 = (0..=r.min(len - 1)).map(at).sum();
+        for i in 0..len {
+            dst[base + i * step] = sum * norm;
+            if i + r + 1 < len {
+                sum += at(i + r + 1);
+            }
+            if i >= r {
+                sum -= at(i - r);
+            }
+        }
+    }
+}
+
+impl Mask {
+    /// Grow the mask by `n` empty pixels on every side
+    pub fn pad(&self, n: usize) -> Mask {
+        let width = self.width + 2 * n;
+        let height = self.height + 2 * n;
+        let mut data = vec![0.0; width * height];
+        for y in 0..self.height {
+            let src = &self.data[y * self.width..(y + 1) * self.width];
+            data[(y + n) * width + n..(y + n) * width + n + self.width].copy_from_slice(src);
+        }
+        Mask {
+            x: self.x - n as i32,
+            y: self.y - n as i32,
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn blur(&mut self, blur: f32) {
+        box_blur(&mut self.data, self.width, self.height, box_radius(blur));
+    }
+}
+
+impl Painter<'_> {
+    /// Draw the shadow of a (rounded) rectangle; paint the shape itself afterwards
+    pub fn draw_shadow(&mut self, rect: Rect, corner_radius: f32, shadow: &Shadow) {
+        let path = Path::rounded_rect(
+            rect.translate(shadow.offset.x, shadow.offset.y),
+            corner_radius,
+        );
+        let t = self.transform();
+        let mut polygons = path.flatten();
+        for p in polygons.iter_mut().flatten() {
+            *p = t.apply(*p);
+        }
+        let pad = BLUR_PASSES * box_radius(shadow.blur);
+        // Rasterize unclipped so the blur sees the whole shape, then clip when painting
+        let region = t
+            .map_rect(rect.translate(shadow.offset.x, shadow.offset.y))
+            .inset(-1.0);
+        if let Some(mask) = raster::rasterize(&polygons, region) {
+            let mut mask = mask.pad(pad);
+            mask.blur(shadow.blur);
+            self.fill_mask(&mask, &Brush::Solid(shadow.color));
+        }
+    }
+}
+
+impl Painter<'_> {
+    /// Blur what has already been drawn inside `rect`
+    pub fn blur_region(&mut self, rect: Rect, blur: f32) {
+        let area = match self
+            .transform()
+            .map_rect(rect)
+            .round_out()
+            .intersect(&self.clip_rect())
+        {
+            Some(area) => area,
+            None => return,
+        };
+        let blurred = self.blurred_pixels(area, blur);
+        let (x0, y0, w) = (area.x as usize, area.y as usize, area.width as usize);
+        let stride = self.width() as usize;
+        let frame = self.frame_mut();
+        for (i, px) in blurred.iter().enumerate() {
+            let idx = ((y0 + i / w) * stride + x0 + i % w) * 4;
+            frame[idx..idx + 4].copy_from_slice(px);
+        }
+    }
+
+    /// Frosted glass: blur the content behind a rounded rectangle, then tint it
+    pub fn backdrop_blur(
+        &mut self,
+        rect: Rect,
+        corner_radius: f32,
+        blur: f32,
+        tint: impl Into<Color>,
+    ) {
+        let t = self.transform();
+        let mut polygons = Path::rounded_rect(rect, corner_radius).flatten();
+        for p in polygons.iter_mut().flatten() {
+            *p = t.apply(*p);
+        }
+        let mask = match raster::rasterize(&polygons, self.clip_rect()) {
+            Some(mask) => mask,
+            None => return,
+        };
+        let blurred = self.blurred_pixels(mask.bounds(), blur);
+        let stride = self.width() as usize;
+        let frame = self.frame_mut();
+        for my in 0..mask.height {
+            for mx in 0..mask.width {
+                let c = mask.data[my * mask.width + mx];
+                if c <= 0.0 {
+                    continue;
+                }
+                let idx = ((mask.y as usize + my) * stride + mask.x as usize + mx) * 4;
+                let src = blurred[my * mask.width + mx];
+                for i in 0..4 {
+                    let dst = frame[idx + i] as f32;
+                    frame[idx + i] = (dst + (src[i] as f32 - dst) * c).round() as u8;
+                }
+            }
+        }
+        self.fill_rounded_rect(rect, corner_radius, tint.into());
+    }
+
+    /// Blurred copy of the frame pixels in `area` (whole pixels, inside the frame)
+    ///
+    /// Neighbouring pixels outside `area` contribute, and the frame edges are
+    /// normalised so they don't darken.
+    fn blurred_pixels(&mut self, area: Rect, blur: f32) -> Vec<[u8; 4]> {
+        let pad = blur_extent(blur);
+        let src = area.inset(-pad).intersect(&self.bounds()).unwrap_or(area);
+        let (sx, sy) = (src.x as usize, src.y as usize);
+        let (sw, sh) = (src.width as usize, src.height as usize);
+        let stride = self.width() as usize;
+        let frame = self.frame_mut();
+
+        let mut planes = vec![vec![0.0f32; sw * sh]; 5];
+        for y in 0..sh {
+            for x in 0..sw {
+                let idx = ((sy + y) * stride + sx + x) * 4;
+                for c in 0..4 {
+                    planes[c][y * sw + x] = frame[idx + c] as f32;
+                }
+                planes[4][y * sw + x] = 1.0;
+            }
+        }
+        for plane in planes.iter_mut() {
+            box_blur(plane, sw, sh, box_radius(blur));
+        }
+
+        let (ox, oy) = (area.x as usize - sx, area.y as usize - sy);
+        let (w, h) = (area.width as usize, area.height as usize);
+        let mut out = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                let i = (oy + y) * sw + ox + x;
+                let weight = planes[4][i].max(f32::EPSILON);
+                out.push(std::array::from_fn(|c| {
+                    (planes[c][i] / weight).round().clamp(0.0, 255.0) as u8
+                }));
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+/// Errors that can stop a Wixe application from starting
+#[derive(Debug)]
+pub enum Error {
+    Window(winit::error::OsError),
+    Pixels(pixels::Error),
+    Image(image::ImageError),
+    Io(std::io::Error),
+    Clipboard(arboard::Error),
+    Icon(winit::window::BadIcon),
+    /// Font data that is not TrueType or OpenType
+    Font,
+    /// No installed font has the requested family name
+    FontNotFound(String),
+    /// Text that does not name a key combination
+    InvalidShortcut(String),
+    /// A shortcut registered twice in the same scope
+    ShortcutConflict(String),
+    /// Line of an input recording that is no event
+    InvalidRecording(usize),
+    /// Line of a stylesheet that is no rule or declaration
+    InvalidStylesheet(usize),
+    #[cfg(feature = "svg")]
+    Svg(usvg::Error),
+    /// The OS refused a system-wide hotkey
+    #[cfg(feature = "hotkeys")]
+    Hotkey(global_hotkey::Error),
+    /// The OS has no notification service or refused the notification
+    #[cfg(feature = "notifications")]
+    Notification(notify_rust::error::Error),
+    /// The OS has no tray or refused the icon
+    #[cfg(feature = "tray")]
+    Tray(tray_icon::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Window(e) => write!(f, "failed to create window: {e}"),
+            Error::Pixels(e) => write!(f, "failed to create frame buffer: {e}"),
+            Error::Image(e) => write!(f, "failed to load image: {e}"),
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Clipboard(e) => write!(f, "clipboard unavailable: {e}"),
+            Error::Icon(e) => write!(f, "invalid window icon: {e}"),
+            Error::Font => write!(f, "failed to parse font"),
+            Error::FontNotFound(family) => write!(f, "font family not found: {family}"),
+            Error::InvalidShortcut(text) => write!(f, "invalid shortcut: {text}"),
+            Error::ShortcutConflict(shortcut) => {
+                write!(f, "shortcut already registered: {shortcut}")
+            }
+            Error::InvalidRecording(line) => write!(f, "invalid input recording at line {line}"),
+            Error::InvalidStylesheet(line) => write!(f, "invalid stylesheet at line {line}"),
+            #[cfg(feature = "svg")]
+            Error::Svg(e) => write!(f, "failed to parse SVG: {e}"),
+            #[cfg(feature = "hotkeys")]
+            Error::Hotkey(e) => write!(f, "failed to register hotkey: {e}"),
+            #[cfg(feature = "notifications")]
+            Error::Notification(e) => write!(f, "failed to show notification: {e}"),
+            #[cfg(feature = "tray")]
+            Error::Tray(e) => write!(f, "failed to show tray icon: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Window(e) => Some(e),
+            Error::Pixels(e) => Some(e),
+            Error::Image(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Clipboard(e) => Some(e),
+            Error::Icon(e) => Some(e),
+            Error::Font
+            | Error::FontNotFound(_)
+            | Error::InvalidShortcut(_)
+            | Error::ShortcutConflict(_)
+            | Error::InvalidRecording(_)
+            | Error::InvalidStylesheet(_) => None,
+            #[cfg(feature = "svg")]
+            Error::Svg(e) => Some(e),
+            #[cfg(feature = "hotkeys")]
+            Error::Hotkey(e) => Some(e),
+            #[cfg(feature = "notifications")]
+            Error::Notification(e) => Some(e),
+            #[cfg(feature = "tray")]
+            Error::Tray(e) => Some(e),
+        }
+    }
+}
+
+impl From<winit::error::OsError> for Error {
+    fn from(e: winit::error::OsError) -> Self {
+        Error::Window(e)
+    }
+}
+
+impl From<pixels::Error> for Error {
+    fn from(e: pixels::Error) -> Self {
+        Error::Pixels(e)
+    }
+}
+
+impl From<winit::window::BadIcon> for Error {
+    fn from(e: winit::window::BadIcon) -> Self {
+        Error::Icon(e)
+    }
+}
+
+impl From<arboard::Error> for Error {
+    fn from(e: arboard::Error) -> Self {
+        Error::Clipboard(e)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Self {
+        Error::Image(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "svg")]
+impl From<usvg::Error> for Error {
+    fn from(e: usvg::Error) -> Self {
+        Error::Svg(e)
+    }
+}
+
+#[cfg(feature = "hotkeys")]
+impl From<global_hotkey::Error> for Error {
+    fn from(e: global_hotkey::Error) -> Self {
+        Error::Hotkey(e)
+    }
+}
+
+#[cfg(feature = "notifications")]
+impl From<notify_rust::error::Error> for Error {
+    fn from(e: notify_rust::error::Error) -> Self {
+        Error::Notification(e)
+    }
+}
+
+#[cfg(feature = "tray")]
+impl From<tray_icon::Error> for Error {
+    fn from(e: tray_icon::Error) -> Self {
+        Error::Tray(e)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::app::Context;
+use crate::drag::DragEvent;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::GamepadEvent;
+use crate::gesture::GestureEvent;
+use crate::keyboard::{Composition, KeyEvent, TextEvent};
+use crate::pointer::PointerEvent;
+use crate::scroll::ScrollEvent;
+
+/// Any input event the application receives, as seen by event filters
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Pointer(PointerEvent),
+    Drag(DragEvent),
+    /// Also covers the shortcut the key triggers
+    Key(KeyEvent),
+    Text(TextEvent),
+    Composition(Composition),
+    Scroll(ScrollEvent),
+    Gesture(GestureEvent),
+    #[cfg(feature = "gamepad")]
+    Gamepad(GamepadEvent),
+}
+
+/// Names an event filter to remove it, returned by `Context::add_event_filter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilterId(u64);
+
+/// Sees an input event before the application does, returning `true` to
+/// consume it
+pub(crate) type Filter = Box<dyn FnMut(&mut Context, &InputEvent) -> bool>;
+
+/// Event filters in the order they were added
+#[derive(Default)]
+pub(crate) struct EventFilters {
+    filters: Vec<(FilterId, Filter)>,
+    /// Filters removed while they were taken out to run
+    removed: Vec<FilterId>,
+    next_id: u64,
+}
+
+impl EventFilters {
+    pub(crate) fn add(&mut self, filter: Filter) -> FilterId {
+        let id = FilterId(self.next_id);
+        self.next_id += 1;
+        self.filters.push((id, filter));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: FilterId) {
+        let count = self.filters.len();
+        self.filters.retain(|(f, _)| *f != id);
+        if self.filters.len() == count {
+            self.removed.push(id);
+        }
+    }
+
+    /// Take the filters out to run them with the context
+    pub(crate) fn take(&mut self) -> Vec<(FilterId, Filter)> {
+        std::mem::take(&mut self.filters)
+    }
+
+    /// Put back filters taken out, keeping ones added and dropping ones
+    /// removed while they ran
+    pub(crate) fn restore(&mut self, mut filters: Vec<(FilterId, Filter)>) {
+        filters.append(&mut self.filters);
+        let removed = std::mem::take(&mut self.removed);
+        filters.retain(|(id, _)| !removed.contains(id));
+        self.filters = filters;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
@@ -0,0 +1,726 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
+
+use ab_glyph::{Font as _, FontArc, FontRef, FontVec, GlyphId};
+use fontdb::{Database, Family, Query, Style, Weight};
+use rustybuzz::ttf_parser::{self, Tag};
+
+use crate::bitmap::Bitmap;
+use crate::color::Color;
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::path;
+use crate::text::{TextLayout, TextMetrics, TextStyle};
+
+/// Fonts installed on the system, scanned on first use
+static SYSTEM_FONTS: LazyLock<Database> = LazyLock::new(|| {
+    let mut db = Database::new();
+    db.load_system_fonts();
+    db
+});
+
+/// Fake bold thickening per 300 units of missing weight, as a fraction of the em
+const EMBOLDEN_PER_BOLD: f32 = 1.0 / 24.0;
+
+/// Roboto Regular, under the Apache License 2.0
+static DEFAULT_FONT: LazyLock<Font> = LazyLock::new(|| {
+    let data = include_bytes!("../assets/Roboto-Regular.ttf");
+    let face = FontRef::try_from_slice(data).expect("embedded font is valid");
+    Font::from_face(
+        FontArc::new(face),
+        Some(FaceData::new(data, 0)),
+        LineMetrics::read(data, 0),
+    )
+});
+
+/// Next identity handed to a loaded face
+static NEXT_FACE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Thickness of a typeface, from 100 (thin) to 900 (black)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const THIN: FontWeight = FontWeight(100);
+    pub const LIGHT: FontWeight = FontWeight(300);
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const MEDIUM: FontWeight = FontWeight(500);
+    pub const SEMIBOLD: FontWeight = FontWeight(600);
+    pub const BOLD: FontWeight = FontWeight(700);
+    pub const BLACK: FontWeight = FontWeight(900);
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// Adjustments faking a style the font has no face for
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Synthesis {
+    /// Extra stem thickness, as a fraction of the em
+    pub embolden: f32,
+    /// Slant upright glyphs
+    pub oblique: bool,
+}
+
+/// The file a face was parsed from, kept for shaping and color glyphs
+#[derive(Clone)]
+pub(crate) struct FaceData {
+    pub bytes: Arc<[u8]>,
+    /// Index of the face within a collection
+    pub index: u32,
+    /// Offsets of the `COLR` and `CPAL` tables, for layered color glyphs
+    colr: Option<usize>,
+    cpal: Option<usize>,
+    /// Whether the face has `CBDT` or `sbix` bitmaps, as color emoji fonts do
+    bitmaps: bool,
+    /// Variation axes of a variable face, with their least, default and greatest values
+    axes: Vec<(Tag, f32, f32, f32)>,
+    /// Axis values other than the defaults, set ones first
+    variations: Vec<(Tag, f32)>,
+}
+
+impl FaceData {
+    fn new(bytes: &[u8], index: u32) -> Self {
+        Self {
+            colr: table(bytes, index, b"COLR"),
+            cpal: table(bytes, index, b"CPAL"),
+            bitmaps: table(bytes, index, b"CBDT")
+                .or(table(bytes, index, b"sbix"))
+                .is_some(),
+            axes: ttf_parser::Face::parse(bytes, index)
+                .map(|face| {
+                    (face.variation_axes().into_iter())
+                        .map(|a| (a.tag, a.min_value, a.def_value, a.max_value))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            variations: Vec::new(),
+            bytes: Arc::from(bytes),
+            index,
+        }
+    }
+
+    /// Least, default and greatest value of variation axis `tag`, `None` if the
+    /// face has no such axis
+    pub fn axis(&self, tag: Tag) -> Option<(f32, f32, f32)> {
+        (self.axes.iter())
+            .find(|a| a.0 == tag)
+            .map(|&(_, min, default, max)| (min, default, max))
+    }
+
+    /// Axis values the face is drawn at, where they differ from the defaults
+    pub fn variations(&self) -> &[(Tag, f32)] {
+        &self.variations
+    }
+
+    /// Draw the face with axis `tag` at `value`, if it has such an axis
+    pub fn set_variation(&mut self, tag: Tag, value: f32) {
+        let Some((min, _, max)) = self.axis(tag) else {
+            return;
+        };
+        let value = value.clamp(min, max);
+        match self.variations.iter_mut().find(|v| v.0 == tag) {
+            Some(v) => v.1 = value,
+            None => self.variations.push((tag, value)),
+        }
+    }
+
+    /// Set axis `tag` to `value` unless it was set already or `value` is its default
+    fn set_default_variation(&mut self, tag: Tag, value: f32) {
+        let default = self.axis(tag).map(|a| a.1);
+        if default != Some(value) && self.variations.iter().all(|v| v.0 != tag) {
+            self.set_variation(tag, value);
+        }
+    }
+
+    /// Tells apart the axis values glyphs were rasterized at, zero for the defaults
+    pub fn variation_key(&self) -> u64 {
+        if self.variations.is_empty() {
+            return 0;
+        }
+        let mut hasher = DefaultHasher::new();
+        for (tag, value) in &self.variations {
+            (tag.0, value.to_bits()).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Outline of `glyph` at the face's axis values, `scale` pixels per em across
+    /// and down, with y down and the pen at `pen`
+    pub fn glyph_outline(
+        &self,
+        glyph: GlyphId,
+        scale: (f32, f32),
+        pen: Point,
+    ) -> Option<path::Path> {
+        let mut face = ttf_parser::Face::parse(&self.bytes, self.index).ok()?;
+        for &(tag, value) in &self.variations {
+            face.set_variation(tag, value);
+        }
+        let units = face.units_per_em() as f32;
+        let mut outline = Outline {
+            path: path::Path::new(),
+            scale: (scale.0 / units, scale.1 / units),
+            pen,
+        };
+        face.outline_glyph(ttf_parser::GlyphId(glyph.0), &mut outline)?;
+        Some(outline.path)
+    }
+
+    /// Whether glyphs may be drawn in their own colors instead of the text's
+    pub fn has_color(&self) -> bool {
+        self.colr.is_some() || self.bitmaps
+    }
+
+    /// Glyphs stacked bottom to top to draw `glyph` in color, each with its palette
+    /// color or `None` for the text color
+    pub fn color_layers(&self, glyph: GlyphId) -> Option<Vec<(GlyphId, Option<Color>)>> {
+        let (colr, data) = (self.colr?, &self.bytes[..]);
+        let count = read_u16(data, colr + 2)? as usize;
+        let bases = colr + read_u32(data, colr + 4)? as usize;
+        let layers = colr + read_u32(data, colr + 8)? as usize;
+        // Base glyph records are sorted by glyph id
+        let (mut lo, mut hi) = (0, count);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let record = bases + 6 * mid;
+            match read_u16(data, record)?.cmp(&glyph.0) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    let first = read_u16(data, record + 2)? as usize;
+                    let count = read_u16(data, record + 4)? as usize;
+                    return (first..first + count)
+                        .map(|i| {
+                            let layer = layers + 4 * i;
+                            let id = read_u16(data, layer)?;
+                            Some((GlyphId(id), self.palette_color(read_u16(data, layer + 2)?)))
+                        })
+                        .collect();
+                }
+            }
+        }
+        None
+    }
+
+    /// Entry `entry` of the first palette, `None` for the text color
+    fn palette_color(&self, entry: u16) -> Option<Color> {
+        if entry == 0xFFFF {
+            return None;
+        }
+        let (cpal, data) = (self.cpal?, &self.bytes[..]);
+        let records = cpal + read_u32(data, cpal + 8)? as usize;
+        let first = read_u16(data, cpal + 12)? as usize;
+        let at = records + 4 * (first + entry as usize);
+        let &[b, g, r, a] = data.get(at..at + 4)? else {
+            return None;
+        };
+        Some(Color::rgba(r, g, b, a))
+    }
+
+    /// The embedded bitmap of `glyph` best suited to `size` pixels per em
+    pub fn glyph_image(&self, glyph: GlyphId, size: f32) -> Option<GlyphImage> {
+        if !self.bitmaps {
+            return None;
+        }
+        let face = ttf_parser::Face::parse(&self.bytes, self.index).ok()?;
+        let ppem = size.round().clamp(1.0, u16::MAX as f32) as u16;
+        let image = face.glyph_raster_image(ttf_parser::GlyphId(glyph.0), ppem)?;
+        if image.format != ttf_parser::RasterImageFormat::PNG {
+            return None;
+        }
+        let scale = 1.0 / image.pixels_per_em as f32;
+        Some(GlyphImage {
+            bitmap: Bitmap::decode(image.data).ok()?,
+            // Images are placed by their bottom-left corner, above the baseline
+            rect: Rect::new(
+                image.x as f32 * scale,
+                -(image.y as f32 + image.height as f32) * scale,
+                image.width as f32 * scale,
+                image.height as f32 * scale,
+            ),
+        })
+    }
+}
+
+/// A color glyph's bitmap and where it goes, in em from the pen position
+pub(crate) struct GlyphImage {
+    pub bitmap: Bitmap,
+    pub rect: Rect,
+}
+
+/// Collects a glyph outline in font units into a path in pixels
+struct Outline {
+    path: path::Path,
+    scale: (f32, f32),
+    pen: Point,
+}
+
+impl Outline {
+    /// Font units are y up from the pen, pixels y down
+    fn point(&self, x: f32, y: f32) -> Point {
+        Point::new(self.pen.x + x * self.scale.0, self.pen.y - y * self.scale.1)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.move_to(p);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.line_to(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (c, p) = (self.point(x1, y1), self.point(x, y));
+        self.path.quad_to(c, p);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (c1, c2, p) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.path.cubic_to(c1, c2, p);
+    }
+
+    fn close(&mut self) {
+        self.path.close();
+    }
+}
+
+/// Placement of underlines and strikethroughs, in em with positive offsets above
+/// the baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+    pub underline_offset: f32,
+    pub underline_thickness: f32,
+    pub strikeout_offset: f32,
+    pub strikeout_thickness: f32,
+}
+
+impl Default for LineMetrics {
+    /// Typical values, for faces that do not say
+    fn default() -> Self {
+        Self {
+            underline_offset: -0.1,
+            underline_thickness: 0.05,
+            strikeout_offset: 0.28,
+            strikeout_thickness: 0.05,
+        }
+    }
+}
+
+impl LineMetrics {
+    /// Read the `post` and `OS/2` tables of face `index` in TrueType or OpenType data
+    fn read(data: &[u8], index: u32) -> Self {
+        let table = |tag: &[u8; 4]| table(data, index, tag);
+        let mut metrics = Self::default();
+        let Some(units) = table(b"head").and_then(|head| read_u16(data, head + 18)) else {
+            return metrics;
+        };
+        let em = |at: usize| read_u16(data, at).map(|v| v as i16 as f32 / units as f32);
+        if let Some(post) = table(b"post")
+            && let (Some(offset), Some(thickness)) = (em(post + 8), em(post + 10))
+            && thickness > 0.0
+        {
+            (metrics.underline_offset, metrics.underline_thickness) = (offset, thickness);
+        }
+        if let Some(os2) = table(b"OS/2")
+            && let (Some(thickness), Some(offset)) = (em(os2 + 26), em(os2 + 28))
+            && thickness > 0.0
+        {
+            (metrics.strikeout_offset, metrics.strikeout_thickness) = (offset, thickness);
+        }
+        metrics
+    }
+}
+
+/// Offset of table `tag` of face `index` in TrueType or OpenType data
+fn table(data: &[u8], index: u32, tag: &[u8; 4]) -> Option<usize> {
+    // Collections list the offset of each face's table directory
+    let directory = match data.get(..4) {
+        Some(b"ttcf") => read_u32(data, 12 + 4 * index as usize)? as usize,
+        _ => 0,
+    };
+    (0..read_u16(data, directory + 4)? as usize)
+        .map(|i| directory + 12 + 16 * i)
+        .find(|&record| data.get(record..record + 4) == Some(tag))
+        .and_then(|record| read_u32(data, record + 8))
+        .map(|offset| offset as usize)
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// One face of a family, with its weight and slant
+#[derive(Clone)]
+struct StyledFace {
+    weight: FontWeight,
+    italic: bool,
+    face: FontArc,
+    id: usize,
+    data: Option<FaceData>,
+    lines: LineMetrics,
+}
+
+/// A typeface followed by fallbacks tried, in order, for characters it lacks
+///
+/// Bold and italic faces of the same family can be added with `variant`; `styled`
+/// picks one, faking weight and slant the family has no face for.
+/// Clones share the loaded font data.
+#[derive(Clone)]
+pub struct Font {
+    /// The face in use, then the fallbacks
+    faces: Vec<FontArc>,
+    /// Identity of each face in `faces`, shared by clones, so its glyphs can be cached
+    ids: Vec<usize>,
+    /// Data of each face in `faces`, where known
+    data: Vec<Option<FaceData>>,
+    /// Every face of the primary family, the one the font was created with first
+    family: Vec<StyledFace>,
+    weight: FontWeight,
+    italic: bool,
+    /// Faked style of the face in use
+    synthesis: Synthesis,
+    /// Decoration placement of the face in use
+    lines: LineMetrics,
+}
+
+impl Font {
+    /// Parse TrueType or OpenType data
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        let (lines, shaping) = (LineMetrics::read(&data, 0), FaceData::new(&data, 0));
+        let face = FontArc::try_from_vec(data).map_err(|_| Error::Font)?;
+        Ok(Self::from_face(face, Some(shaping), lines))
+    }
+
+    /// Load a TrueType or OpenType file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Load an installed font by family name, e.g. "Segoe UI" or "Noto Sans"
+    ///
+    /// Its bold, italic and bold italic faces are loaded too when installed.
+    /// The generic names `serif`, `sans-serif`, `monospace`, `cursive` and
+    /// `fantasy` pick the system's default for that style.
+    pub fn system(family: &str) -> Result<Self, Error> {
+        let not_found = || Error::FontNotFound(family.to_string());
+        let regular = system_face(family, FontWeight::NORMAL, false).ok_or_else(not_found)?;
+        let (face, data, lines) = load_system_face(regular).ok_or_else(not_found)?;
+        let mut font = Self::from_face(face, Some(data), lines);
+        let mut loaded = vec![regular];
+        for (weight, italic) in [
+            (FontWeight::BOLD, false),
+            (FontWeight::NORMAL, true),
+            (FontWeight::BOLD, true),
+        ] {
+            // Queries fall back to the closest style, which may be one already loaded
+            if let Some(id) = system_face(family, weight, italic)
+                && !loaded.contains(&id)
+                && let Some((face, data, lines)) = load_system_face(id)
+            {
+                loaded.push(id);
+                font.family.push(StyledFace {
+                    weight,
+                    italic,
+                    face,
+                    id: NEXT_FACE_ID.fetch_add(1, Ordering::Relaxed),
+                    data: Some(data),
+                    lines,
+                });
+            }
+        }
+        Ok(font)
+    }
+
+    /// Add the primary face of `font` as the `weight` and `italic` style of this family
+    pub fn variant(mut self, weight: FontWeight, italic: bool, font: Font) -> Self {
+        self.family.push(StyledFace {
+            weight,
+            italic,
+            face: font.faces[0].clone(),
+            id: font.ids[0],
+            data: font.data[0].clone(),
+            lines: font.lines,
+        });
+        self
+    }
+
+    /// Draw every face of the family with variation axis `axis` at `value`, such as
+    /// `"wght"` for weight, `"wdth"` for width or `"slnt"` for slant
+    ///
+    /// Values are clamped to the axis's range; faces without the axis, and
+    /// fonts that are not variable, are left as they are. Axes set here win
+    /// over the weight and slant asked for with `styled`.
+    pub fn variation(mut self, axis: &str, value: f32) -> Self {
+        let tag = Tag::from_bytes_lossy(axis.as_bytes());
+        for data in (self.family.iter_mut().map(|f| &mut f.data))
+            .chain([&mut self.data[0]])
+            .flatten()
+        {
+            data.set_variation(tag, value);
+        }
+        self
+    }
+
+    /// The family's face closest to `weight` and `italic`
+    ///
+    /// Variable faces are set to the weight along their `wght` axis and
+    /// slanted along their `slnt` axis. Otherwise missing slant is faked by
+    /// shearing glyphs and missing weight by thickening them, fallback faces
+    /// included.
+    pub fn styled(&self, weight: FontWeight, italic: bool) -> Font {
+        let best = self
+            .family
+            .iter()
+            .min_by_key(|f| (f.italic != italic, f.weight.0.abs_diff(weight.0)))
+            .expect("a font has at least one face");
+        let mut font = self.clone();
+        font.faces[0] = best.face.clone();
+        font.ids[0] = best.id;
+        font.data[0] = best.data.clone();
+        font.lines = best.lines;
+        (font.weight, font.italic) = (weight, italic);
+        font.synthesis = Synthesis {
+            embolden: embolden(best.weight, weight),
+            oblique: italic && !best.italic,
+        };
+        if let Some(data) = &mut font.data[0] {
+            let (wght, slnt) = (Tag::from_bytes(b"wght"), Tag::from_bytes(b"slnt"));
+            if data.axis(wght).is_some() {
+                data.set_default_variation(wght, weight.0 as f32);
+                font.synthesis.embolden = 0.0;
+            }
+            // Negative slant leans forward, as italics do
+            if font.synthesis.oblique
+                && let Some((min, _, _)) = data.axis(slnt)
+            {
+                data.set_default_variation(slnt, min);
+                font.synthesis.oblique = false;
+            }
+        }
+        font
+    }
+
+    /// Weight and slant last asked for with `styled`
+    pub fn style(&self) -> (FontWeight, bool) {
+        (self.weight, self.italic)
+    }
+
+    /// Underline and strikethrough placement of the face in use
+    pub fn line_metrics(&self) -> LineMetrics {
+        self.lines
+    }
+
+    fn from_face(face: FontArc, data: Option<FaceData>, lines: LineMetrics) -> Self {
+        let id = NEXT_FACE_ID.fetch_add(1, Ordering::Relaxed);
+        Self {
+            family: vec![StyledFace {
+                weight: FontWeight::NORMAL,
+                italic: false,
+                face: face.clone(),
+                id,
+                data: data.clone(),
+                lines,
+            }],
+            faces: vec![face],
+            ids: vec![id],
+            data: vec![data],
+            weight: FontWeight::NORMAL,
+            italic: false,
+            synthesis: Synthesis::default(),
+            lines,
+        }
+    }
+
+    /// Append the faces of `fallback` to try after those already in the chain
+    pub fn fallback(mut self, fallback: Font) -> Self {
+        self.faces.extend(fallback.faces);
+        self.ids.extend(fallback.ids);
+        self.data.extend(fallback.data);
+        self
+    }
+
+    /// The face in use followed by its fallbacks
+    pub fn faces(&self) -> &[FontArc] {
+        &self.faces
+    }
+
+    /// Faked style of the face at `index`
+    pub(crate) fn synthesis(&self, index: usize) -> Synthesis {
+        if index == 0 {
+            return self.synthesis;
+        }
+        // Fallbacks are upright regular faces of other families
+        Synthesis {
+            embolden: embolden(FontWeight::NORMAL, self.weight),
+            oblique: self.italic,
+        }
+    }
+
+    /// Identity of the face at `index`, equal for its clones and never reused
+    pub(crate) fn face_id(&self, index: usize) -> usize {
+        self.ids[index]
+    }
+
+    /// Data of the face at `index`, `None` for faces made from a bare `FontArc`
+    pub(crate) fn face_data(&self, index: usize) -> Option<&FaceData> {
+        self.data[index].as_ref()
+    }
+
+    pub(crate) fn has_glyph(&self, index: usize, c: char) -> bool {
+        self.faces[index].glyph_id(c) != GlyphId(0)
+    }
+
+    /// Index of the first face with a glyph for `c`, the primary face if none has one
+    pub(crate) fn face_for(&self, c: char) -> usize {
+        (0..self.faces.len())
+            .find(|&index| self.has_glyph(index, c))
+            .unwrap_or(0)
+    }
+}
+
+/// The font built into Wixe, so text renders without any asset files
+impl Default for Font {
+    fn default() -> Self {
+        DEFAULT_FONT.clone()
+    }
+}
+
+/// Without its data the face is not shaped and its decoration metrics are
+/// unknown, so typical ones are used
+impl From<FontArc> for Font {
+    fn from(face: FontArc) -> Self {
+        Self::from_face(face, None, LineMetrics::default())
+    }
+}
+
+/// Thickening that makes a face of weight `from` look like `to`; faces are never thinned
+fn embolden(from: FontWeight, to: FontWeight) -> f32 {
+    to.0.saturating_sub(from.0) as f32 / 300.0 * EMBOLDEN_PER_BOLD
+}
+
+/// The installed face closest to `family`, `weight` and `italic`
+fn system_face(family: &str, weight: FontWeight, italic: bool) -> Option<fontdb::ID> {
+    let families = [match family {
+        "serif" => Family::Serif,
+        "sans-serif" => Family::SansSerif,
+        "monospace" => Family::Monospace,
+        "cursive" => Family::Cursive,
+        "fantasy" => Family::Fantasy,
+        name => Family::Name(name),
+    }];
+    let query = Query {
+        families: &families,
+        weight: Weight(weight.0),
+        style: if italic { Style::Italic } else { Style::Normal },
+        ..Query::default()
+    };
+    SYSTEM_FONTS.query(&query)
+}
+
+fn load_system_face(id: fontdb::ID) -> Option<(FontArc, FaceData, LineMetrics)> {
+    SYSTEM_FONTS
+        .with_face_data(id, |data, index| {
+            let face = FontVec::try_from_vec_and_index(data.to_vec(), index).ok()?;
+            Some((
+                FontArc::new(face),
+                FaceData::new(data, index),
+                LineMetrics::read(data, index),
+            ))
+        })
+        .flatten()
+}
+
+/// Handle to a font registered with `Fonts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontId(usize);
+
+impl FontId {
+    /// The built-in font, registered as `"default"`
+    pub const DEFAULT: FontId = FontId(0);
+}
+
+/// Fonts registered under logical names such as `"ui"` or `"mono"`, so widgets
+/// can refer to them without holding a `Font`
+#[derive(Clone)]
+pub struct Fonts {
+    fonts: Vec<Font>,
+    names: HashMap<String, FontId>,
+}
+
+impl Default for Fonts {
+    fn default() -> Self {
+        Self {
+            fonts: vec![Font::default()],
+            names: HashMap::from([("default".to_string(), FontId::DEFAULT)]),
+        }
+    }
+}
+
+impl Fonts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `font` under `name`; an existing name keeps its handle and gets the new font
+    pub fn insert(&mut self, name: &str, font: Font) -> FontId {
+        if let Some(&id) = self.names.get(name) {
+            self.fonts[id.0] = font;
+            return id;
+        }
+        let id = FontId(self.fonts.len());
+        self.fonts.push(font);
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    /// Register TrueType or OpenType data under `name`
+    pub fn register(&mut self, name: &str, data: impl Into<Vec<u8>>) -> Result<FontId, Error> {
+        Ok(self.insert(name, Font::from_bytes(data.into())?))
+    }
+
+    /// Register a TrueType or OpenType file under `name`
+    pub fn register_file(&mut self, name: &str, path: impl AsRef<Path>) -> Result<FontId, Error> {
+        Ok(self.insert(name, Font::open(path)?))
+    }
+
+    /// Handle of the font registered under `name`
+    pub fn id(&self, name: &str) -> Option<FontId> {
+        self.names.get(name).copied()
+    }
+
+    /// The font behind `id`, the default font for handles from another registry
+    pub fn get(&self, id: FontId) -> &Font {
+        self.fonts.get(id.0).unwrap_or(&self.fonts[0])
+    }
+
+    /// The font registered under `name`
+    pub fn by_name(&self, name: &str) -> Option<&Font> {
+        self.id(name).map(|id| self.get(id))
+    }
+
+    /// The font registered as `"default"`
+    pub fn default_font(&self) -> &Font {
+        self.get(FontId::DEFAULT)
+    }
+
+    /// Measure `text` in the style's font without drawing it, wrapped like
+    /// `TextLayout::new` given `max_width`
+    pub fn measure(&self, text: &str, style: &TextStyle, max_width: Option<f32>) -> TextMetrics {
+        TextLayout::new(text, self.get(style.font), style, max_width).metrics()
+    }
+}
@@ -0,0 +1,144 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+/// A gamepad button, named by its place on an Xbox-style layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// The bottom face button: A on Xbox, Cross on PlayStation
+    South,
+    /// The right face button: B on Xbox, Circle on PlayStation
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    /// The logo button in the middle
+    Mode,
+    /// Pressing the left stick in
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Unknown,
+}
+
+impl From<Button> for GamepadButton {
+    fn from(button: Button) -> Self {
+        match button {
+            Button::South => Self::South,
+            Button::East => Self::East,
+            Button::North => Self::North,
+            Button::West => Self::West,
+            Button::LeftTrigger => Self::LeftBumper,
+            Button::RightTrigger => Self::RightBumper,
+            Button::LeftTrigger2 => Self::LeftTrigger,
+            Button::RightTrigger2 => Self::RightTrigger,
+            Button::Select => Self::Select,
+            Button::Start => Self::Start,
+            Button::Mode => Self::Mode,
+            Button::LeftThumb => Self::LeftStick,
+            Button::RightThumb => Self::RightStick,
+            Button::DPadUp => Self::DPadUp,
+            Button::DPadDown => Self::DPadDown,
+            Button::DPadLeft => Self::DPadLeft,
+            Button::DPadRight => Self::DPadRight,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    /// Positive up
+    LeftStickY,
+    RightStickX,
+    /// Positive up
+    RightStickY,
+    Unknown,
+}
+
+impl From<Axis> for GamepadAxis {
+    fn from(axis: Axis) -> Self {
+        match axis {
+            Axis::LeftStickX => Self::LeftStickX,
+            Axis::LeftStickY => Self::LeftStickY,
+            Axis::RightStickX => Self::RightStickX,
+            Axis::RightStickY => Self::RightStickY,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadEventKind {
+    Connected,
+    Disconnected,
+    Press(GamepadButton),
+    Release(GamepadButton),
+    /// A stick moved to a value from -1 to 1
+    Axis(GamepadAxis, f32),
+}
+
+/// Input from a gamepad, delivered through `Application::gamepad_event`
+///
+/// Besides, the d-pad moves focus like Tab and the arrow keys, and South and
+/// East press Enter and Escape on the focused widget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GamepadEvent {
+    /// Tells apart gamepads connected at once
+    pub gamepad: usize,
+    pub kind: GamepadEventKind,
+}
+
+/// Gamepads connected to the machine, polled for input
+#[derive(Default)]
+pub(crate) struct Gamepads {
+    /// `None` where gamepads cannot be read
+    gilrs: Option<Gilrs>,
+}
+
+impl Gamepads {
+    pub(crate) fn new() -> Self {
+        Self {
+            gilrs: Gilrs::new().ok(),
+        }
+    }
+
+    /// Whether any gamepad is connected, so input must be polled
+    pub(crate) fn is_active(&self) -> bool {
+        self.gilrs
+            .as_ref()
+            .is_some_and(|gilrs| gilrs.gamepads().next().is_some())
+    }
+
+    /// Input since the last poll
+    pub(crate) fn poll(&mut self) -> Vec<GamepadEvent> {
+        let Some(gilrs) = &mut self.gilrs else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            let kind = match event.event {
+                EventType::Connected => GamepadEventKind::Connected,
+                EventType::Disconnected => GamepadEventKind::Disconnected,
+                EventType::ButtonPressed(button, _) => GamepadEventKind::Press(button.into()),
+                EventType::ButtonReleased(button, _) => GamepadEventKind::Release(button.into()),
+                EventType::AxisChanged(axis, value, _) => {
+                    GamepadEventKind::Axis(axis.into(), value)
+                }
+                _ => continue,
+            };
+            events.push(GamepadEvent {
+                gamepad: event.id.into(),
+                kind,
+            });
+        }
+        events
+    }
+}
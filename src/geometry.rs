@@ -0,0 +1,190 @@
+/// A point in frame coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub const ZERO: Point = Point { x: 0.0, y: 0.0 };
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn lerp(self, other: Point, t: f32) -> Point {
+        Point::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+        )
+    }
+
+    pub fn distance(self, other: Point) -> f32 {
+        ((other.x - self.x).powi(2) + (other.y - self.y).powi(2)).sqrt()
+    }
+}
+
+/// A width and height pair
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Size {
+    pub const ZERO: Size = Size {
+        width: 0.0,
+        height: 0.0,
+    };
+
+    pub const fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+}
+
+/// An axis-aligned rectangle
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_points(a: Point, b: Point) -> Self {
+        let x = a.x.min(b.x);
+        let y = a.y.min(b.y);
+        Self::new(x, y, a.x.max(b.x) - x, a.y.max(b.y) - y)
+    }
+
+    pub fn min_x(&self) -> f32 {
+        self.x
+    }
+
+    pub fn min_y(&self) -> f32 {
+        self.y
+    }
+
+    pub fn max_x(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn max_y(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= self.x && p.x < self.max_x() && p.y >= self.y && p.y < self.max_y()
+    }
+
+    /// Overlapping area of both rectangles, if any
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = self.max_x().min(other.max_x());
+        let y1 = self.max_y().min(other.max_y());
+        if x1 > x0 && y1 > y0 {
+            Some(Rect::new(x0, y0, x1 - x0, y1 - y0))
+        } else {
+            None
+        }
+    }
+
+    /// Smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = self.max_x().max(other.max_x());
+        let y1 = self.max_y().max(other.max_y());
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    /// Shrink by `d` on every side (grow when negative)
+    pub fn inset(&self, d: f32) -> Rect {
+        Rect::new(
+            self.x + d,
+            self.y + d,
+            self.width - 2.0 * d,
+            self.height - 2.0 * d,
+        )
+    }
+
+    pub fn translate(&self, dx: f32, dy: f32) -> Rect {
+        Rect::new(self.x + dx, self.y + dy, self.width, self.height)
+    }
+
+    /// Expand outwards to whole pixel boundaries
+    pub fn round_out(&self) -> Rect {
+        let x0 = self.x.floor();
+        let y0 = self.y.floor();
+        Rect::new(x0, y0, self.max_x().ceil() - x0, self.max_y().ceil() - y0)
+    }
+}
+
+/// Distances from each edge of a rectangle
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub const fn uniform(d: f32) -> Self {
+        Self::new(d, d, d, d)
+    }
+}
+
+impl Rect {
+    /// Shrink by different amounts on each side
+    pub fn inset_by(&self, insets: Insets) -> Rect {
+        Rect::new(
+            self.x + insets.left,
+            self.y + insets.top,
+            self.width - insets.left - insets.right,
+            self.height - insets.top - insets.bottom,
+        )
+    }
+}
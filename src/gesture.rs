@@ -0,0 +1,345 @@
+use std::f32::consts::{PI, TAU};
+use std::time::{Duration, Instant};
+
+use crate::geometry::Point;
+use crate::widget::WidgetId;
+
+/// Touches moving less than this in frame pixels are taps rather than drags
+const TOUCH_SLOP: f32 = 10.0;
+/// Drags lifted at this speed in frame pixels per second are swipes
+const SWIPE_SPEED: f32 = 600.0;
+/// Zoom per wheel notch of a Ctrl+wheel pinch
+const WHEEL_ZOOM: f32 = 1.1;
+/// Trackpad pixels of a Ctrl+wheel pinch that zoom by a factor of e
+const PIXELS_PER_E: f32 = 100.0;
+/// Seconds a finger rests before lifting that stop a drag being a swipe
+const SWIPE_REST: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GestureKind {
+    /// A finger touched and lifted without moving
+    Tap,
+    /// A finger moved by `delta` frame pixels since the last drag event
+    Drag { delta: Point },
+    /// The finger dragging lifted
+    DragEnd,
+    /// A drag ended with a flick, moving at `velocity` frame pixels per second;
+    /// follows its `DragEnd`
+    Swipe { velocity: Point },
+    /// Two fingers moved since the last pinch event, or the trackpad was
+    /// pinched: the distance between them grew `scale` times, they turned
+    /// clockwise by `rotation` radians and their midpoint, the position,
+    /// moved by `pan` frame pixels
+    ///
+    /// Zoom about the position, e.g. with `Viewport::zoom_at`, then pan.
+    Pinch {
+        scale: f32,
+        rotation: f32,
+        pan: Point,
+    },
+    /// One of the two fingers pinching lifted
+    PinchEnd,
+    /// A finger or the left mouse button was held down without moving for
+    /// `WindowOptions::long_press`, e.g. to open a context menu; lifting the
+    /// finger afterwards is no tap
+    LongPress,
+}
+
+/// A gesture recognized from touch input, delivered through
+/// `Application::gesture_event`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureEvent {
+    pub kind: GestureKind,
+    /// Innermost widget under the touch when it began
+    pub target: Option<WidgetId>,
+    /// Touch position in frame pixels
+    pub position: Point,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Touch {
+    id: u64,
+    target: Option<WidgetId>,
+    start: Point,
+    /// Where the last gesture event was, the start until the touch drags
+    position: Point,
+    /// Where the last move was, at `time`
+    last: Point,
+    time: Instant,
+    /// Frame pixels per second, smoothed over the last moves
+    velocity: Point,
+    dragging: bool,
+    /// Took part in a pinch or long press, so lifting it is no tap
+    no_tap: bool,
+}
+
+/// Two fingers on the screen as of the last pinch event
+#[derive(Debug, Clone, Copy)]
+struct Pinch {
+    fingers: [u64; 2],
+    center: Point,
+    distance: f32,
+    angle: f32,
+}
+
+/// Fingers on the screen, recognizing gestures as they move
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Touches {
+    touches: Vec<Touch>,
+    /// The finger that stands in for the mouse pointer
+    pub(crate) primary: Option<u64>,
+    pinch: Option<Pinch>,
+}
+
+impl Touches {
+    /// A finger touched at `p`, over `target`; the first one down becomes primary
+    ///
+    /// A second finger starts a pinch, ending the first one's drag.
+    pub(crate) fn start(
+        &mut self,
+        id: u64,
+        p: Point,
+        target: Option<WidgetId>,
+        now: Instant,
+    ) -> Vec<GestureEvent> {
+        self.touches.retain(|t| t.id != id);
+        self.touches.push(Touch {
+            id,
+            target,
+            start: p,
+            position: p,
+            last: p,
+            time: now,
+            velocity: Point::ZERO,
+            dragging: false,
+            no_tap: false,
+        });
+        self.primary.get_or_insert(id);
+
+        let mut events = Vec::new();
+        if let [first, second] = &mut self.touches[..] {
+            if first.dragging {
+                events.push(gesture(first, GestureKind::DragEnd));
+            }
+            for touch in [&mut *first, &mut *second] {
+                (touch.dragging, touch.no_tap) = (false, true);
+            }
+            let (center, distance, angle) = span(first.last, second.last);
+            self.pinch = Some(Pinch {
+                fingers: [first.id, second.id],
+                center,
+                distance,
+                angle,
+            });
+        }
+        events
+    }
+
+    /// Fingers down
+    pub(crate) fn count(&self) -> usize {
+        self.touches.len()
+    }
+
+    /// Keep a finger from tapping when it lifts
+    pub(crate) fn cancel_tap(&mut self, id: u64) {
+        if let Some(touch) = self.touches.iter_mut().find(|t| t.id == id) {
+            touch.no_tap = true;
+        }
+    }
+
+    /// Follow the pinch after one of its fingers moved
+    fn pinch_moved(&mut self) -> Option<GestureEvent> {
+        let pinch = self.pinch.as_mut()?;
+        let [a, b] = pinch
+            .fingers
+            .map(|id| self.touches.iter().find(|t| t.id == id));
+        let (a, b) = (a?, b?);
+        let (center, distance, angle) = span(a.last, b.last);
+        let scale = match pinch.distance > 0.0 {
+            true => distance / pinch.distance,
+            false => 1.0,
+        };
+        let kind = GestureKind::Pinch {
+            scale,
+            rotation: (angle - pinch.angle + PI).rem_euclid(TAU) - PI,
+            pan: Point::new(center.x - pinch.center.x, center.y - pinch.center.y),
+        };
+        (pinch.center, pinch.distance, pinch.angle) = (center, distance, angle);
+        Some(GestureEvent {
+            kind,
+            target: a.target,
+            position: center,
+        })
+    }
+
+    pub(crate) fn moved(&mut self, id: u64, p: Point, now: Instant) -> Vec<GestureEvent> {
+        let pinching = self.pinch.is_some_and(|pinch| pinch.fingers.contains(&id));
+        let Some(touch) = self.touches.iter_mut().find(|t| t.id == id) else {
+            return Vec::new();
+        };
+        let dt = now.duration_since(touch.time).as_secs_f32();
+        if dt > 0.0 {
+            let speed = Point::new((p.x - touch.last.x) / dt, (p.y - touch.last.y) / dt);
+            touch.velocity = touch.velocity.lerp(speed, 0.5);
+        }
+        (touch.last, touch.time) = (p, now);
+        if pinching {
+            return self.pinch_moved().into_iter().collect();
+        }
+        touch.dragging |= touch.start.distance(p) > TOUCH_SLOP;
+        if !touch.dragging {
+            return Vec::new();
+        }
+        // The first drag event covers the movement within the slop too
+        let delta = Point::new(p.x - touch.position.x, p.y - touch.position.y);
+        touch.position = p;
+        vec![gesture(touch, GestureKind::Drag { delta })]
+    }
+
+    /// A finger lifted at `p`, or the touch was `cancelled` by the system
+    pub(crate) fn end(
+        &mut self,
+        id: u64,
+        p: Point,
+        now: Instant,
+        cancelled: bool,
+    ) -> Vec<GestureEvent> {
+        let Some(index) = self.touches.iter().position(|t| t.id == id) else {
+            return Vec::new();
+        };
+        let mut touch = self.touches.remove(index);
+        if self.primary == Some(id) {
+            self.primary = None;
+        }
+        touch.position = p;
+        let mut events = Vec::new();
+        if let Some(pinch) = self.pinch.filter(|pinch| pinch.fingers.contains(&id)) {
+            self.pinch = None;
+            events.push(GestureEvent {
+                kind: GestureKind::PinchEnd,
+                target: touch.target,
+                position: pinch.center,
+            });
+            // The finger left behind may start a drag of its own
+            for other in &mut self.touches {
+                (other.start, other.position) = (other.last, other.last);
+            }
+        } else if touch.dragging {
+            events.push(gesture(&touch, GestureKind::DragEnd));
+            let rested = now.duration_since(touch.time).as_secs_f32() > SWIPE_REST;
+            let fast = touch.velocity.distance(Point::ZERO) > SWIPE_SPEED;
+            if !cancelled && !rested && fast {
+                let velocity = touch.velocity;
+                events.push(gesture(&touch, GestureKind::Swipe { velocity }));
+            }
+        } else if !cancelled && !touch.no_tap {
+            events.push(gesture(&touch, GestureKind::Tap));
+        }
+        events
+    }
+}
+
+/// A press held in place, to become a long press at `deadline`
+#[derive(Debug, Clone, Copy)]
+struct Hold {
+    /// The finger held, `None` for the mouse
+    touch: Option<u64>,
+    target: Option<WidgetId>,
+    position: Point,
+    deadline: Instant,
+}
+
+/// Turns presses held without moving into long presses
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LongPress {
+    pub(crate) delay: Duration,
+    hold: Option<Hold>,
+}
+
+impl LongPress {
+    /// A finger, or the mouse for `None`, pressed at `p`
+    pub(crate) fn press(
+        &mut self,
+        touch: Option<u64>,
+        target: Option<WidgetId>,
+        p: Point,
+        now: Instant,
+    ) {
+        self.hold = Some(Hold {
+            touch,
+            target,
+            position: p,
+            deadline: now + self.delay,
+        });
+    }
+
+    /// A finger, or the mouse for `None`, moved to `p`; moving off ends the hold
+    pub(crate) fn moved(&mut self, touch: Option<u64>, p: Point) {
+        let moved_off = |hold: Hold| hold.touch == touch && hold.position.distance(p) > TOUCH_SLOP;
+        if self.hold.is_some_and(moved_off) {
+            self.hold = None;
+        }
+    }
+
+    /// A finger lifted, or the mouse button was released for `None`
+    pub(crate) fn release(&mut self, touch: Option<u64>) {
+        if self.hold.is_some_and(|hold| hold.touch == touch) {
+            self.hold = None;
+        }
+    }
+
+    pub(crate) fn cancel(&mut self) {
+        self.hold = None;
+    }
+
+    /// When the press held turns into a long press
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        Some(self.hold?.deadline)
+    }
+
+    /// The long press of the press held, once it has been held long enough,
+    /// along with the finger held
+    pub(crate) fn tick(&mut self, now: Instant) -> Option<(GestureEvent, Option<u64>)> {
+        let hold = self.hold.filter(|hold| now >= hold.deadline)?;
+        self.hold = None;
+        let event = GestureEvent {
+            kind: GestureKind::LongPress,
+            target: hold.target,
+            position: hold.position,
+        };
+        Some((event, hold.touch))
+    }
+}
+
+/// A trackpad pinch at `position`, which arrives as wheel scrolling with Ctrl
+/// held, `lines` notches or `pixels` frame pixels up to zoom in
+pub(crate) fn wheel_pinch(
+    target: Option<WidgetId>,
+    position: Point,
+    lines: f32,
+    pixels: f32,
+) -> GestureEvent {
+    let scale = WHEEL_ZOOM.powf(lines) * (pixels / PIXELS_PER_E).exp();
+    GestureEvent {
+        kind: GestureKind::Pinch {
+            scale,
+            rotation: 0.0,
+            pan: Point::ZERO,
+        },
+        target,
+        position,
+    }
+}
+
+/// Midpoint, distance and angle between two fingers
+fn span(a: Point, b: Point) -> (Point, f32, f32) {
+    (a.lerp(b, 0.5), a.distance(b), (b.y - a.y).atan2(b.x - a.x))
+}
+
+fn gesture(touch: &Touch, kind: GestureKind) -> GestureEvent {
+    GestureEvent {
+        kind,
+        target: touch.target,
+        position: touch.position,
+    }
+}
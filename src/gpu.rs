@@ -0,0 +1,1339 @@
+//! GPU rendering through wgpu, behind the `gpu` feature
+//!
+//! The frame is drawn into a texture kept from one frame to the next, so only
+//! damaged areas are redrawn, and then scaled into the window. Solid
+//! rectangles, images and text become textured quads, glyphs sampled from a
+//! copy of the glyph atlas kept on the GPU. Other shapes are rasterized on the
+//! CPU the first time they are drawn and kept in a shape atlas while they are
+//! drawn unchanged. Opacity groups and the remaining commands are rasterized
+//! whenever they are redrawn. Those cannot see what was drawn below them, so
+//! blurs are skipped and a backdrop blur shows only its tint.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::ops::Range;
+
+use pixels::wgpu;
+use pixels::{Pixels, PixelsBuilder, PixelsContext, SurfaceTexture};
+use winit::window::Window;
+
+use crate::atlas::{AtlasAllocator, Region};
+use crate::backend::{
+    self, CommandState, PostProcess, PresentMode, RenderBackend, Scaling, frame_viewport,
+};
+use crate::bitmap::{Bitmap, FilterMode};
+use crate::blend::BlendMode;
+use crate::brush::Brush;
+use crate::color::Color;
+use crate::draw_list::{DrawCmd, DrawList, Layer};
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::target::RenderTarget;
+use crate::text::{self, GlyphAtlasCopy, TextDraw};
+use crate::transform::Transform;
+
+/// Largest texture side every wgpu adapter supports
+const MAX_TEXTURE_SIZE: u32 = 8192;
+/// Side of the texture commands rasterized for one frame are packed into
+const STAGING_SIZE: u32 = 2048;
+/// Side of the texture rasterized shapes are kept in between frames
+const SHAPE_ATLAS_SIZE: u32 = 2048;
+/// Largest side of a shape kept in the shape atlas
+const MAX_CACHED_SHAPE: u32 = SHAPE_ATLAS_SIZE / 2;
+/// Frames an image may go undrawn before its texture is dropped
+const IMAGE_FRAMES: u64 = 120;
+/// Not sRGB, so colors blend exactly as they do on the CPU
+const SURFACE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8Unorm;
+
+/// Position, texture coordinate and premultiplied color
+const VERTEX_FLOATS: usize = 8;
+const VERTEX_SIZE: u64 = (VERTEX_FLOATS * std::mem::size_of::<f32>()) as u64;
+
+/// A texture together with a bind group for each filter mode
+struct Texture {
+    texture: wgpu::Texture,
+    linear: wgpu::BindGroup,
+    nearest: wgpu::BindGroup,
+}
+
+impl Texture {
+    fn bind_group(&self, filter: FilterMode) -> &wgpu::BindGroup {
+        match filter {
+            FilterMode::Nearest => &self.nearest,
+            FilterMode::Bilinear => &self.linear,
+        }
+    }
+}
+
+/// Which texture a batch samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    White,
+    Staging,
+    Shapes,
+    /// Copy of a glyph atlas page
+    Glyphs(usize),
+    Image(usize),
+    Transient(usize),
+}
+
+/// Consecutive vertices drawn with the same texture, scissor and blend mode
+struct Batch {
+    source: Source,
+    filter: FilterMode,
+    scissor: [u32; 4],
+    mode: BlendMode,
+    vertices: Range<u32>,
+}
+
+struct CachedImage {
+    /// Kept alive so the pixel storage, and with it the cache key, is never reused
+    _bitmap: Bitmap,
+    texture: Texture,
+    /// Frame the image was last drawn in
+    last_used: u64,
+}
+
+/// What a rasterized shape depends on besides the command itself: the local
+/// area it covers, the linear part of its transform and where the transform
+/// puts it within a pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ShapeKey {
+    bounds: [u32; 4],
+    linear: [u32; 4],
+    offset: [u32; 2],
+}
+
+impl ShapeKey {
+    fn new(t: Transform, bounds: Rect) -> Self {
+        let fract = |v: f32| (v - v.floor()).to_bits();
+        Self {
+            bounds: [bounds.x, bounds.y, bounds.width, bounds.height].map(f32::to_bits),
+            linear: [t.a, t.b, t.c, t.d].map(f32::to_bits),
+            offset: [fract(t.e), fract(t.f)],
+        }
+    }
+}
+
+/// A shape rasterized into the shape atlas, with no region if it drew nothing
+struct CachedShape {
+    cmd: DrawCmd,
+    region: Option<Region>,
+}
+
+/// Commands of an opacity group, rasterized together once it closes
+struct PendingGroup {
+    opacity: f32,
+    /// Groups opened inside this one and not closed yet
+    depth: usize,
+    cmds: Vec<DrawCmd>,
+    transform: Transform,
+    clip: Option<Rect>,
+}
+
+/// Frame state that lives on the GPU
+struct Renderer {
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    /// One pipeline per blend mode, created the first time the mode is used
+    pipelines: HashMap<BlendMode, wgpu::RenderPipeline>,
+    /// Overwrites what is below, to clear damage and present the frame
+    replace: wgpu::RenderPipeline,
+    /// Blend mode of the command being encoded
+    mode: BlendMode,
+    layout: wgpu::BindGroupLayout,
+    uniforms: wgpu::Buffer,
+    linear: wgpu::Sampler,
+    nearest: wgpu::Sampler,
+    white: Texture,
+    staging: Texture,
+    staging_alloc: AtlasAllocator,
+    shapes: Texture,
+    shape_alloc: AtlasAllocator,
+    shape_cache: HashMap<ShapeKey, Vec<CachedShape>>,
+    /// Set once a shape did not fit, to empty the shape atlas before the next frame
+    shapes_full: bool,
+    /// Copy of each glyph atlas page, created once it has a glyph
+    glyph_pages: Vec<Option<Texture>>,
+    glyph_copy: GlyphAtlasCopy,
+    images: HashMap<usize, CachedImage>,
+    /// Frames prepared so far
+    frame_count: u64,
+    /// Textures for rasterized commands too big for the staging texture, dropped each frame
+    transient: Vec<Texture>,
+    /// The frame, kept between frames so only damage is redrawn, and its size
+    frame: Option<(Texture, (u32, u32))>,
+    /// Frame areas being redrawn, as scissor rectangles
+    damage: Vec<[u32; 4]>,
+    /// Pixels of the command being rasterized on the CPU, reused between commands
+    scratch: Vec<u8>,
+    vertex_buffer: wgpu::Buffer,
+    vertex_capacity: u64,
+    vertices: Vec<f32>,
+    batches: Vec<Batch>,
+    /// Vertices of a quad over the whole frame in the clear color
+    clear_quad: Range<u32>,
+    /// Vertices of a quad over the whole frame sampling all of a texture
+    frame_quad: Range<u32>,
+}
+
+impl Renderer {
+    fn new(context: &PixelsContext) -> Self {
+        let device = &context.device;
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("wixe quad shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wixe bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wixe pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let blend = blend_state(BlendMode::SourceOver);
+        let pipeline = create_pipeline(device, &pipeline_layout, &shader, blend);
+        let pipelines = HashMap::from([(BlendMode::SourceOver, pipeline)]);
+        let replace = create_pipeline(device, &pipeline_layout, &shader, wgpu::BlendState::REPLACE);
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("wixe uniforms"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = |filter| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("wixe sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+        let linear = sampler(wgpu::FilterMode::Linear);
+        let nearest = sampler(wgpu::FilterMode::Nearest);
+        let vertex_capacity = 4096 * VERTEX_SIZE;
+        let vertex_buffer = create_vertex_buffer(device, vertex_capacity);
+        let texture = |width, height| {
+            let descriptor = texture_descriptor(width, height);
+            create_texture(device, &layout, &uniforms, &linear, &nearest, &descriptor)
+        };
+
+        let renderer = Self {
+            white: texture(1, 1),
+            staging: texture(STAGING_SIZE, STAGING_SIZE),
+            shapes: texture(SHAPE_ATLAS_SIZE, SHAPE_ATLAS_SIZE),
+            shader,
+            pipeline_layout,
+            pipelines,
+            replace,
+            mode: BlendMode::SourceOver,
+            layout,
+            uniforms,
+            linear,
+            nearest,
+            staging_alloc: AtlasAllocator::new(STAGING_SIZE, STAGING_SIZE),
+            shape_alloc: AtlasAllocator::new(SHAPE_ATLAS_SIZE, SHAPE_ATLAS_SIZE),
+            shape_cache: HashMap::new(),
+            shapes_full: false,
+            glyph_pages: Vec::new(),
+            glyph_copy: GlyphAtlasCopy::default(),
+            images: HashMap::new(),
+            frame_count: 0,
+            transient: Vec::new(),
+            frame: None,
+            damage: Vec::new(),
+            scratch: Vec::new(),
+            vertex_buffer,
+            vertex_capacity,
+            vertices: Vec::new(),
+            batches: Vec::new(),
+            clear_quad: 0..0,
+            frame_quad: 0..0,
+        };
+        upload(
+            &context.queue,
+            &renderer.white.texture,
+            Region::new(0, 0, 1, 1),
+            &[255; 4],
+        );
+        renderer
+    }
+
+    fn create_texture(&self, device: &wgpu::Device, width: u32, height: u32) -> Texture {
+        let descriptor = texture_descriptor(width, height);
+        self.create_texture_with(device, &descriptor)
+    }
+
+    fn create_texture_with(
+        &self,
+        device: &wgpu::Device,
+        descriptor: &wgpu::TextureDescriptor,
+    ) -> Texture {
+        create_texture(
+            device,
+            &self.layout,
+            &self.uniforms,
+            &self.linear,
+            &self.nearest,
+            descriptor,
+        )
+    }
+
+    /// Turn the commands of `list` that touch `damage` into vertices and
+    /// batches, uploading the textures they use
+    ///
+    /// A frame of a new size is redrawn whole, whatever `damage` says.
+    fn prepare(
+        &mut self,
+        context: &PixelsContext,
+        list: &DrawList,
+        size: (u32, u32),
+        damage: &[Rect],
+        clear: Color,
+    ) {
+        self.frame_count += 1;
+        if self.shapes_full {
+            self.shape_alloc.clear();
+            self.shape_cache.clear();
+            self.shapes_full = false;
+        }
+        let frame = Rect::new(0.0, 0.0, size.0 as f32, size.1 as f32);
+        let resized = self.frame.as_ref().map(|(_, s)| *s) != Some(size);
+        if resized {
+            let descriptor = wgpu::TextureDescriptor {
+                format: SURFACE_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                ..texture_descriptor(size.0.max(1), size.1.max(1))
+            };
+            let texture = self.create_texture_with(&context.device, &descriptor);
+            self.frame = Some((texture, size));
+        }
+        let damage = match resized {
+            true => std::slice::from_ref(&frame),
+            false => damage,
+        };
+        self.damage = damage.iter().filter_map(|r| scissor(*r, size)).collect();
+
+        // Glyphs placed before the glyph atlas was emptied are gone from it,
+        // so a frame that filled it up is encoded again
+        for _ in 0..2 {
+            let generation = text::glyph_atlas_generation();
+            self.encode_list(context, list, size);
+            if text::glyph_atlas_generation() == generation {
+                break;
+            }
+        }
+        self.copy_glyphs(context);
+        let frame_count = self.frame_count;
+        self.images
+            .retain(|_, image| frame_count - image.last_used < IMAGE_FRAMES);
+
+        let corners = corners(Transform::IDENTITY, frame);
+        let full = Rect::new(0.0, 0.0, 1.0, 1.0);
+        let clear = clear.premultiplied().map(|c| c as f32 / 255.0);
+        self.clear_quad = self.push_quad(corners, full, clear);
+        self.frame_quad = self.push_quad(corners, full, [1.0; 4]);
+
+        let uniforms: Vec<u8> = [size.0 as f32, size.1 as f32, 0.0, 0.0]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        context.queue.write_buffer(&self.uniforms, 0, &uniforms);
+
+        let bytes: Vec<u8> = self.vertices.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        if bytes.len() as u64 > self.vertex_capacity {
+            self.vertex_capacity = (bytes.len() as u64).next_power_of_two();
+            self.vertex_buffer = create_vertex_buffer(&context.device, self.vertex_capacity);
+        }
+        context.queue.write_buffer(&self.vertex_buffer, 0, &bytes);
+    }
+
+    fn encode_list(&mut self, context: &PixelsContext, list: &DrawList, size: (u32, u32)) {
+        self.vertices.clear();
+        self.batches.clear();
+        self.transient.clear();
+        self.staging_alloc.clear();
+
+        let frame = Rect::new(0.0, 0.0, size.0 as f32, size.1 as f32);
+        let mut state = CommandState::new(frame);
+        let mut group: Option<PendingGroup> = None;
+        list.visit(|cmd| {
+            if let Some(g) = &mut group {
+                match cmd {
+                    DrawCmd::PushOpacity(_) => g.depth += 1,
+                    DrawCmd::PopOpacity if g.depth == 0 => {
+                        if let Some(g) = group.take() {
+                            self.set_mode(context, state.blend_mode());
+                            self.encode_group(context, g, size);
+                        }
+                        return;
+                    }
+                    DrawCmd::PopOpacity => g.depth -= 1,
+                    _ => {}
+                }
+                g.cmds.push(cmd.clone());
+                return;
+            }
+            if let DrawCmd::PushOpacity(opacity) = cmd {
+                group = Some(PendingGroup {
+                    opacity: *opacity,
+                    depth: 0,
+                    cmds: Vec::new(),
+                    transform: state.transform(),
+                    clip: state.clip(),
+                });
+                return;
+            }
+            if state.update(cmd) {
+                return;
+            }
+            let (Some(clip), Some(area)) = (state.clip(), state.visible_area(cmd)) else {
+                return;
+            };
+            if let Some(scissor) = scissor(clip, size)
+                && self.damaged(area, size)
+            {
+                self.set_mode(context, state.blend_mode());
+                self.encode(context, cmd, state.transform(), area, scissor);
+            }
+        });
+    }
+
+    /// Whether frame area `area` is being redrawn
+    fn damaged(&self, area: Rect, size: (u32, u32)) -> bool {
+        scissor(area, size)
+            .is_some_and(|area| self.damage.iter().any(|d| overlap(area, *d).is_some()))
+    }
+
+    fn encode(
+        &mut self,
+        context: &PixelsContext,
+        cmd: &DrawCmd,
+        t: Transform,
+        area: Rect,
+        scissor: [u32; 4],
+    ) {
+        let full = Rect::new(0.0, 0.0, 1.0, 1.0);
+        match cmd {
+            DrawCmd::FillRect {
+                rect,
+                brush: Brush::Solid(color),
+            } => {
+                let corners = corners(t, *rect);
+                let color = premultiplied(*color);
+                self.quad(
+                    Source::White,
+                    FilterMode::Nearest,
+                    scissor,
+                    corners,
+                    full,
+                    color,
+                );
+            }
+            DrawCmd::Image {
+                bitmap,
+                dst,
+                filter,
+                opacity,
+            } if fits(bitmap) => {
+                let source = self.image_source(context, bitmap);
+                let color = [*opacity; 4];
+                self.quad(source, *filter, scissor, corners(t, *dst), full, color);
+            }
+            DrawCmd::ImageRegion {
+                bitmap,
+                src,
+                dst,
+                filter,
+            } if fits(bitmap) => {
+                let (bw, bh) = (bitmap.width() as f32, bitmap.height() as f32);
+                let uv = Rect::new(
+                    src.x as f32 / bw,
+                    src.y as f32 / bh,
+                    src.width as f32 / bw,
+                    src.height as f32 / bh,
+                );
+                let source = self.image_source(context, bitmap);
+                self.quad(source, *filter, scissor, corners(t, *dst), uv, [1.0; 4]);
+            }
+            DrawCmd::Text { .. } | DrawCmd::TextLayout { .. } => {
+                self.encode_text(context, cmd, t, scissor)
+            }
+            DrawCmd::FillRect { .. }
+            | DrawCmd::FillRoundedRect { .. }
+            | DrawCmd::FillPath { .. }
+            | DrawCmd::StrokePath { .. }
+            | DrawCmd::Shadow { .. } => self.encode_shape(context, cmd, t, area, scissor),
+            _ => self.rasterize(context, area, scissor, 1.0, |p| {
+                p.with_transform(t, |p| cmd.apply(p))
+            }),
+        }
+    }
+
+    /// Lay out a text command and draw each glyph, decoration and color glyph
+    /// as a quad
+    fn encode_text(
+        &mut self,
+        context: &PixelsContext,
+        cmd: &DrawCmd,
+        t: Transform,
+        scissor: [u32; 4],
+    ) {
+        let mut pixel = [0; 4];
+        let mut painter = Painter::new(&mut pixel, 1, 1);
+        painter.record_text();
+        painter.with_transform(t, |p| cmd.apply(p));
+        let full = Rect::new(0.0, 0.0, 1.0, 1.0);
+        for draw in painter.take_text_draws() {
+            match draw {
+                TextDraw::Glyph {
+                    page,
+                    uv,
+                    rect,
+                    color,
+                    transform,
+                } => {
+                    // Under a translation glyphs land on whole pixels
+                    let filter = match transform.is_translation() {
+                        true => FilterMode::Nearest,
+                        false => FilterMode::Bilinear,
+                    };
+                    let corners = corners(transform, rect);
+                    let color = premultiplied(color);
+                    self.quad(Source::Glyphs(page), filter, scissor, corners, uv, color);
+                }
+                TextDraw::Rect {
+                    rect,
+                    color,
+                    transform,
+                } => {
+                    let corners = corners(transform, rect);
+                    let color = premultiplied(color);
+                    self.quad(
+                        Source::White,
+                        FilterMode::Nearest,
+                        scissor,
+                        corners,
+                        full,
+                        color,
+                    );
+                }
+                TextDraw::Image {
+                    bitmap,
+                    rect,
+                    opacity,
+                    transform,
+                } if fits(&bitmap) => {
+                    let source = self.image_source(context, &bitmap);
+                    let corners = corners(transform, rect);
+                    let filter = FilterMode::Bilinear;
+                    self.quad(source, filter, scissor, corners, full, [opacity; 4]);
+                }
+                TextDraw::Image { .. } => {}
+            }
+        }
+    }
+
+    /// Draw a shape from the shape atlas, rasterizing it there the first time
+    /// it is drawn under this transform
+    fn encode_shape(
+        &mut self,
+        context: &PixelsContext,
+        cmd: &DrawCmd,
+        t: Transform,
+        area: Rect,
+        scissor: [u32; 4],
+    ) {
+        let Some(bounds) = cmd.bounds() else {
+            return;
+        };
+        let rect = t.map_rect(bounds).round_out();
+        let (w, h) = (rect.width as u32, rect.height as u32);
+        if w > MAX_CACHED_SHAPE || h > MAX_CACHED_SHAPE {
+            // Only the visible part of a big shape is drawn, whenever it is damaged
+            self.rasterize(context, area, scissor, 1.0, |p| {
+                p.with_transform(t, |p| cmd.apply(p))
+            });
+            return;
+        }
+        let key = ShapeKey::new(t, bounds);
+        let cached = (self.shape_cache.get(&key))
+            .and_then(|shapes| shapes.iter().find(|s| same_shape(&s.cmd, cmd)))
+            .map(|shape| shape.region);
+        let region = match cached {
+            Some(region) => region,
+            None => {
+                let drawn = self.paint(rect, |p| p.with_transform(t, |p| cmd.apply(p)));
+                let region = match drawn {
+                    true => match self.shape_alloc.allocate(w, h) {
+                        Some(region) => Some(region),
+                        None => {
+                            // Shown this once, the atlas starts over on the next frame
+                            self.shapes_full = true;
+                            self.place_scratch(context, rect, scissor, 1.0);
+                            return;
+                        }
+                    },
+                    false => None,
+                };
+                if let Some(region) = region {
+                    upload(&context.queue, &self.shapes.texture, region, &self.scratch);
+                }
+                let shape = CachedShape {
+                    cmd: cmd.clone(),
+                    region,
+                };
+                self.shape_cache.entry(key).or_default().push(shape);
+                region
+            }
+        };
+        if let Some(region) = region {
+            let uv = region_uv(region, SHAPE_ATLAS_SIZE, SHAPE_ATLAS_SIZE);
+            let corners = corners(Transform::IDENTITY, rect);
+            self.quad(
+                Source::Shapes,
+                FilterMode::Nearest,
+                scissor,
+                corners,
+                uv,
+                [1.0; 4],
+            );
+        }
+    }
+
+    /// Rasterize the whole group over its clip and fade the result as one quad
+    fn encode_group(&mut self, context: &PixelsContext, group: PendingGroup, size: (u32, u32)) {
+        let Some(area) = group.clip else {
+            return;
+        };
+        let Some(scissor) = scissor(area, size) else {
+            return;
+        };
+        if !self.damaged(area, size) {
+            return;
+        }
+        self.rasterize(context, area, scissor, group.opacity, |p| {
+            p.push_transform(group.transform);
+            for cmd in &group.cmds {
+                cmd.apply(p);
+            }
+        });
+    }
+
+    /// Draw with the software painter over the frame pixels in `area` and show
+    /// the result as a quad faded to `opacity`
+    fn rasterize(
+        &mut self,
+        context: &PixelsContext,
+        area: Rect,
+        scissor: [u32; 4],
+        opacity: f32,
+        draw: impl FnOnce(&mut Painter),
+    ) {
+        if self.paint(area, draw) {
+            self.place_scratch(context, area, scissor, opacity);
+        }
+    }
+
+    /// Draw with the software painter over the frame pixels in `area`, into
+    /// the scratch buffer; `false` if nothing was drawn
+    fn paint(&mut self, area: Rect, draw: impl FnOnce(&mut Painter)) -> bool {
+        let (w, h) = (area.width as u32, area.height as u32);
+        if w == 0 || h == 0 || w > MAX_TEXTURE_SIZE || h > MAX_TEXTURE_SIZE {
+            return false;
+        }
+        self.scratch.clear();
+        self.scratch.resize((w * h * 4) as usize, 0);
+        let mut painter = Painter::new(&mut self.scratch, w, h);
+        painter.push_transform(Transform::translate(-area.x, -area.y));
+        draw(&mut painter);
+        self.scratch.iter().any(|&b| b != 0)
+    }
+
+    /// Upload the scratch buffer painted over `area` for this frame only and
+    /// show it as a quad faded to `opacity`
+    fn place_scratch(
+        &mut self,
+        context: &PixelsContext,
+        area: Rect,
+        scissor: [u32; 4],
+        opacity: f32,
+    ) {
+        let (w, h) = (area.width as u32, area.height as u32);
+        let (source, region, tw, th) = match self.staging_alloc.allocate(w, h) {
+            Some(region) => (Source::Staging, region, STAGING_SIZE, STAGING_SIZE),
+            None => {
+                let texture = self.create_texture(&context.device, w, h);
+                self.transient.push(texture);
+                let source = Source::Transient(self.transient.len() - 1);
+                (source, Region::new(0, 0, w, h), w, h)
+            }
+        };
+        if let Some(texture) = self.texture(source) {
+            upload(&context.queue, &texture.texture, region, &self.scratch);
+        }
+        let uv = region_uv(region, tw, th);
+        let corners = corners(Transform::IDENTITY, area);
+        let color = [opacity.clamp(0.0, 1.0); 4];
+        self.quad(source, FilterMode::Nearest, scissor, corners, uv, color);
+    }
+
+    /// Encode the following commands with `mode`, creating its pipeline if needed
+    fn set_mode(&mut self, context: &PixelsContext, mode: BlendMode) {
+        if !self.pipelines.contains_key(&mode) {
+            let pipeline = create_pipeline(
+                &context.device,
+                &self.pipeline_layout,
+                &self.shader,
+                blend_state(mode),
+            );
+            self.pipelines.insert(mode, pipeline);
+        }
+        self.mode = mode;
+    }
+
+    /// Texture holding `bitmap`, uploaded the first time it is drawn
+    fn image_source(&mut self, context: &PixelsContext, bitmap: &Bitmap) -> Source {
+        let id = bitmap.id();
+        if !self.images.contains_key(&id) {
+            let texture = self.create_texture(&context.device, bitmap.width(), bitmap.height());
+            let region = Region::new(0, 0, bitmap.width(), bitmap.height());
+            upload(&context.queue, &texture.texture, region, bitmap.pixels());
+            self.images.insert(
+                id,
+                CachedImage {
+                    _bitmap: bitmap.clone(),
+                    texture,
+                    last_used: 0,
+                },
+            );
+        }
+        if let Some(image) = self.images.get_mut(&id) {
+            image.last_used = self.frame_count;
+        }
+        Source::Image(id)
+    }
+
+    /// Bring the copy of the glyph atlas up to date with the glyphs added to it
+    fn copy_glyphs(&mut self, context: &PixelsContext) {
+        let mut copy = std::mem::take(&mut self.glyph_copy);
+        text::copy_glyph_atlas(&mut copy, |glyph| {
+            if self.glyph_pages.len() <= glyph.page {
+                self.glyph_pages.resize_with(glyph.page + 1, || None);
+            }
+            // A page created since the last copy starts over from an empty texture
+            if glyph.new_page || self.glyph_pages[glyph.page].is_none() {
+                let texture = self.create_texture(&context.device, glyph.side, glyph.side);
+                self.glyph_pages[glyph.page] = Some(texture);
+            }
+            if let Some(texture) = &self.glyph_pages[glyph.page] {
+                upload(&context.queue, &texture.texture, glyph.region, glyph.rgba);
+            }
+        });
+        self.glyph_copy = copy;
+    }
+
+    /// Append two triangles covering `corners`, given clockwise from the top-left
+    fn quad(
+        &mut self,
+        source: Source,
+        filter: FilterMode,
+        scissor: [u32; 4],
+        corners: [Point; 4],
+        uv: Rect,
+        color: [f32; 4],
+    ) {
+        let vertices = self.push_quad(corners, uv, color);
+        if let Some(last) = self.batches.last_mut()
+            && last.source == source
+            && last.filter == filter
+            && last.scissor == scissor
+            && last.mode == self.mode
+        {
+            last.vertices.end = vertices.end;
+            return;
+        }
+        self.batches.push(Batch {
+            source,
+            filter,
+            scissor,
+            mode: self.mode,
+            vertices,
+        });
+    }
+
+    /// Append the vertices of a quad without drawing it in any batch
+    fn push_quad(&mut self, corners: [Point; 4], uv: Rect, color: [f32; 4]) -> Range<u32> {
+        let uvs = [
+            Point::new(uv.min_x(), uv.min_y()),
+            Point::new(uv.max_x(), uv.min_y()),
+            Point::new(uv.max_x(), uv.max_y()),
+            Point::new(uv.min_x(), uv.max_y()),
+        ];
+        let start = (self.vertices.len() / VERTEX_FLOATS) as u32;
+        for i in [0, 1, 2, 0, 2, 3] {
+            let (p, uv) = (corners[i], uvs[i]);
+            self.vertices.extend_from_slice(&[p.x, p.y, uv.x, uv.y]);
+            self.vertices.extend_from_slice(&color);
+        }
+        start..start + 6
+    }
+
+    fn texture(&self, source: Source) -> Option<&Texture> {
+        match source {
+            Source::White => Some(&self.white),
+            Source::Staging => Some(&self.staging),
+            Source::Shapes => Some(&self.shapes),
+            Source::Glyphs(page) => self.glyph_pages.get(page)?.as_ref(),
+            Source::Image(id) => self.images.get(&id).map(|image| &image.texture),
+            Source::Transient(index) => self.transient.get(index),
+        }
+    }
+
+    /// Redraw the damage in the frame texture, then show the frame in
+    /// `viewport` of `target`, sampled with `filter`
+    fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        viewport: Rect,
+        filter: FilterMode,
+        clear: wgpu::Color,
+    ) {
+        let Some((frame, _)) = &self.frame else {
+            return;
+        };
+        if !self.damage.is_empty() {
+            let view = frame.texture.create_view(&Default::default());
+            let mut pass = begin_pass(encoder, &view, wgpu::LoadOp::Load);
+            pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            for &damage in &self.damage {
+                let [x, y, w, h] = damage;
+                pass.set_scissor_rect(x, y, w, h);
+                pass.set_pipeline(&self.replace);
+                pass.set_bind_group(0, self.white.bind_group(FilterMode::Nearest), &[]);
+                pass.draw(self.clear_quad.clone(), 0..1);
+                let mut mode = None;
+                for batch in &self.batches {
+                    let (Some(texture), Some(pipeline), Some([x, y, w, h])) = (
+                        self.texture(batch.source),
+                        self.pipelines.get(&batch.mode),
+                        overlap(batch.scissor, damage),
+                    ) else {
+                        continue;
+                    };
+                    if mode != Some(batch.mode) {
+                        pass.set_pipeline(pipeline);
+                        mode = Some(batch.mode);
+                    }
+                    pass.set_bind_group(0, texture.bind_group(batch.filter), &[]);
+                    pass.set_scissor_rect(x, y, w, h);
+                    pass.draw(batch.vertices.clone(), 0..1);
+                }
+            }
+        }
+
+        let mut pass = begin_pass(encoder, target, wgpu::LoadOp::Clear(clear));
+        let v = viewport;
+        if v.width < 1.0 || v.height < 1.0 {
+            return;
+        }
+        pass.set_viewport(v.x, v.y, v.width, v.height, 0.0, 1.0);
+        pass.set_pipeline(&self.replace);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_bind_group(0, frame.bind_group(filter), &[]);
+        pass.draw(self.frame_quad.clone(), 0..1);
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    blend: wgpu::BlendState,
+) -> wgpu::RenderPipeline {
+    let attributes = [
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x2,
+            offset: 0,
+            shader_location: 0,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x2,
+            offset: 8,
+            shader_location: 1,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x4,
+            offset: 16,
+            shader_location: 2,
+        },
+    ];
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("wixe quad pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: VERTEX_SIZE,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &attributes,
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: SURFACE_FORMAT,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// Fixed-function equivalent of `mode` for premultiplied colors
+///
+/// Multiply is exact over an opaque frame, which a cleared frame always is;
+/// darken and lighten only for opaque colors.
+fn blend_state(mode: BlendMode) -> wgpu::BlendState {
+    use wgpu::BlendFactor as F;
+    let component = |src_factor, dst_factor| wgpu::BlendComponent {
+        src_factor,
+        dst_factor,
+        operation: wgpu::BlendOperation::Add,
+    };
+    let extreme = |operation| wgpu::BlendComponent {
+        src_factor: F::One,
+        dst_factor: F::One,
+        operation,
+    };
+    let over = component(F::One, F::OneMinusSrcAlpha);
+    let (color, alpha) = match mode {
+        BlendMode::SourceOver => (over, over),
+        BlendMode::DestinationOver => {
+            let c = component(F::OneMinusDstAlpha, F::One);
+            (c, c)
+        }
+        BlendMode::SourceAtop => {
+            let c = component(F::DstAlpha, F::OneMinusSrcAlpha);
+            (c, c)
+        }
+        BlendMode::DestinationOut => {
+            let c = component(F::Zero, F::OneMinusSrcAlpha);
+            (c, c)
+        }
+        BlendMode::Xor => {
+            let c = component(F::OneMinusDstAlpha, F::OneMinusSrcAlpha);
+            (c, c)
+        }
+        BlendMode::Plus => {
+            let c = component(F::One, F::One);
+            (c, c)
+        }
+        BlendMode::Multiply => (component(F::Dst, F::OneMinusSrcAlpha), over),
+        BlendMode::Screen => (component(F::One, F::OneMinusSrc), over),
+        BlendMode::Darken => (extreme(wgpu::BlendOperation::Min), over),
+        BlendMode::Lighten => (extreme(wgpu::BlendOperation::Max), over),
+    };
+    wgpu::BlendState { color, alpha }
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wixe vertices"),
+        size,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// An RGBA texture that is sampled and uploaded to
+fn texture_descriptor(width: u32, height: u32) -> wgpu::TextureDescriptor<'static> {
+    wgpu::TextureDescriptor {
+        label: Some("wixe texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    }
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniforms: &wgpu::Buffer,
+    linear: &wgpu::Sampler,
+    nearest: &wgpu::Sampler,
+    descriptor: &wgpu::TextureDescriptor,
+) -> Texture {
+    let texture = device.create_texture(descriptor);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = |sampler| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wixe bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    };
+    Texture {
+        linear: bind_group(linear),
+        nearest: bind_group(nearest),
+        texture,
+    }
+}
+
+/// Copy premultiplied RGBA rows into `region` of `texture`
+fn upload(queue: &wgpu::Queue, texture: &wgpu::Texture, region: Region, pixels: &[u8]) {
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: region.x,
+                y: region.y,
+                z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        pixels,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: NonZeroU32::new(region.width * 4),
+            rows_per_image: NonZeroU32::new(region.height),
+        },
+        wgpu::Extent3d {
+            width: region.width,
+            height: region.height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+fn begin_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("wixe render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations { load, store: true },
+        })],
+        depth_stencil_attachment: None,
+    })
+}
+
+fn fits(bitmap: &Bitmap) -> bool {
+    let (w, h) = (bitmap.width(), bitmap.height());
+    w > 0 && h > 0 && w <= MAX_TEXTURE_SIZE && h <= MAX_TEXTURE_SIZE
+}
+
+fn corners(t: Transform, rect: Rect) -> [Point; 4] {
+    [
+        t.apply(Point::new(rect.min_x(), rect.min_y())),
+        t.apply(Point::new(rect.max_x(), rect.min_y())),
+        t.apply(Point::new(rect.max_x(), rect.max_y())),
+        t.apply(Point::new(rect.min_x(), rect.max_y())),
+    ]
+}
+
+/// Texture coordinates of `region` in a texture of `width` by `height`
+fn region_uv(region: Region, width: u32, height: u32) -> Rect {
+    let (w, h) = (width as f32, height as f32);
+    Rect::new(
+        region.x as f32 / w,
+        region.y as f32 / h,
+        region.width as f32 / w,
+        region.height as f32 / h,
+    )
+}
+
+fn premultiplied(color: Color) -> [f32; 4] {
+    color.premultiplied().map(|c| c as f32 / 255.0)
+}
+
+/// Whether two shape commands rasterize the same under the same transform
+fn same_shape(a: &DrawCmd, b: &DrawCmd) -> bool {
+    match (a, b) {
+        (
+            DrawCmd::FillRect { rect, brush },
+            DrawCmd::FillRect {
+                rect: rect2,
+                brush: brush2,
+            },
+        ) => rect == rect2 && brush == brush2,
+        (
+            DrawCmd::FillRoundedRect {
+                rect,
+                radius,
+                brush,
+            },
+            DrawCmd::FillRoundedRect {
+                rect: rect2,
+                radius: radius2,
+                brush: brush2,
+            },
+        ) => rect == rect2 && radius == radius2 && brush == brush2,
+        (
+            DrawCmd::FillPath { path, brush },
+            DrawCmd::FillPath {
+                path: path2,
+                brush: brush2,
+            },
+        ) => path == path2 && brush == brush2,
+        (
+            DrawCmd::StrokePath {
+                path,
+                stroke,
+                brush,
+            },
+            DrawCmd::StrokePath {
+                path: path2,
+                stroke: stroke2,
+                brush: brush2,
+            },
+        ) => path == path2 && stroke == stroke2 && brush == brush2,
+        (
+            DrawCmd::Shadow {
+                rect,
+                radius,
+                shadow,
+            },
+            DrawCmd::Shadow {
+                rect: rect2,
+                radius: radius2,
+                shadow: shadow2,
+            },
+        ) => rect == rect2 && radius == radius2 && shadow == shadow2,
+        _ => false,
+    }
+}
+
+/// A frame area as a scissor rectangle within the frame, `None` when nothing is left
+fn scissor(area: Rect, size: (u32, u32)) -> Option<[u32; 4]> {
+    let x = |v: f32| v.round().clamp(0.0, size.0 as f32) as u32;
+    let y = |v: f32| v.round().clamp(0.0, size.1 as f32) as u32;
+    let (x0, y0, x1, y1) = (
+        x(area.min_x()),
+        y(area.min_y()),
+        x(area.max_x()),
+        y(area.max_y()),
+    );
+    (x1 > x0 && y1 > y0).then(|| [x0, y0, x1 - x0, y1 - y0])
+}
+
+/// Where two scissor rectangles overlap, `None` if they do not
+fn overlap(a: [u32; 4], b: [u32; 4]) -> Option<[u32; 4]> {
+    let (x0, y0) = (a[0].max(b[0]), a[1].max(b[1]));
+    let (x1, y1) = (
+        (a[0] + a[2]).min(b[0] + b[2]),
+        (a[1] + a[3]).min(b[1] + b[3]),
+    );
+    (x1 > x0 && y1 > y0).then(|| [x0, y0, x1 - x0, y1 - y0])
+}
+
+/// wgpu rendering of the frame, scaled into the window
+pub struct GpuBackend {
+    pixels: Pixels,
+    renderer: Renderer,
+    width: u32,
+    height: u32,
+    surface: (u32, u32),
+    scaling: Scaling,
+    clear_color: Color,
+    /// What was presented last, kept to rebuild the frame on the CPU for captures
+    last: DrawList,
+    post_process: Option<PostProcess>,
+    /// Frame last presented after post-processing
+    processed: Option<Bitmap>,
+    /// Set when the kept frame no longer matches what was drawn, to redraw it whole
+    redraw: bool,
+}
+
+impl GpuBackend {
+    pub fn new(
+        window: &Window,
+        width: u32,
+        height: u32,
+        clear_color: Color,
+        present_mode: PresentMode,
+    ) -> Result<Self, Error> {
+        let window_size = window.inner_size();
+        let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, window);
+        // Only the device and surface of `pixels` are used, not its frame buffer
+        let pixels = PixelsBuilder::new(1, 1, surface_texture)
+            .surface_texture_format(SURFACE_FORMAT)
+            .present_mode(present_mode.to_wgpu())
+            .build()?;
+        let renderer = Renderer::new(pixels.context());
+        Ok(Self {
+            pixels,
+            renderer,
+            width,
+            height,
+            surface: (window_size.width, window_size.height),
+            scaling: Scaling::default(),
+            clear_color,
+            last: DrawList::new(),
+            post_process: None,
+            processed: None,
+            redraw: true,
+        })
+    }
+}
+
+impl RenderBackend for GpuBackend {
+    fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.pixels.resize_surface(width, height);
+        self.surface = (width, height);
+        Ok(())
+    }
+
+    fn resize_buffer(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        (self.width, self.height) = (width, height);
+        Ok(())
+    }
+
+    /// With a post-processing hook the frame is drawn in software, so the hook
+    /// can see it, and presented whole as a single image.
+    fn render(&mut self, list: &DrawList, damage: &[Rect]) -> Result<(), Error> {
+        let size = (self.width, self.height);
+        let viewport = frame_viewport(size, self.surface, self.scaling);
+        let full = [Rect::new(0.0, 0.0, self.width as f32, self.height as f32)];
+        let damage = match self.redraw || self.post_process.is_some() {
+            true => &full[..],
+            false => damage,
+        };
+        self.redraw = false;
+        self.last.clone_from(list);
+        if let Some(hook) = &mut self.post_process {
+            let mut target = RenderTarget::new(self.width, self.height);
+            target.painter().clear(self.clear_color);
+            target.render(list);
+            let mut pixels = target.pixels().to_vec();
+            hook(&mut pixels, self.width, self.height);
+            let frame = Bitmap::from_premultiplied(self.width, self.height, pixels);
+            let mut processed = DrawList::new();
+            let dst = Rect::new(0.0, 0.0, self.width as f32, self.height as f32);
+            processed.layer(Layer::Background).draw_image_filtered(
+                &frame,
+                dst,
+                FilterMode::Nearest,
+            );
+            let context = self.pixels.context();
+            self.renderer
+                .prepare(context, &processed, size, damage, self.clear_color);
+            self.processed = Some(frame);
+        } else {
+            let context = self.pixels.context();
+            self.renderer
+                .prepare(context, list, size, damage, self.clear_color);
+            self.processed = None;
+        }
+        let [r, g, b, a] = self.clear_color.premultiplied().map(|c| c as f64 / 255.0);
+        let clear = wgpu::Color { r, g, b, a };
+        // Pixel-perfect scaling samples whole texels
+        let filter = match self.scaling {
+            Scaling::Integer => FilterMode::Nearest,
+            Scaling::Fit => FilterMode::Bilinear,
+        };
+        let renderer = &self.renderer;
+        self.pixels.render_with(|encoder, target, _| {
+            renderer.draw(encoder, target, viewport, filter, clear);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn frame_position(&self, x: f32, y: f32) -> Point {
+        let size = (self.width, self.height);
+        let viewport = frame_viewport(size, self.surface, self.scaling);
+        backend::viewport_position(viewport, size, x, y)
+    }
+
+    /// Reading back the swap chain is not portable, so the frame is replayed in software
+    fn capture(&mut self) -> Bitmap {
+        if let Some(frame) = &self.processed {
+            return frame.clone();
+        }
+        let mut target = RenderTarget::new(self.width, self.height);
+        target.painter().clear(self.clear_color);
+        target.render(&self.last);
+        target.to_bitmap()
+    }
+
+    fn set_post_process(&mut self, hook: Option<PostProcess>) {
+        self.post_process = hook;
+        self.redraw = true;
+    }
+
+    fn set_clear_color(&mut self, color: Color) {
+        self.redraw |= color != self.clear_color;
+        self.clear_color = color;
+    }
+
+    fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+    }
+}
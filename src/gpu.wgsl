@@ -0,0 +1,33 @@
+struct Screen {
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> screen: Screen;
+@group(0) @binding(1) var tex: texture_2d<f32>;
+@group(0) @binding(2) var samp: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) pos: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let ndc = pos / screen.size * 2.0 - vec2<f32>(1.0, 1.0);
+    out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.uv = uv;
+    out.color = color;
+    return out;
+}
+
+// Texels and colors are both premultiplied
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(tex, samp, in.uv) * in.color;
+}
@@ -0,0 +1,209 @@
+use std::any::Any;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver};
+
+use global_hotkey::hotkey::{Code, HotKey, Modifiers as HotKeyModifiers};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use winit::event_loop::EventLoopProxy;
+
+use crate::error::Error;
+use crate::keyboard::Key;
+use crate::shortcut::{Shortcut, ShortcutEvent};
+
+struct Entry {
+    hotkey: HotKey,
+    shortcut: Shortcut,
+    action: Rc<dyn Any>,
+}
+
+/// System-wide hotkeys of the application, which work even while another
+/// application has focus, e.g.
+/// `ctx.hotkeys_mut().register("Ctrl+Shift+Space", Msg::ShowLauncher)`
+///
+/// A pressed hotkey is delivered through `Application::hotkey`. Hotkeys are
+/// released when the application exits.
+#[derive(Default)]
+pub struct Hotkeys {
+    /// Opened with the first hotkey registered
+    manager: Option<GlobalHotKeyManager>,
+    entries: Vec<Entry>,
+    /// Ids of hotkeys pressed, sent from the OS's thread
+    pressed: Option<Receiver<u32>>,
+}
+
+impl Hotkeys {
+    /// Receive hotkeys, waking the event loop through `proxy` when one is
+    /// pressed
+    pub(crate) fn listen(&mut self, proxy: EventLoopProxy<()>) {
+        let (sender, receiver) = mpsc::channel();
+        let proxy = Mutex::new(proxy);
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            let pressed = event.state == HotKeyState::Pressed;
+            if pressed && sender.send(event.id).is_ok() {
+                // Wake the event loop up to deliver it
+                let _ = proxy.lock().map(|proxy| proxy.send_event(()));
+            }
+        }));
+        self.pressed = Some(receiver);
+    }
+
+    /// Run `action` on `shortcut` wherever focus is, in any application
+    ///
+    /// Fails if the text is no shortcut, the key has no hotkey equivalent,
+    /// or the OS refuses it, e.g. because another application holds it.
+    pub fn register(&mut self, shortcut: &str, action: impl Any) -> Result<(), Error> {
+        let shortcut: Shortcut = shortcut.parse()?;
+        let code =
+            code(shortcut.key).ok_or_else(|| Error::InvalidShortcut(shortcut.to_string()))?;
+        let hotkey = HotKey::new(Some(modifiers(shortcut)), code);
+        if self.entries.iter().any(|e| e.hotkey == hotkey) {
+            return Err(Error::ShortcutConflict(shortcut.to_string()));
+        }
+        let manager = match &mut self.manager {
+            Some(manager) => manager,
+            manager => manager.insert(GlobalHotKeyManager::new()?),
+        };
+        manager.register(hotkey)?;
+        self.entries.push(Entry {
+            hotkey,
+            shortcut,
+            action: Rc::new(action),
+        });
+        Ok(())
+    }
+
+    /// Remove every hotkey running `action`, giving it back to the OS
+    pub fn unregister<T: Any + PartialEq>(&mut self, action: &T) {
+        let manager = self.manager.as_ref();
+        self.entries.retain(|e| {
+            let keep = e.action.downcast_ref::<T>() != Some(action);
+            if let Some(manager) = manager.filter(|_| !keep) {
+                let _ = manager.unregister(e.hotkey);
+            }
+            keep
+        });
+    }
+
+    /// Hotkeys pressed since the last poll
+    pub(crate) fn poll(&mut self) -> Vec<ShortcutEvent> {
+        let Some(pressed) = &self.pressed else {
+            return Vec::new();
+        };
+        let ids: Vec<u32> = pressed.try_iter().collect();
+        ids.iter()
+            .filter_map(|&id| self.entries.iter().find(|e| e.hotkey.id() == id))
+            .map(|e| ShortcutEvent::new(e.shortcut, None, e.action.clone()))
+            .collect()
+    }
+}
+
+fn modifiers(shortcut: Shortcut) -> HotKeyModifiers {
+    let m = shortcut.modifiers;
+    let mut modifiers = HotKeyModifiers::empty();
+    modifiers.set(HotKeyModifiers::SHIFT, m.shift);
+    modifiers.set(HotKeyModifiers::CONTROL, m.ctrl);
+    modifiers.set(HotKeyModifiers::ALT, m.alt);
+    modifiers.set(HotKeyModifiers::SUPER, m.logo);
+    modifiers
+}
+
+/// Physical key a hotkey is registered for, on a US layout
+fn code(key: Key) -> Option<Code> {
+    let code = match key {
+        Key::Character(c) => match c {
+            'a' => Code::KeyA,
+            'b' => Code::KeyB,
+            'c' => Code::KeyC,
+            'd' => Code::KeyD,
+            'e' => Code::KeyE,
+            'f' => Code::KeyF,
+            'g' => Code::KeyG,
+            'h' => Code::KeyH,
+            'i' => Code::KeyI,
+            'j' => Code::KeyJ,
+            'k' => Code::KeyK,
+            'l' => Code::KeyL,
+            'm' => Code::KeyM,
+            'n' => Code::KeyN,
+            'o' => Code::KeyO,
+            'p' => Code::KeyP,
+            'q' => Code::KeyQ,
+            'r' => Code::KeyR,
+            's' => Code::KeyS,
+            't' => Code::KeyT,
+            'u' => Code::KeyU,
+            'v' => Code::KeyV,
+            'w' => Code::KeyW,
+            'x' => Code::KeyX,
+            'y' => Code::KeyY,
+            'z' => Code::KeyZ,
+            '0' => Code::Digit0,
+            '1' => Code::Digit1,
+            '2' => Code::Digit2,
+            '3' => Code::Digit3,
+            '4' => Code::Digit4,
+            '5' => Code::Digit5,
+            '6' => Code::Digit6,
+            '7' => Code::Digit7,
+            '8' => Code::Digit8,
+            '9' => Code::Digit9,
+            '-' => Code::Minus,
+            '=' => Code::Equal,
+            '[' => Code::BracketLeft,
+            ']' => Code::BracketRight,
+            '\\' => Code::Backslash,
+            ';' => Code::Semicolon,
+            '\'' => Code::Quote,
+            '`' => Code::Backquote,
+            ',' => Code::Comma,
+            '.' => Code::Period,
+            '/' => Code::Slash,
+            _ => return None,
+        },
+        Key::Enter => Code::Enter,
+        Key::Tab => Code::Tab,
+        Key::Space => Code::Space,
+        Key::Backspace => Code::Backspace,
+        Key::Delete => Code::Delete,
+        Key::Insert => Code::Insert,
+        Key::Escape => Code::Escape,
+        Key::ArrowLeft => Code::ArrowLeft,
+        Key::ArrowRight => Code::ArrowRight,
+        Key::ArrowUp => Code::ArrowUp,
+        Key::ArrowDown => Code::ArrowDown,
+        Key::Home => Code::Home,
+        Key::End => Code::End,
+        Key::PageUp => Code::PageUp,
+        Key::PageDown => Code::PageDown,
+        Key::F(n) => match n {
+            1 => Code::F1,
+            2 => Code::F2,
+            3 => Code::F3,
+            4 => Code::F4,
+            5 => Code::F5,
+            6 => Code::F6,
+            7 => Code::F7,
+            8 => Code::F8,
+            9 => Code::F9,
+            10 => Code::F10,
+            11 => Code::F11,
+            12 => Code::F12,
+            13 => Code::F13,
+            14 => Code::F14,
+            15 => Code::F15,
+            16 => Code::F16,
+            17 => Code::F17,
+            18 => Code::F18,
+            19 => Code::F19,
+            20 => Code::F20,
+            21 => Code::F21,
+            22 => Code::F22,
+            23 => Code::F23,
+            24 => Code::F24,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(code)
+}
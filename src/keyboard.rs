@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use winit::event::{ModifiersState, VirtualKeyCode as Vk};
+
+use crate::geometry::Point;
+use crate::widget::WidgetId;
+
+/// What a key means in the current keyboard layout
+///
+/// Typed text arrives separately through `Application::text_input`, so match
+/// keys for commands, e.g. `Key::Character('s')` with `Modifiers::command`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A letter, digit or punctuation key, lowercase and unshifted
+    Character(char),
+    Enter,
+    Tab,
+    Space,
+    Backspace,
+    Delete,
+    Insert,
+    Escape,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// A function key, `F(1)` to `F(24)`
+    F(u8),
+    Shift,
+    Control,
+    Alt,
+    /// The Windows key, or Command on macOS
+    Super,
+    /// A key with no meaning in this model, told apart by its `KeyCode`
+    Unidentified,
+}
+
+impl From<Vk> for Key {
+    fn from(key: Vk) -> Self {
+        use Key::*;
+        match key {
+            Vk::Key1 | Vk::Numpad1 => Character('1'),
+            Vk::Key2 | Vk::Numpad2 => Character('2'),
+            Vk::Key3 | Vk::Numpad3 => Character('3'),
+            Vk::Key4 | Vk::Numpad4 => Character('4'),
+            Vk::Key5 | Vk::Numpad5 => Character('5'),
+            Vk::Key6 | Vk::Numpad6 => Character('6'),
+            Vk::Key7 | Vk::Numpad7 => Character('7'),
+            Vk::Key8 | Vk::Numpad8 => Character('8'),
+            Vk::Key9 | Vk::Numpad9 => Character('9'),
+            Vk::Key0 | Vk::Numpad0 => Character('0'),
+            Vk::A => Character('a'),
+            Vk::B => Character('b'),
+            Vk::C => Character('c'),
+            Vk::D => Character('d'),
+            Vk::E => Character('e'),
+            Vk::F => Character('f'),
+            Vk::G => Character('g'),
+            Vk::H => Character('h'),
+            Vk::I => Character('i'),
+            Vk::J => Character('j'),
+            Vk::K => Character('k'),
+            Vk::L => Character('l'),
+            Vk::M => Character('m'),
+            Vk::N => Character('n'),
+            Vk::O => Character('o'),
+            Vk::P => Character('p'),
+            Vk::Q => Character('q'),
+            Vk::R => Character('r'),
+            Vk::S => Character('s'),
+            Vk::T => Character('t'),
+            Vk::U => Character('u'),
+            Vk::V => Character('v'),
+            Vk::W => Character('w'),
+            Vk::X => Character('x'),
+            Vk::Y => Character('y'),
+            Vk::Z => Character('z'),
+            Vk::Apostrophe => Character('\''),
+            Vk::Asterisk | Vk::NumpadMultiply => Character('*'),
+            Vk::At => Character('@'),
+            Vk::Backslash => Character('\\'),
+            Vk::Colon => Character(':'),
+            Vk::Comma | Vk::NumpadComma => Character(','),
+            Vk::Equals | Vk::NumpadEquals => Character('='),
+            Vk::Grave => Character('`'),
+            Vk::LBracket => Character('['),
+            Vk::RBracket => Character(']'),
+            Vk::Minus | Vk::NumpadSubtract => Character('-'),
+            Vk::Period | Vk::NumpadDecimal => Character('.'),
+            Vk::Plus | Vk::NumpadAdd => Character('+'),
+            Vk::Semicolon => Character(';'),
+            Vk::Slash | Vk::NumpadDivide => Character('/'),
+            Vk::Return | Vk::NumpadEnter => Enter,
+            Vk::Tab => Tab,
+            Vk::Space => Space,
+            Vk::Back => Backspace,
+            Vk::Delete => Delete,
+            Vk::Insert => Insert,
+            Vk::Escape => Escape,
+            Vk::Left => ArrowLeft,
+            Vk::Right => ArrowRight,
+            Vk::Up => ArrowUp,
+            Vk::Down => ArrowDown,
+            Vk::Home => Home,
+            Vk::End => End,
+            Vk::PageUp => PageUp,
+            Vk::PageDown => PageDown,
+            Vk::F1 => F(1),
+            Vk::F2 => F(2),
+            Vk::F3 => F(3),
+            Vk::F4 => F(4),
+            Vk::F5 => F(5),
+            Vk::F6 => F(6),
+            Vk::F7 => F(7),
+            Vk::F8 => F(8),
+            Vk::F9 => F(9),
+            Vk::F10 => F(10),
+            Vk::F11 => F(11),
+            Vk::F12 => F(12),
+            Vk::F13 => F(13),
+            Vk::F14 => F(14),
+            Vk::F15 => F(15),
+            Vk::F16 => F(16),
+            Vk::F17 => F(17),
+            Vk::F18 => F(18),
+            Vk::F19 => F(19),
+            Vk::F20 => F(20),
+            Vk::F21 => F(21),
+            Vk::F22 => F(22),
+            Vk::F23 => F(23),
+            Vk::F24 => F(24),
+            Vk::LShift | Vk::RShift => Shift,
+            Vk::LControl | Vk::RControl => Control,
+            Vk::LAlt | Vk::RAlt => Alt,
+            Vk::LWin | Vk::RWin => Super,
+            _ => Unidentified,
+        }
+    }
+}
+
+/// Where a key sits on the keyboard, whatever the layout: the platform's scan code
+///
+/// Use it for bindings that should stay in place across layouts, like WASD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCode(pub u32);
+
+/// Modifier keys held down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    /// The Windows key, or Command on macOS
+    pub logo: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        shift: false,
+        ctrl: false,
+        alt: false,
+        logo: false,
+    };
+
+    /// The modifier for commands like copy and save: Command on macOS, Ctrl elsewhere
+    pub fn command(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.logo
+        } else {
+            self.ctrl
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+impl From<ModifiersState> for Modifiers {
+    fn from(state: ModifiersState) -> Self {
+        Self {
+            shift: state.shift(),
+            ctrl: state.ctrl(),
+            alt: state.alt(),
+            logo: state.logo(),
+        }
+    }
+}
+
+/// A key pressed or released, delivered through `Application::key_event`
+///
+/// Text typed with it comes after it as a `TextEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+    pub pressed: bool,
+    /// Sent again because the key is held down
+    pub repeat: bool,
+    /// Widget with keyboard focus, set with `Context::focus`
+    pub target: Option<WidgetId>,
+}
+
+/// How held keys repeat, in `WindowOptions::key_repeat`
+///
+/// Applies to keys that move the caret, selection or focus and edit text, like
+/// the arrows and Backspace, so they step at the same pace on every platform.
+/// Keys that type text repeat as the OS has them, along with their text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRepeat {
+    /// How long a key is held before it repeats
+    pub delay: Duration,
+    /// Time between repeats
+    pub interval: Duration,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(500),
+            interval: Duration::from_millis(33),
+        }
+    }
+}
+
+/// Turns the window's keyboard input into key events
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Keyboard {
+    pub(crate) modifiers: Modifiers,
+    /// Keys down, to tell repeats from presses
+    held: HashSet<KeyCode>,
+    /// `None` to pass on the OS's repeats instead
+    pub(crate) repeat: Option<KeyRepeat>,
+    /// The key repeating and when it repeats next
+    repeating: Option<(KeyEvent, Instant)>,
+}
+
+impl Keyboard {
+    /// The event for a key going down or up, `None` for an OS repeat of a key
+    /// that repeats on its own
+    pub(crate) fn key(
+        &mut self,
+        key: Option<Vk>,
+        code: KeyCode,
+        pressed: bool,
+        target: Option<WidgetId>,
+        now: Instant,
+    ) -> Option<KeyEvent> {
+        let repeat = pressed && !self.held.insert(code);
+        if !pressed {
+            self.held.remove(&code);
+        }
+        let event = KeyEvent {
+            key: key.map_or(Key::Unidentified, Key::from),
+            code,
+            modifiers: self.modifiers,
+            pressed,
+            repeat,
+            target,
+        };
+        let Some(options) = self.repeat.filter(|_| repeats(event.key)) else {
+            // Typing stops the key held from repeating, as modifiers don't
+            if pressed && !repeat && !is_modifier(event.key) {
+                self.repeating = None;
+            }
+            return Some(event);
+        };
+        if repeat {
+            return None;
+        }
+        if pressed {
+            let next = now + options.delay;
+            self.repeating = Some((
+                KeyEvent {
+                    repeat: true,
+                    ..event
+                },
+                next,
+            ));
+        } else if self.repeating.is_some_and(|(held, _)| held.code == code) {
+            self.repeating = None;
+        }
+        Some(event)
+    }
+
+    /// When the key held repeats next
+    pub(crate) fn repeat_deadline(&self) -> Option<Instant> {
+        Some(self.repeating?.1)
+    }
+
+    /// The repeat of the key held, once it is due, with the modifiers now held
+    pub(crate) fn tick(&mut self, now: Instant) -> Option<KeyEvent> {
+        let interval = self.repeat?.interval;
+        let (event, next) = self.repeating.as_mut().filter(|(_, next)| now >= *next)?;
+        // Skip repeats missed while the event loop was busy rather than burst
+        *next = (*next + interval).max(now);
+        event.modifiers = self.modifiers;
+        Some(*event)
+    }
+
+    /// Forget the keys held, as when the window loses focus and misses their release
+    pub(crate) fn release_all(&mut self) {
+        self.held.clear();
+        self.modifiers = Modifiers::NONE;
+        self.repeating = None;
+    }
+}
+
+fn is_modifier(key: Key) -> bool {
+    matches!(key, Key::Shift | Key::Control | Key::Alt | Key::Super)
+}
+
+/// Whether `key` repeats through `KeyRepeat` rather than as the OS has it
+fn repeats(key: Key) -> bool {
+    !is_modifier(key) && !matches!(key, Key::Character(_) | Key::Space | Key::Unidentified)
+}
+
+/// Text typed into the focused widget, delivered through `Application::text_input`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEvent {
+    pub text: String,
+    /// Widget with keyboard focus, set with `Context::focus`
+    pub target: Option<WidgetId>,
+}
+
+/// Text being composed with an input method, e.g. pinyin before it becomes
+/// Chinese, delivered through `Application::composition`
+///
+/// Draw it at the caret, underlined, until it is committed as a `TextEvent` or
+/// cleared with empty `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Composition {
+    pub text: String,
+    /// Bytes of `text` to show as the caret or selection, `None` to hide it
+    pub cursor: Option<Range<usize>>,
+    /// Widget with keyboard focus, set with `Context::focus`
+    pub target: Option<WidgetId>,
+}
+
+impl Composition {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+/// Input method settings requested by the application, applied to the window
+/// after each batch of events
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Ime {
+    pub(crate) allowed: bool,
+    /// Where the candidate window goes, in frame pixels
+    pub(crate) position: Option<Point>,
+    /// Changed since last applied
+    pub(crate) dirty: bool,
+}
+
+/// Whether a received character is text to insert rather than part of a command
+///
+/// Enter, Tab, Backspace and Ctrl or Command shortcuts also arrive as
+/// characters, but are handled by their key events. Ctrl+Alt is AltGr on
+/// Windows, which types text.
+pub(crate) fn is_text(c: char, modifiers: Modifiers) -> bool {
+    !c.is_control() && !modifiers.logo && (!modifiers.ctrl || modifiers.alt)
+}
@@ -0,0 +1,114 @@
+//! Wixe is a cross-platform GUI framework for Rust.
+
+pub mod app;
+pub mod atlas;
+pub mod backend;
+pub mod bitmap;
+pub mod blend;
+pub mod brush;
+pub mod clipboard;
+pub mod color;
+pub mod cursor;
+pub mod damage;
+pub mod drag;
+pub mod draw_list;
+pub mod effects;
+pub mod error;
+pub mod event;
+pub mod font;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub mod geometry;
+pub mod gesture;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "hotkeys")]
+pub mod hotkey;
+pub mod keyboard;
+pub mod nine_patch;
+#[cfg(feature = "notifications")]
+pub mod notify;
+pub mod painter;
+pub mod path;
+pub mod pointer;
+pub mod popup;
+pub mod raster;
+pub mod record;
+pub mod scroll;
+pub mod selection;
+pub mod shortcut;
+#[cfg(feature = "skia")]
+pub mod skia;
+pub mod stroke;
+pub mod style;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod target;
+#[cfg(feature = "taskbar")]
+pub mod taskbar;
+pub mod text;
+pub mod theme;
+pub mod transform;
+pub mod transition;
+#[cfg(feature = "tray")]
+pub mod tray;
+pub mod viewport;
+pub mod widget;
+pub mod window;
+
+pub use app::{Application, Context, WindowOptions, run};
+pub use atlas::{AtlasAllocator, Region, SpriteSheet};
+pub use backend::{Backend, PostProcess, PresentMode, RenderBackend, Scaling, SoftwareBackend};
+pub use bitmap::{Bitmap, FilterMode, ImageSource};
+pub use blend::BlendMode;
+pub use brush::{Brush, ColorStop, LinearGradient, Pattern, RadialGradient, Tiling};
+pub use clipboard::Clipboard;
+pub use color::Color;
+pub use cursor::{Cursor, CursorIcon, CustomCursor};
+pub use damage::DamageTracker;
+pub use drag::{DragEvent, DragEventKind};
+pub use draw_list::{DrawCmd, DrawList, Layer};
+pub use effects::Shadow;
+pub use error::Error;
+pub use event::{FilterId, InputEvent};
+pub use font::{Font, FontId, FontWeight, Fonts, LineMetrics};
+#[cfg(feature = "gamepad")]
+pub use gamepad::{GamepadAxis, GamepadButton, GamepadEvent, GamepadEventKind};
+pub use geometry::{Insets, Point, Rect, Size};
+pub use gesture::{GestureEvent, GestureKind};
+#[cfg(feature = "gpu")]
+pub use gpu::GpuBackend;
+#[cfg(feature = "hotkeys")]
+pub use hotkey::Hotkeys;
+pub use keyboard::{Composition, Key, KeyCode, KeyEvent, KeyRepeat, Modifiers, TextEvent};
+pub use nine_patch::NinePatch;
+#[cfg(feature = "notifications")]
+pub use notify::{NotificationEvent, Notifications, notify};
+pub use painter::Painter;
+pub use path::Path;
+pub use pointer::{MouseButton, PointerEvent, PointerEventKind};
+pub use popup::{PopupId, PopupOptions};
+pub use scroll::{ScrollEvent, ScrollOptions};
+pub use selection::{CaretMove, TextSelection};
+pub use shortcut::{Shortcut, ShortcutEvent, Shortcuts};
+#[cfg(feature = "skia")]
+pub use skia::SkiaBackend;
+pub use stroke::{LineCap, LineJoin, Stroke};
+pub use style::{Property, State, Style, Stylesheet};
+#[cfg(feature = "svg")]
+pub use svg::Svg;
+pub use target::RenderTarget;
+#[cfg(feature = "taskbar")]
+pub use taskbar::Progress;
+pub use text::{
+    TextAlign, TextAntialiasing, TextLayout, TextLine, TextMetrics, TextOutline, TextSpan,
+    TextStyle, VerticalAlign, WritingMode,
+};
+pub use theme::{Appearance, Density, Radii, Theme, Tokens};
+pub use transform::Transform;
+pub use transition::{Easing, Transition};
+#[cfg(feature = "tray")]
+pub use tray::{Tray, TrayEvent, TrayMenuItem, TrayOptions};
+pub use viewport::Viewport;
+pub use widget::WidgetId;
+pub use window::{Fullscreen, Monitor, WindowState};
@@ -0,0 +1,80 @@
+use crate::bitmap::Bitmap;
+use crate::geometry::{Insets, Rect};
+use crate::painter::Painter;
+
+/// A bitmap split into a 3x3 grid so it can stretch without distorting its corners
+///
+/// Corners are drawn at their natural size, edges stretch along one axis and
+/// the center stretches in both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NinePatch {
+    /// Corner sizes in source pixels
+    insets: Insets,
+    /// Slices in row-major order, top-left first
+    slices: [Bitmap; 9],
+}
+
+impl NinePatch {
+    /// Slice `bitmap` with fixed borders of `insets` source pixels
+    pub fn new(bitmap: &Bitmap, insets: Insets) -> Self {
+        let (w, h) = (bitmap.width(), bitmap.height());
+        let left = (insets.left.max(0.0) as u32).min(w);
+        let right = (insets.right.max(0.0) as u32).min(w - left);
+        let top = (insets.top.max(0.0) as u32).min(h);
+        let bottom = (insets.bottom.max(0.0) as u32).min(h - top);
+        let xs = [(0, left), (left, w - left - right), (w - right, right)];
+        let ys = [(0, top), (top, h - top - bottom), (h - bottom, bottom)];
+        let slices = std::array::from_fn(|i| {
+            let ((x, sw), (y, sh)) = (xs[i % 3], ys[i / 3]);
+            bitmap.crop(x, y, sw, sh)
+        });
+        Self {
+            insets: Insets::new(left as f32, top as f32, right as f32, bottom as f32),
+            slices,
+        }
+    }
+
+    pub fn insets(&self) -> Insets {
+        self.insets
+    }
+}
+
+impl Painter<'_> {
+    /// Draw a nine-patch stretched to fill `dst`
+    pub fn draw_nine_patch(&mut self, patch: &NinePatch, dst: Rect) {
+        let Insets {
+            left,
+            top,
+            right,
+            bottom,
+        } = patch.insets;
+        // Shrink the corners proportionally when the target is smaller than them
+        let sx = if left + right > dst.width {
+            dst.width / (left + right)
+        } else {
+            1.0
+        };
+        let sy = if top + bottom > dst.height {
+            dst.height / (top + bottom)
+        } else {
+            1.0
+        };
+        let (l, r, t, b) = (left * sx, right * sx, top * sy, bottom * sy);
+        let xs = [
+            (dst.x, l),
+            (dst.x + l, dst.width - l - r),
+            (dst.max_x() - r, r),
+        ];
+        let ys = [
+            (dst.y, t),
+            (dst.y + t, dst.height - t - b),
+            (dst.max_y() - b, b),
+        ];
+        for (i, slice) in patch.slices.iter().enumerate() {
+            let ((x, w), (y, h)) = (xs[i % 3], ys[i / 3]);
+            if slice.width() > 0 && slice.height() > 0 && w > 0.0 && h > 0.0 {
+                self.draw_image(slice, Rect::new(x, y, w, h));
+            }
+        }
+    }
+}
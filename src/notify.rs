@@ -0,0 +1,130 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use notify_rust::Notification;
+use winit::event_loop::EventLoopProxy;
+
+use crate::error::Error;
+
+/// Show a desktop notification, e.g.
+/// `wixe::notify("Export finished", "report.pdf was saved", None)`
+///
+/// `icon` names an icon of the desktop theme or is the path of an image. To
+/// hear about clicks, show it with `Context::notifications_mut` instead.
+pub fn notify(title: &str, body: &str, icon: Option<&str>) -> Result<(), Error> {
+    notification(title, body, icon).show()?;
+    Ok(())
+}
+
+fn notification(title: &str, body: &str, icon: Option<&str>) -> Notification {
+    let mut notification = Notification::new();
+    notification.summary(title).body(body);
+    if let Some(icon) = icon {
+        notification.icon(icon);
+    }
+    notification
+}
+
+/// Id of a notification, and whether it was clicked rather than closed
+type Response = (u64, bool);
+
+/// A notification was clicked, delivered through
+/// `Application::notification_clicked`
+#[derive(Debug, Clone)]
+pub struct NotificationEvent {
+    action: Rc<dyn Any>,
+}
+
+impl NotificationEvent {
+    /// The action the notification was shown with, if it is a `T`
+    pub fn action<T: Any>(&self) -> Option<&T> {
+        self.action.downcast_ref()
+    }
+}
+
+/// Desktop notifications that report clicks, e.g.
+/// `ctx.notifications_mut().show("New message", "From Ada", None, Msg::OpenInbox)`
+///
+/// A click is delivered through `Application::notification_clicked`. Only the
+/// notification servers of Linux and the BSDs report clicks; elsewhere the
+/// notification is just shown.
+#[derive(Default)]
+pub struct Notifications {
+    /// Actions of notifications still on screen, by id
+    actions: HashMap<u64, Rc<dyn Any>>,
+    next_id: u64,
+    /// Where threads waiting on notifications report them clicked or closed
+    listener: Option<(Sender<Response>, EventLoopProxy<()>)>,
+    responses: Option<Receiver<Response>>,
+}
+
+impl Notifications {
+    /// Receive clicks, waking the event loop through `proxy`
+    pub(crate) fn listen(&mut self, proxy: EventLoopProxy<()>) {
+        let (sender, receiver) = mpsc::channel();
+        self.listener = Some((sender, proxy));
+        self.responses = Some(receiver);
+    }
+
+    /// Show a notification, delivering `action` when it is clicked
+    ///
+    /// `icon` names an icon of the desktop theme or is the path of an image.
+    pub fn show(
+        &mut self,
+        title: &str,
+        body: &str,
+        icon: Option<&str>,
+        action: impl Any,
+    ) -> Result<(), Error> {
+        let mut notification = notification(title, body, icon);
+        // Clicking the notification itself invokes its default action
+        notification.action("default", "Open");
+        let handle = notification.show()?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.actions.insert(id, Rc::new(action));
+        self.wait_for_click(handle, id);
+        Ok(())
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn wait_for_click(&self, handle: notify_rust::NotificationHandle, id: u64) {
+        let Some((sender, proxy)) = &self.listener else {
+            return;
+        };
+        let (sender, proxy) = (sender.clone(), proxy.clone());
+        // Blocks until the notification is clicked or closed
+        std::thread::spawn(move || {
+            handle.wait_for_action(|response| {
+                if sender.send((id, response == "default")).is_ok() {
+                    let _ = proxy.send_event(());
+                }
+            });
+        });
+    }
+
+    /// Elsewhere a notification is never heard of again, as if closed
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    fn wait_for_click<H>(&self, _handle: H, id: u64) {
+        if let Some((sender, _)) = &self.listener {
+            let _ = sender.send((id, false));
+        }
+    }
+
+    /// Notifications clicked since the last poll
+    pub(crate) fn poll(&mut self) -> Vec<NotificationEvent> {
+        let Some(responses) = &self.responses else {
+            return Vec::new();
+        };
+        let responses: Vec<Response> = responses.try_iter().collect();
+        responses
+            .into_iter()
+            .filter_map(|(id, clicked)| {
+                let action = self.actions.remove(&id)?;
+                clicked.then_some(NotificationEvent { action })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,499 @@
+use crate::bitmap::Bitmap;
+use crate::blend::{self, BlendMode};
+use crate::brush::Brush;
+use crate::color::Color;
+use crate::geometry::{Point, Rect};
+use crate::path::Path;
+use crate::raster::{self, Mask};
+use crate::text::TextAntialiasing;
+#[cfg(feature = "gpu")]
+use crate::text::TextDraw;
+use crate::transform::Transform;
+
+/// Pixel-aligned clip bounds, `x1`/`y1` exclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ClipBox {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+impl ClipBox {
+    fn from_rect(rect: Rect) -> Self {
+        Self {
+            x0: rect.min_x().round() as i32,
+            y0: rect.min_y().round() as i32,
+            x1: rect.max_x().round() as i32,
+            y1: rect.max_y().round() as i32,
+        }
+    }
+
+    fn intersect(self, other: ClipBox) -> ClipBox {
+        let x0 = self.x0.max(other.x0);
+        let y0 = self.y0.max(other.y0);
+        ClipBox {
+            x0,
+            y0,
+            x1: self.x1.min(other.x1).max(x0),
+            y1: self.y1.min(other.y1).max(y0),
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
+
+    fn to_rect(self) -> Rect {
+        Rect::new(
+            self.x0 as f32,
+            self.y0 as f32,
+            (self.x1 - self.x0) as f32,
+            (self.y1 - self.y0) as f32,
+        )
+    }
+}
+
+/// Offscreen buffer collecting the drawing of one `push_group`
+struct Group {
+    pixels: Vec<u8>,
+    opacity: f32,
+}
+
+/// Immediate-mode drawing onto an RGBA frame buffer
+///
+/// The frame holds premultiplied alpha; colors passed in are straight alpha.
+/// Every drawing operation is limited to the current clip rectangle and
+/// positioned through the current transform.
+pub struct Painter<'a> {
+    frame: &'a mut [u8],
+    width: u32,
+    height: u32,
+    clip_stack: Vec<ClipBox>,
+    transform_stack: Vec<Transform>,
+    opacity_stack: Vec<f32>,
+    blend_stack: Vec<BlendMode>,
+    groups: Vec<Group>,
+    text_antialiasing: TextAntialiasing,
+    /// Text recorded instead of drawn, see `record_text`
+    #[cfg(feature = "gpu")]
+    text_draws: Option<Vec<TextDraw>>,
+}
+
+impl<'a> Painter<'a> {
+    pub fn new(frame: &'a mut [u8], width: u32, height: u32) -> Self {
+        debug_assert_eq!(frame.len(), (width * height * 4) as usize);
+        Self {
+            frame,
+            width,
+            height,
+            clip_stack: Vec::new(),
+            transform_stack: Vec::new(),
+            opacity_stack: Vec::new(),
+            blend_stack: Vec::new(),
+            groups: Vec::new(),
+            text_antialiasing: TextAntialiasing::default(),
+            #[cfg(feature = "gpu")]
+            text_draws: None,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixels being drawn into, the innermost group's buffer while one is open
+    pub fn frame_mut(&mut self) -> &mut [u8] {
+        match self.groups.last_mut() {
+            Some(group) => &mut group.pixels,
+            None => self.frame,
+        }
+    }
+
+    /// Area of the frame in pixels
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.width as f32, self.height as f32)
+    }
+
+    fn clip(&self) -> ClipBox {
+        self.clip_stack.last().copied().unwrap_or(ClipBox {
+            x0: 0,
+            y0: 0,
+            x1: self.width as i32,
+            y1: self.height as i32,
+        })
+    }
+
+    /// Restrict drawing to `rect`, intersected with the current clip
+    ///
+    /// The rect is in local coordinates; under rotation its bounding box is used.
+    pub fn push_clip(&mut self, rect: Rect) {
+        let rect = self.transform().map_rect(rect);
+        let clip = self.clip().intersect(ClipBox::from_rect(rect));
+        self.clip_stack.push(clip);
+    }
+
+    /// Restore the clip that was active before the last `push_clip`
+    pub fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+
+    /// Run `f` with drawing restricted to `rect`
+    pub fn with_clip(&mut self, rect: Rect, f: impl FnOnce(&mut Self)) {
+        self.push_clip(rect);
+        f(self);
+        self.pop_clip();
+    }
+
+    /// The area drawing is currently limited to, in frame coordinates
+    pub fn clip_rect(&self) -> Rect {
+        self.clip().to_rect()
+    }
+
+    /// Mapping from local drawing coordinates to frame pixels
+    pub fn transform(&self) -> Transform {
+        self.transform_stack.last().copied().unwrap_or_default()
+    }
+
+    /// Apply `t` to everything drawn until the matching `pop_transform`
+    pub fn push_transform(&mut self, t: Transform) {
+        let current = self.transform();
+        self.transform_stack.push(t.then(current));
+    }
+
+    pub fn pop_transform(&mut self) {
+        self.transform_stack.pop();
+    }
+
+    /// Run `f` with `t` applied on top of the current transform
+    pub fn with_transform(&mut self, t: Transform, f: impl FnOnce(&mut Self)) {
+        self.push_transform(t);
+        f(self);
+        self.pop_transform();
+    }
+
+    /// Alpha multiplier applied to everything drawn
+    pub fn opacity(&self) -> f32 {
+        self.opacity_stack.last().copied().unwrap_or(1.0)
+    }
+
+    /// Fade everything drawn until the matching `pop_opacity`, on top of the current opacity
+    ///
+    /// Each primitive is faded on its own; use `push_group` to fade overlapping
+    /// content as a whole.
+    pub fn push_opacity(&mut self, opacity: f32) {
+        let current = self.opacity();
+        self.opacity_stack.push(current * opacity.clamp(0.0, 1.0));
+    }
+
+    pub fn pop_opacity(&mut self) {
+        self.opacity_stack.pop();
+    }
+
+    /// Run `f` with `opacity` applied on top of the current opacity
+    pub fn with_opacity(&mut self, opacity: f32, f: impl FnOnce(&mut Self)) {
+        self.push_opacity(opacity);
+        f(self);
+        self.pop_opacity();
+    }
+
+    /// How drawn pixels combine with the frame
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_stack.last().copied().unwrap_or_default()
+    }
+
+    /// Composite everything drawn with `mode` until the matching `pop_blend_mode`
+    pub fn push_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_stack.push(mode);
+    }
+
+    pub fn pop_blend_mode(&mut self) {
+        self.blend_stack.pop();
+    }
+
+    /// Run `f` with drawing composited using `mode`
+    pub fn with_blend_mode(&mut self, mode: BlendMode, f: impl FnOnce(&mut Self)) {
+        self.push_blend_mode(mode);
+        f(self);
+        self.pop_blend_mode();
+    }
+
+    /// How glyph edges are smoothed
+    pub fn text_antialiasing(&self) -> TextAntialiasing {
+        self.text_antialiasing
+    }
+
+    /// Smooth glyph edges with `mode` from now on
+    ///
+    /// Subpixel modes only suit opaque backgrounds on a display with that
+    /// subpixel order; text falls back to grayscale under rotation, scaling or
+    /// blend modes other than `SourceOver`.
+    pub fn set_text_antialiasing(&mut self, mode: TextAntialiasing) {
+        self.text_antialiasing = mode;
+    }
+
+    /// Record text as `TextDraw`s from now on instead of drawing it, in
+    /// grayscale
+    #[cfg(feature = "gpu")]
+    pub(crate) fn record_text(&mut self) {
+        self.text_draws = Some(Vec::new());
+    }
+
+    /// The text recorded so far, `None` unless recording
+    #[cfg(feature = "gpu")]
+    pub(crate) fn text_draws(&mut self) -> Option<&mut Vec<TextDraw>> {
+        self.text_draws.as_mut()
+    }
+
+    /// Stop recording text, returning what was recorded
+    #[cfg(feature = "gpu")]
+    pub(crate) fn take_text_draws(&mut self) -> Vec<TextDraw> {
+        self.text_draws.take().unwrap_or_default()
+    }
+
+    /// Draw into an offscreen buffer until the matching `pop_group`, which
+    /// composites it at `opacity`
+    ///
+    /// Overlapping content inside the group fades as one picture, as a
+    /// disabled widget or one fading out should. The group is blended with the
+    /// current blend mode; inside it drawing starts over with source-over.
+    pub fn push_group(&mut self, opacity: f32) {
+        let len = (self.width * self.height * 4) as usize;
+        self.groups.push(Group {
+            pixels: vec![0; len],
+            opacity: opacity.clamp(0.0, 1.0),
+        });
+        // The group is faded and blended once as a whole, not again per primitive
+        self.opacity_stack.push(1.0);
+        self.blend_stack.push(BlendMode::SourceOver);
+    }
+
+    pub fn pop_group(&mut self) {
+        let Some(group) = self.groups.pop() else {
+            return;
+        };
+        self.opacity_stack.pop();
+        self.blend_stack.pop();
+        let bitmap = Bitmap::from_premultiplied(self.width, self.height, group.pixels);
+        self.with_opacity(group.opacity, |p| p.blit(&bitmap, 0, 0));
+    }
+
+    /// Run `f` inside a group composited at `opacity`
+    pub fn with_group(&mut self, opacity: f32, f: impl FnOnce(&mut Self)) {
+        self.push_group(opacity);
+        f(self);
+        self.pop_group();
+    }
+
+    /// Map a frame position back into local coordinates, e.g. for hit-testing
+    pub fn to_local(&self, p: Point) -> Option<Point> {
+        self.transform().inverse().map(|inv| inv.apply(p))
+    }
+
+    /// Fill the clipped area of the frame with a color
+    pub fn clear(&mut self, color: impl Into<Color>) {
+        let color = color.into().premultiplied();
+        let (clip, width) = (self.clip(), self.width);
+        let frame = self.frame_mut();
+        for y in clip.y0..clip.y1 {
+            let start = ((y as u32 * width + clip.x0 as u32) * 4) as usize;
+            let end = ((y as u32 * width + clip.x1 as u32) * 4) as usize;
+            for pixel in frame[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, brush: impl Into<Brush>) {
+        let t = self.transform();
+        if !t.is_translation() {
+            self.fill_path(&Path::rect(rect), brush);
+            return;
+        }
+        let brush = brush.into();
+        let rect = rect.translate(t.e, t.f);
+        let area = match rect.round_out().intersect(&self.clip_rect()) {
+            Some(area) => area,
+            None => return,
+        };
+        for y in area.y as i32..area.max_y() as i32 {
+            // Exact anti-aliasing: coverage is the overlap of the pixel with the rect
+            let cov_y = overlap(y as f32, rect.min_y(), rect.max_y());
+            for x in area.x as i32..area.max_x() as i32 {
+                let coverage = cov_y * overlap(x as f32, rect.min_x(), rect.max_x());
+                if coverage > 0.0 {
+                    let p = Point::new(x as f32 + 0.5 - t.e, y as f32 + 0.5 - t.f);
+                    let color = brush.color_at(p);
+                    self.blend_pixel(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn fill_rounded_rect(&mut self, rect: Rect, radius: f32, brush: impl Into<Brush>) {
+        self.fill_path(&Path::rounded_rect(rect, radius), brush);
+    }
+
+    pub fn fill_path(&mut self, path: &Path, brush: impl Into<Brush>) {
+        let t = self.transform();
+        let mut polygons = path.flatten();
+        for p in polygons.iter_mut().flatten() {
+            *p = t.apply(*p);
+        }
+        if let Some(mask) = raster::rasterize(&polygons, self.clip_rect()) {
+            self.fill_mask(&mask, &brush.into());
+        }
+    }
+
+    /// Paint `brush` through a coverage mask given in frame coordinates
+    ///
+    /// The brush itself is sampled in local coordinates.
+    pub fn fill_mask(&mut self, mask: &Mask, brush: &Brush) {
+        let inv = self.transform().inverse().unwrap_or_default();
+        for my in 0..mask.height {
+            let y = mask.y + my as i32;
+            for mx in 0..mask.width {
+                let coverage = mask.data[my * mask.width + mx];
+                if coverage > 0.0 {
+                    let x = mask.x + mx as i32;
+                    let color = match brush {
+                        Brush::Solid(color) => *color,
+                        _ => brush.color_at(inv.apply(Point::new(x as f32 + 0.5, y as f32 + 0.5))),
+                    };
+                    self.blend_pixel(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    /// Paint `color` through a row-major coverage bitmap placed at `origin` in local coordinates
+    pub fn fill_coverage(
+        &mut self,
+        origin: Point,
+        width: usize,
+        height: usize,
+        coverage: &[f32],
+        color: impl Into<Color>,
+    ) {
+        let at = |x: usize, y: usize| coverage[y * width + x];
+        self.fill_coverage_with(origin, width, height, at, color.into());
+    }
+
+    /// Paint `color` through `width` x `height` coverage read with `at(x, y)`,
+    /// e.g. from a region of an atlas
+    pub(crate) fn fill_coverage_with(
+        &mut self,
+        origin: Point,
+        width: usize,
+        height: usize,
+        at: impl Fn(usize, usize) -> f32,
+        color: Color,
+    ) {
+        let t = self.transform();
+        if t.is_translation() {
+            let ox = (origin.x + t.e).round() as i32;
+            let oy = (origin.y + t.f).round() as i32;
+            for y in 0..height {
+                for x in 0..width {
+                    let c = at(x, y);
+                    if c > 0.0 {
+                        self.blend_pixel(ox + x as i32, oy + y as i32, color, c);
+                    }
+                }
+            }
+            return;
+        }
+        let inv = match t.inverse() {
+            Some(inv) => inv,
+            None => return,
+        };
+        let local = Rect::new(origin.x, origin.y, width as f32, height as f32);
+        let area = match t.map_rect(local).round_out().intersect(&self.clip_rect()) {
+            Some(area) => area,
+            None => return,
+        };
+        for y in area.y as i32..area.max_y() as i32 {
+            for x in area.x as i32..area.max_x() as i32 {
+                let p = inv.apply(Point::new(x as f32 + 0.5, y as f32 + 0.5));
+                let c = sample_bilinear(
+                    &at,
+                    width,
+                    height,
+                    p.x - origin.x - 0.5,
+                    p.y - origin.y - 0.5,
+                );
+                if c > 0.0 {
+                    self.blend_pixel(x, y, color, c);
+                }
+            }
+        }
+    }
+
+    /// Composite a straight-alpha `color` over the pixel at (x, y), weighted by coverage
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        let src = blend::scale(color.premultiplied(), coverage);
+        self.composite_pixel(x, y, src);
+    }
+
+    /// Source-over `color` at device pixel (x, y) with separate red, green and blue coverage
+    pub(crate) fn blend_subpixels(&mut self, x: i32, y: i32, color: Color, coverage: [f32; 3]) {
+        if !self.clip().contains(x, y) {
+            return;
+        }
+        let alpha = color.a as f32 / 255.0 * self.opacity();
+        let idx = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let dst = &mut self.frame_mut()[idx..idx + 4];
+        for (k, src) in [color.r, color.g, color.b].into_iter().enumerate() {
+            let c = coverage[k] * alpha;
+            dst[k] = (src as f32 * c + dst[k] as f32 * (1.0 - c)).round() as u8;
+        }
+        let c = coverage.iter().sum::<f32>() / 3.0 * alpha;
+        dst[3] = (255.0 * c + dst[3] as f32 * (1.0 - c)).round() as u8;
+    }
+
+    /// Composite an already premultiplied color onto the pixel at (x, y) with the current blend mode
+    pub fn composite_pixel(&mut self, x: i32, y: i32, src: [u8; 4]) {
+        if !self.clip().contains(x, y) {
+            return;
+        }
+        let src = match self.opacity_stack.last() {
+            Some(&opacity) if opacity < 1.0 => blend::scale(src, opacity),
+            _ => src,
+        };
+        let idx = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let mode = self.blend_mode();
+        blend::composite(&mut self.frame_mut()[idx..idx + 4], src, mode);
+    }
+}
+
+/// Bilinear lookup into a coverage bitmap, zero outside of it
+fn sample_bilinear(
+    data: impl Fn(usize, usize) -> f32,
+    width: usize,
+    height: usize,
+    x: f32,
+    y: f32,
+) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let at = |xi: i32, yi: i32| {
+        if xi < 0 || yi < 0 || xi as usize >= width || yi as usize >= height {
+            0.0
+        } else {
+            data(xi as usize, yi as usize)
+        }
+    };
+    let (xi, yi) = (x0 as i32, y0 as i32);
+    let top = at(xi, yi) * (1.0 - fx) + at(xi + 1, yi) * fx;
+    let bottom = at(xi, yi + 1) * (1.0 - fx) + at(xi + 1, yi + 1) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+/// Length of the overlap between pixel `[p, p + 1)` and `[min, max)`
+fn overlap(p: f32, min: f32, max: f32) -> f32 {
+    ((p + 1.0).min(max) - p.max(min)).max(0.0)
+}
@@ -0,0 +1,250 @@
+use crate::geometry::{Point, Rect};
+
+/// Bézier control point factor for approximating a quarter circle
+const KAPPA: f32 = 0.552_284_8;
+
+/// Number of line segments each curve is flattened into
+const CURVE_SEGMENTS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathEl {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// A vector outline made of lines and Bézier curves
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    elements: Vec<PathEl>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, p: Point) -> &mut Self {
+        self.elements.push(PathEl::MoveTo(p));
+        self
+    }
+
+    pub fn line_to(&mut self, p: Point) -> &mut Self {
+        self.elements.push(PathEl::LineTo(p));
+        self
+    }
+
+    pub fn quad_to(&mut self, c: Point, p: Point) -> &mut Self {
+        self.elements.push(PathEl::QuadTo(c, p));
+        self
+    }
+
+    pub fn cubic_to(&mut self, c1: Point, c2: Point, p: Point) -> &mut Self {
+        self.elements.push(PathEl::CubicTo(c1, c2, p));
+        self
+    }
+
+    pub fn close(&mut self) -> &mut Self {
+        self.elements.push(PathEl::Close);
+        self
+    }
+
+    pub fn elements(&self) -> &[PathEl] {
+        &self.elements
+    }
+
+    pub fn line(from: Point, to: Point) -> Self {
+        let mut path = Path::new();
+        path.move_to(from).line_to(to);
+        path
+    }
+
+    pub fn rect(rect: Rect) -> Self {
+        let mut path = Path::new();
+        path.move_to(Point::new(rect.min_x(), rect.min_y()))
+            .line_to(Point::new(rect.max_x(), rect.min_y()))
+            .line_to(Point::new(rect.max_x(), rect.max_y()))
+            .line_to(Point::new(rect.min_x(), rect.max_y()))
+            .close();
+        path
+    }
+
+    /// Rectangle with circular corners of the given radius
+    pub fn rounded_rect(rect: Rect, radius: f32) -> Self {
+        let r = radius.min(rect.width / 2.0).min(rect.height / 2.0).max(0.0);
+        if r == 0.0 {
+            return Path::rect(rect);
+        }
+        let k = r * KAPPA;
+        let (x0, y0, x1, y1) = (rect.min_x(), rect.min_y(), rect.max_x(), rect.max_y());
+        let mut path = Path::new();
+        path.move_to(Point::new(x0 + r, y0))
+            .line_to(Point::new(x1 - r, y0))
+            .cubic_to(
+                Point::new(x1 - r + k, y0),
+                Point::new(x1, y0 + r - k),
+                Point::new(x1, y0 + r),
+            )
+            .line_to(Point::new(x1, y1 - r))
+            .cubic_to(
+                Point::new(x1, y1 - r + k),
+                Point::new(x1 - r + k, y1),
+                Point::new(x1 - r, y1),
+            )
+            .line_to(Point::new(x0 + r, y1))
+            .cubic_to(
+                Point::new(x0 + r - k, y1),
+                Point::new(x0, y1 - r + k),
+                Point::new(x0, y1 - r),
+            )
+            .line_to(Point::new(x0, y0 + r))
+            .cubic_to(
+                Point::new(x0, y0 + r - k),
+                Point::new(x0 + r - k, y0),
+                Point::new(x0 + r, y0),
+            )
+            .close();
+        path
+    }
+
+    pub fn ellipse(rect: Rect) -> Self {
+        let (rx, ry) = (rect.width / 2.0, rect.height / 2.0);
+        let c = rect.center();
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+        let mut path = Path::new();
+        path.move_to(Point::new(c.x + rx, c.y))
+            .cubic_to(
+                Point::new(c.x + rx, c.y + ky),
+                Point::new(c.x + kx, c.y + ry),
+                Point::new(c.x, c.y + ry),
+            )
+            .cubic_to(
+                Point::new(c.x - kx, c.y + ry),
+                Point::new(c.x - rx, c.y + ky),
+                Point::new(c.x - rx, c.y),
+            )
+            .cubic_to(
+                Point::new(c.x - rx, c.y - ky),
+                Point::new(c.x - kx, c.y - ry),
+                Point::new(c.x, c.y - ry),
+            )
+            .cubic_to(
+                Point::new(c.x + kx, c.y - ry),
+                Point::new(c.x + rx, c.y - ky),
+                Point::new(c.x + rx, c.y),
+            )
+            .close();
+        path
+    }
+
+    pub fn circle(center: Point, radius: f32) -> Self {
+        Path::ellipse(Rect::new(
+            center.x - radius,
+            center.y - radius,
+            2.0 * radius,
+            2.0 * radius,
+        ))
+    }
+
+    /// Flatten into closed polygons, one per subpath
+    pub fn flatten(&self) -> Vec<Vec<Point>> {
+        self.flatten_subpaths()
+            .into_iter()
+            .map(|(points, _)| points)
+            .collect()
+    }
+
+    /// Flatten into polylines, one per subpath, each with whether it was closed
+    pub fn flatten_subpaths(&self) -> Vec<(Vec<Point>, bool)> {
+        let mut polygons = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+        let mut last = Point::ZERO;
+        for el in &self.elements {
+            match *el {
+                PathEl::MoveTo(p) => {
+                    if current.len() > 1 {
+                        polygons.push((std::mem::take(&mut current), false));
+                    }
+                    current.clear();
+                    current.push(p);
+                    last = p;
+                }
+                PathEl::LineTo(p) => {
+                    if current.is_empty() {
+                        current.push(last);
+                    }
+                    current.push(p);
+                    last = p;
+                }
+                PathEl::QuadTo(c, p) => {
+                    if current.is_empty() {
+                        current.push(last);
+                    }
+                    for i in 1..=CURVE_SEGMENTS {
+                        let t = i as f32 / CURVE_SEGMENTS as f32;
+                        current.push(last.lerp(c, t).lerp(c.lerp(p, t), t));
+                    }
+                    last = p;
+                }
+                PathEl::CubicTo(c1, c2, p) => {
+                    if current.is_empty() {
+                        current.push(last);
+                    }
+                    for i in 1..=CURVE_SEGMENTS {
+                        let t = i as f32 / CURVE_SEGMENTS as f32;
+                        let a = last.lerp(c1, t);
+                        let b = c1.lerp(c2, t);
+                        let c = c2.lerp(p, t);
+                        current.push(a.lerp(b, t).lerp(b.lerp(c, t), t));
+                    }
+                    last = p;
+                }
+                PathEl::Close => {
+                    if let Some(&first) = current.first() {
+                        last = first;
+                    }
+                    if current.len() > 1 {
+                        polygons.push((std::mem::take(&mut current), true));
+                    }
+                }
+            }
+        }
+        if current.len() > 1 {
+            polygons.push((current, false));
+        }
+        polygons
+    }
+
+    /// Bounding box of all points, including control points
+    pub fn bounds(&self) -> Rect {
+        let mut min = Point::new(f32::MAX, f32::MAX);
+        let mut max = Point::new(f32::MIN, f32::MIN);
+        let mut add = |p: Point| {
+            min.x = min.x.min(p.x);
+            min.y = min.y.min(p.y);
+            max.x = max.x.max(p.x);
+            max.y = max.y.max(p.y);
+        };
+        for el in &self.elements {
+            match *el {
+                PathEl::MoveTo(p) | PathEl::LineTo(p) => add(p),
+                PathEl::QuadTo(c, p) => {
+                    add(c);
+                    add(p);
+                }
+                PathEl::CubicTo(c1, c2, p) => {
+                    add(c1);
+                    add(c2);
+                    add(p);
+                }
+                PathEl::Close => {}
+            }
+        }
+        if min.x > max.x {
+            return Rect::default();
+        }
+        Rect::from_points(min, max)
+    }
+}
@@ -0,0 +1,221 @@
+use std::time::{Duration, Instant};
+
+use winit::event::MouseButton as WinitButton;
+
+use crate::geometry::Point;
+use crate::widget::{WidgetId, WidgetTree};
+
+/// Presses further apart than this in frame pixels are separate clicks
+const CLICK_SLOP: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16),
+}
+
+impl From<WinitButton> for MouseButton {
+    fn from(button: WinitButton) -> Self {
+        match button {
+            WinitButton::Left => Self::Left,
+            WinitButton::Right => Self::Right,
+            WinitButton::Middle => Self::Middle,
+            WinitButton::Other(n) => Self::Other(n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointerEventKind {
+    Press(MouseButton),
+    Release(MouseButton),
+    /// Second press of a button in quick succession, right after its `Press`
+    DoubleClick(MouseButton),
+    /// Third press of a button in quick succession, right after its `Press`
+    TripleClick(MouseButton),
+    Move,
+    /// The pointer moved over the widget or one of its children
+    Enter,
+    /// The pointer is no longer over the widget or any of its children
+    Leave,
+}
+
+/// What the pointer did, delivered through `Application::pointer_event`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerEvent {
+    pub kind: PointerEventKind,
+    /// Widget the event is for: the innermost one under the pointer, the one
+    /// holding pointer capture, or the one entered or left; `None` over no widget
+    pub target: Option<WidgetId>,
+    /// Pointer position in frame pixels
+    pub position: Point,
+}
+
+/// Routes the window's pointer input to the widgets of the last frame
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PointerDispatcher {
+    pub(crate) widgets: WidgetTree,
+    position: Option<Point>,
+    /// Widgets under the pointer, outermost first
+    hovered: Vec<WidgetId>,
+    /// Longest time between the presses of a double or triple click
+    pub(crate) click_interval: Duration,
+    last_click: Option<Click>,
+    /// Widget getting moves and releases wherever the pointer is
+    pub(crate) captured: Option<WidgetId>,
+    buttons_down: Vec<MouseButton>,
+}
+
+/// The latest press, to tell whether the next one continues a multiple click
+#[derive(Debug, Clone, Copy)]
+struct Click {
+    button: MouseButton,
+    target: Option<WidgetId>,
+    position: Point,
+    time: Instant,
+    count: u8,
+}
+
+impl PointerDispatcher {
+    pub(crate) fn position(&self) -> Option<Point> {
+        self.position
+    }
+
+    /// Innermost widget under the pointer
+    pub(crate) fn target(&self) -> Option<WidgetId> {
+        self.hovered.last().copied()
+    }
+
+    /// Widgets under the pointer, outermost first
+    pub(crate) fn hovered(&self) -> &[WidgetId] {
+        &self.hovered
+    }
+
+    pub(crate) fn is_hovered(&self, id: WidgetId) -> bool {
+        self.hovered.contains(&id)
+    }
+
+    /// Whether buttons are held since being pressed on `id`, which holds
+    /// capture
+    pub(crate) fn is_pressed(&self, id: WidgetId) -> bool {
+        !self.buttons_down.is_empty() && self.captured == Some(id)
+    }
+
+    fn event(&self, kind: PointerEventKind, target: Option<WidgetId>) -> PointerEvent {
+        PointerEvent {
+            kind,
+            target,
+            position: self.position.unwrap_or_default(),
+        }
+    }
+
+    /// Hit-test the pointer again, e.g. after the widgets moved in a new frame
+    ///
+    /// Returns the leave events, innermost first, then the enter events,
+    /// outermost first.
+    pub(crate) fn update_hover(&mut self) -> Vec<PointerEvent> {
+        let path = self
+            .position
+            .map_or_else(Vec::new, |p| self.widgets.path_at(p));
+        self.hover(path)
+    }
+
+    fn hover(&mut self, path: Vec<WidgetId>) -> Vec<PointerEvent> {
+        let kept = (self.hovered.iter().zip(&path))
+            .take_while(|(a, b)| a == b)
+            .count();
+        let left = self.hovered[kept..].iter().rev();
+        let mut events: Vec<_> = left
+            .map(|&id| self.event(PointerEventKind::Leave, Some(id)))
+            .collect();
+        events.extend(
+            path[kept..]
+                .iter()
+                .map(|&id| self.event(PointerEventKind::Enter, Some(id))),
+        );
+        self.hovered = path;
+        events
+    }
+
+    /// Widget pointer events other than enter and leave go to
+    fn event_target(&self) -> Option<WidgetId> {
+        self.captured.or(self.target())
+    }
+
+    pub(crate) fn moved(&mut self, p: Point) -> Vec<PointerEvent> {
+        self.position = Some(p);
+        let mut events = self.update_hover();
+        events.push(self.event(PointerEventKind::Move, self.event_target()));
+        events
+    }
+
+    /// Forget the buttons held and end capture, as when the window loses
+    /// focus and misses their release
+    pub(crate) fn release_all(&mut self) {
+        self.buttons_down.clear();
+        self.captured = None;
+    }
+
+    /// The pointer left the window; leave events are at its last position
+    pub(crate) fn left(&mut self) -> Vec<PointerEvent> {
+        let events = self.hover(Vec::new());
+        self.position = None;
+        events
+    }
+
+    /// A press or release at `now`; presses may add a double or triple click
+    ///
+    /// The first button pressed captures the pointer for the widget under it
+    /// until every button is released again.
+    pub(crate) fn button(
+        &mut self,
+        button: MouseButton,
+        pressed: bool,
+        now: Instant,
+    ) -> Vec<PointerEvent> {
+        if !pressed {
+            let event = self.event(PointerEventKind::Release(button), self.event_target());
+            self.buttons_down.retain(|&b| b != button);
+            if self.buttons_down.is_empty() {
+                self.captured = None;
+            }
+            return vec![event];
+        }
+        if self.buttons_down.is_empty() {
+            self.captured = self.target();
+        }
+        if !self.buttons_down.contains(&button) {
+            self.buttons_down.push(button);
+        }
+        let target = self.event_target();
+        let position = self.position.unwrap_or_default();
+        let count = match self.last_click {
+            Some(last)
+                if last.button == button
+                    && last.target == target
+                    && last.count < 3
+                    && now.duration_since(last.time) <= self.click_interval
+                    && last.position.distance(position) <= CLICK_SLOP =>
+            {
+                last.count + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some(Click {
+            button,
+            target,
+            position,
+            time: now,
+            count,
+        });
+        let mut events = vec![self.event(PointerEventKind::Press(button), target)];
+        match count {
+            2 => events.push(self.event(PointerEventKind::DoubleClick(button), target)),
+            3 => events.push(self.event(PointerEventKind::TripleClick(button), target)),
+            _ => {}
+        }
+        events
+    }
+}
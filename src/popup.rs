@@ -0,0 +1,174 @@
+use winit::dpi::{LogicalSize, PhysicalPosition};
+use winit::event::{ElementState, WindowEvent};
+use winit::event_loop::EventLoopWindowTarget;
+#[cfg(windows)]
+use winit::platform::windows::WindowBuilderExtWindows;
+use winit::window::{Window, WindowBuilder, WindowId};
+
+use crate::backend::{self, Backend, PresentMode, RenderBackend};
+use crate::color::Color;
+use crate::draw_list::DrawList;
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::pointer::{PointerEvent, PointerEventKind};
+
+/// Names a popup window, returned by `Context::open_popup`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PopupId(u64);
+
+/// Where a popup window opens and how large it is, passed to
+/// `Context::open_popup`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopupOptions {
+    /// Top-left corner on the screen in physical pixels, e.g.
+    /// `ctx.screen_position(Point::new(r.min_x(), r.max_y()))` for a dropdown
+    /// under a button
+    pub position: Point,
+    /// Width in logical pixels, which is also the frame's
+    pub width: u32,
+    pub height: u32,
+}
+
+pub(crate) enum PopupRequest {
+    Open(PopupId, PopupOptions),
+    Close(PopupId),
+}
+
+/// A popup window and the renderer presenting its frames
+pub(crate) struct Popup {
+    pub(crate) id: PopupId,
+    window: Window,
+    renderer: Box<dyn RenderBackend>,
+    width: u32,
+    height: u32,
+    /// Last pointer position in frame pixels
+    pointer: Point,
+}
+
+impl Popup {
+    fn open(
+        target: &EventLoopWindowTarget<()>,
+        id: PopupId,
+        options: PopupOptions,
+        backend: Backend,
+        clear_color: Color,
+        present_mode: PresentMode,
+    ) -> Result<Self, Error> {
+        let PopupOptions {
+            position,
+            width,
+            height,
+        } = options;
+        let builder = WindowBuilder::new()
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_always_on_top(true)
+            .with_inner_size(LogicalSize::new(width, height))
+            .with_position(PhysicalPosition::new(position.x as i32, position.y as i32));
+        #[cfg(windows)]
+        let builder = builder.with_skip_taskbar(true);
+        let window = builder.build(target)?;
+        let renderer = backend::create(backend, &window, width, height, clear_color, present_mode)?;
+        Ok(Self {
+            id,
+            window,
+            renderer,
+            width,
+            height,
+            pointer: Point::default(),
+        })
+    }
+
+    pub(crate) fn request_redraw(&self) {
+        self.window.request_redraw();
+    }
+
+    /// Present `list`, drawn for the whole popup
+    pub(crate) fn render(&mut self, list: &DrawList) -> Result<(), Error> {
+        let frame = Rect::new(0.0, 0.0, self.width as f32, self.height as f32);
+        self.renderer.render(list, &[frame])
+    }
+
+    /// The pointer event for a window event of the popup, if it is one
+    pub(crate) fn pointer_event(&mut self, event: &WindowEvent) -> Option<PointerEvent> {
+        let kind = match *event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let (x, y) = (position.x as f32, position.y as f32);
+                self.pointer = self.renderer.frame_position(x, y);
+                PointerEventKind::Move
+            }
+            WindowEvent::CursorLeft { .. } => PointerEventKind::Leave,
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => PointerEventKind::Press(button.into()),
+                ElementState::Released => PointerEventKind::Release(button.into()),
+            },
+            _ => return None,
+        };
+        Some(PointerEvent {
+            kind,
+            target: None,
+            position: self.pointer,
+        })
+    }
+}
+
+/// The application's popup windows
+#[derive(Default)]
+pub(crate) struct Popups {
+    pub(crate) open: Vec<Popup>,
+    /// Popups to open and close after this batch of events, which needs the
+    /// event loop
+    pub(crate) requests: Vec<PopupRequest>,
+    next_id: u64,
+}
+
+impl Popups {
+    pub(crate) fn next_id(&mut self) -> PopupId {
+        let id = PopupId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    pub(crate) fn get_mut(&mut self, window: WindowId) -> Option<&mut Popup> {
+        self.open.iter_mut().find(|p| p.window.id() == window)
+    }
+
+    pub(crate) fn set_clear_color(&mut self, color: Color) {
+        for popup in &mut self.open {
+            popup.renderer.set_clear_color(color);
+        }
+    }
+
+    pub(crate) fn is_open(&self, id: PopupId) -> bool {
+        let opening = self.requests.iter().rev().find_map(|r| match r {
+            PopupRequest::Open(open, _) => (*open == id).then_some(true),
+            PopupRequest::Close(closed) => (*closed == id).then_some(false),
+        });
+        opening.unwrap_or_else(|| self.open.iter().any(|p| p.id == id))
+    }
+
+    /// Open and close the popups requested
+    ///
+    /// A popup the platform refuses stays closed.
+    pub(crate) fn apply(
+        &mut self,
+        target: &EventLoopWindowTarget<()>,
+        backend: Backend,
+        clear_color: Color,
+        present_mode: PresentMode,
+    ) {
+        for request in std::mem::take(&mut self.requests) {
+            match request {
+                PopupRequest::Open(id, options) => {
+                    if let Ok(popup) =
+                        Popup::open(target, id, options, backend, clear_color, present_mode)
+                    {
+                        popup.request_redraw();
+                        self.open.push(popup);
+                    }
+                }
+                PopupRequest::Close(id) => self.open.retain(|p| p.id != id),
+            }
+        }
+    }
+}
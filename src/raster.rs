@@ -0,0 +1,148 @@
+use crate::geometry::{Point, Rect};
+
+/// Anti-aliased coverage values for a pixel-aligned region of the frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+    /// Row-major coverage in `0.0..=1.0`
+    pub data: Vec<f32>,
+}
+
+impl Mask {
+    pub fn bounds(&self) -> Rect {
+        Rect::new(
+            self.x as f32,
+            self.y as f32,
+            self.width as f32,
+            self.height as f32,
+        )
+    }
+
+    /// Coverage at an absolute frame position, zero outside the mask
+    pub fn coverage(&self, x: i32, y: i32) -> f32 {
+        let (mx, my) = (x - self.x, y - self.y);
+        if mx < 0 || my < 0 || mx as usize >= self.width || my as usize >= self.height {
+            return 0.0;
+        }
+        self.data[my as usize * self.width + mx as usize]
+    }
+}
+
+/// Rasterize closed polygons with the non-zero fill rule, limited to `region`
+pub fn rasterize(polygons: &[Vec<Point>], region: Rect) -> Option<Mask> {
+    let mut bounds: Option<Rect> = None;
+    for p in polygons.iter().flatten() {
+        let r = Rect::new(p.x, p.y, 0.0, 0.0);
+        bounds = Some(match bounds {
+            Some(b) => Rect::from_points(
+                Point::new(b.min_x().min(r.x), b.min_y().min(r.y)),
+                Point::new(b.max_x().max(r.x), b.max_y().max(r.y)),
+            ),
+            None => r,
+        });
+    }
+    let bounds = bounds?;
+    // Grow by a pixel so edges lying exactly on the boundary still get coverage
+    let area = bounds
+        .inset(-1.0)
+        .round_out()
+        .intersect(&region.round_out())?;
+    let (x0, y0) = (area.x as i32, area.y as i32);
+    let (width, height) = (area.width as usize, area.height as usize);
+    let stride = width + 2;
+    let mut acc = vec![0.0f32; stride * height];
+
+    for polygon in polygons {
+        for i in 0..polygon.len() {
+            let a = polygon[i];
+            let b = polygon[(i + 1) % polygon.len()];
+            add_line(
+                &mut acc,
+                stride,
+                width as f32,
+                height,
+                Point::new(a.x - x0 as f32, a.y - y0 as f32),
+                Point::new(b.x - x0 as f32, b.y - y0 as f32),
+            );
+        }
+    }
+
+    let mut data = vec![0.0f32; width * height];
+    for row in 0..height {
+        let mut sum = 0.0;
+        for col in 0..width {
+            sum += acc[row * stride + col];
+            data[row * width + col] = sum.abs().min(1.0);
+        }
+    }
+    Some(Mask {
+        x: x0,
+        y: y0,
+        width,
+        height,
+        data,
+    })
+}
+
+/// Accumulate the signed area contribution of one edge
+fn add_line(acc: &mut [f32], stride: usize, width: f32, height: usize, p0: Point, p1: Point) {
+    if p0.y == p1.y {
+        return;
+    }
+    let (dir, p0, p1) = if p0.y < p1.y {
+        (1.0, p0, p1)
+    } else {
+        (-1.0, p1, p0)
+    };
+    let dxdy = (p1.x - p0.x) / (p1.y - p0.y);
+    let y_start = p0.y.max(0.0);
+    let y_end = p1.y.min(height as f32);
+    if y_start >= y_end {
+        return;
+    }
+    let mut x = p0.x + (y_start - p0.y) * dxdy;
+    for row in (y_start as usize)..(y_end.ceil() as usize).min(height) {
+        let top = (row as f32).max(y_start);
+        let bottom = ((row + 1) as f32).min(y_end);
+        let dy = bottom - top;
+        let x_next = x + dxdy * dy;
+        let d = dy * dir;
+        // Clamping horizontally keeps the winding contribution of edges left of the region
+        let xa = x.clamp(0.0, width);
+        let xb = x_next.clamp(0.0, width);
+        let (x0, x1) = if xa < xb { (xa, xb) } else { (xb, xa) };
+        let line = row * stride;
+        let x0_floor = x0.floor();
+        let x0i = x0_floor as usize;
+        let x1_ceil = x1.ceil();
+        let x1i = x1_ceil as usize;
+        if x1i <= x0i + 1 {
+            let xmf = 0.5 * (xa + xb) - x0_floor;
+            acc[line + x0i] += d - d * xmf;
+            acc[line + x0i + 1] += d * xmf;
+        } else {
+            let s = (x1 - x0).recip();
+            let x0f = x0 - x0_floor;
+            let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+            let x1f = x1 - x1_ceil + 1.0;
+            let am = 0.5 * s * x1f * x1f;
+            acc[line + x0i] += d * a0;
+            if x1i == x0i + 2 {
+                acc[line + x0i + 1] += d * (1.0 - a0 - am);
+            } else {
+                let a1 = s * (1.5 - x0f);
+                acc[line + x0i + 1] += d * (a1 - a0);
+                for xi in x0i + 2..x1i - 1 {
+                    acc[line + xi] += d * s;
+                }
+                let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                acc[line + x1i - 1] += d * (1.0 - a2 - am);
+            }
+            acc[line + x1i] += d * am;
+        }
+        x = x_next;
+    }
+}
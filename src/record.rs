@@ -0,0 +1,492 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::drag::{DragEvent, DragEventKind};
+use crate::error::Error;
+use crate::event::InputEvent;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::{GamepadAxis, GamepadButton, GamepadEvent, GamepadEventKind};
+use crate::geometry::Point;
+use crate::gesture::{GestureEvent, GestureKind};
+use crate::keyboard::{Composition, Key, KeyCode, KeyEvent, Modifiers, TextEvent};
+use crate::pointer::{MouseButton, PointerEvent, PointerEventKind};
+use crate::scroll::ScrollEvent;
+use crate::widget::WidgetId;
+
+/// First line of a recording, naming its format
+const HEADER: &str = "wixe-input 1";
+
+/// Input events being written to a file as the application gets them
+///
+/// Each line holds the microseconds since recording began, then the event.
+pub(crate) struct Recorder {
+    file: BufWriter<File>,
+    start: Instant,
+    /// First failure to write, reported when recording stops
+    error: Option<std::io::Error>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> Result<Self, Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{HEADER}")?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+            error: None,
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &InputEvent) {
+        if self.error.is_some() {
+            return;
+        }
+        let mut line = self.start.elapsed().as_micros().to_string();
+        write_event(&mut line, event);
+        if let Err(e) = writeln!(self.file, "{line}") {
+            self.error = Some(e);
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Result<(), Error> {
+        match self.error.take() {
+            Some(e) => Err(e.into()),
+            None => Ok(self.file.flush()?),
+        }
+    }
+}
+
+/// A recording being played back, each event at its time since the start
+pub(crate) struct Replay {
+    events: VecDeque<(Duration, InputEvent)>,
+    start: Instant,
+}
+
+impl Replay {
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let text = std::fs::read_to_string(path)?;
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(Error::InvalidRecording(1));
+        }
+        let events = lines
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| parse_line(line).ok_or(Error::InvalidRecording(i + 1)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            events,
+            start: Instant::now(),
+        })
+    }
+
+    /// When the next event is due, `None` once all were played
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        Some(self.start + self.events.front()?.0)
+    }
+
+    /// The events due by `now`
+    pub(crate) fn due(&mut self, now: Instant) -> Vec<InputEvent> {
+        let mut due = Vec::new();
+        while self.deadline().is_some_and(|deadline| deadline <= now) {
+            due.extend(self.events.pop_front().map(|(_, event)| event));
+        }
+        due
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+fn parse_line(line: &str) -> Option<(Duration, InputEvent)> {
+    let mut tokens = line.split(' ');
+    let time = Duration::from_micros(tokens.next()?.parse().ok()?);
+    let event = parse_event(&mut tokens)?;
+    tokens.next().is_none().then_some((time, event))
+}
+
+fn write_event(out: &mut String, event: &InputEvent) {
+    match event {
+        InputEvent::Pointer(e) => {
+            out.push_str(" pointer");
+            match e.kind {
+                PointerEventKind::Press(b) => write_button(out, "press", b),
+                PointerEventKind::Release(b) => write_button(out, "release", b),
+                PointerEventKind::DoubleClick(b) => write_button(out, "double", b),
+                PointerEventKind::TripleClick(b) => write_button(out, "triple", b),
+                PointerEventKind::Move => out.push_str(" move"),
+                PointerEventKind::Enter => out.push_str(" enter"),
+                PointerEventKind::Leave => out.push_str(" leave"),
+            }
+            write_target(out, e.target);
+            write_point(out, e.position);
+        }
+        InputEvent::Drag(e) => {
+            let kind = match e.kind {
+                DragEventKind::Enter => "enter",
+                DragEventKind::Over => "over",
+                DragEventKind::Leave => "leave",
+                DragEventKind::Drop => "drop",
+            };
+            let _ = write!(out, " drag {kind}");
+            write_target(out, Some(e.target));
+            write_point(out, e.position);
+        }
+        InputEvent::Key(e) => {
+            out.push_str(" key ");
+            match e.key {
+                Key::Character(c) => {
+                    let _ = write!(out, "Character({})", c as u32);
+                }
+                key => {
+                    let _ = write!(out, "{key:?}");
+                }
+            }
+            let m = e.modifiers;
+            let flags = [m.shift, m.ctrl, m.alt, m.logo, e.pressed, e.repeat];
+            let _ = write!(out, " {} ", e.code.0);
+            for (set, flag) in flags.into_iter().zip("scalpr".chars()) {
+                out.push(if set { flag } else { '-' });
+            }
+            write_target(out, e.target);
+        }
+        InputEvent::Text(e) => {
+            out.push_str(" text ");
+            write_text(out, &e.text);
+            write_target(out, e.target);
+        }
+        InputEvent::Composition(e) => {
+            out.push_str(" composition ");
+            write_text(out, &e.text);
+            match &e.cursor {
+                Some(cursor) => {
+                    let _ = write!(out, " {}..{}", cursor.start, cursor.end);
+                }
+                None => out.push_str(" -"),
+            }
+            write_target(out, e.target);
+        }
+        InputEvent::Scroll(e) => {
+            out.push_str(" scroll");
+            write_target(out, Some(e.target));
+            write_point(out, e.delta);
+            write_point(out, e.position);
+        }
+        InputEvent::Gesture(e) => {
+            out.push_str(" gesture");
+            match e.kind {
+                GestureKind::Tap => out.push_str(" tap"),
+                GestureKind::Drag { delta } => {
+                    out.push_str(" drag");
+                    write_point(out, delta);
+                }
+                GestureKind::DragEnd => out.push_str(" dragend"),
+                GestureKind::Swipe { velocity } => {
+                    out.push_str(" swipe");
+                    write_point(out, velocity);
+                }
+                GestureKind::Pinch {
+                    scale,
+                    rotation,
+                    pan,
+                } => {
+                    let _ = write!(out, " pinch {scale} {rotation}");
+                    write_point(out, pan);
+                }
+                GestureKind::PinchEnd => out.push_str(" pinchend"),
+                GestureKind::LongPress => out.push_str(" longpress"),
+            }
+            write_target(out, e.target);
+            write_point(out, e.position);
+        }
+        #[cfg(feature = "gamepad")]
+        InputEvent::Gamepad(e) => {
+            let _ = write!(out, " gamepad {}", e.gamepad);
+            match e.kind {
+                GamepadEventKind::Connected => out.push_str(" connected"),
+                GamepadEventKind::Disconnected => out.push_str(" disconnected"),
+                GamepadEventKind::Press(button) => {
+                    let _ = write!(out, " press {button:?}");
+                }
+                GamepadEventKind::Release(button) => {
+                    let _ = write!(out, " release {button:?}");
+                }
+                GamepadEventKind::Axis(axis, value) => {
+                    let _ = write!(out, " axis {axis:?} {value}");
+                }
+            }
+        }
+    }
+}
+
+fn parse_event<'a>(t: &mut impl Iterator<Item = &'a str>) -> Option<InputEvent> {
+    let event = match t.next()? {
+        "pointer" => {
+            let kind = match t.next()? {
+                "press" => PointerEventKind::Press(parse_button(t.next()?)?),
+                "release" => PointerEventKind::Release(parse_button(t.next()?)?),
+                "double" => PointerEventKind::DoubleClick(parse_button(t.next()?)?),
+                "triple" => PointerEventKind::TripleClick(parse_button(t.next()?)?),
+                "move" => PointerEventKind::Move,
+                "enter" => PointerEventKind::Enter,
+                "leave" => PointerEventKind::Leave,
+                _ => return None,
+            };
+            InputEvent::Pointer(PointerEvent {
+                kind,
+                target: parse_target(t.next()?)?,
+                position: parse_point(t)?,
+            })
+        }
+        "drag" => {
+            let kind = match t.next()? {
+                "enter" => DragEventKind::Enter,
+                "over" => DragEventKind::Over,
+                "leave" => DragEventKind::Leave,
+                "drop" => DragEventKind::Drop,
+                _ => return None,
+            };
+            InputEvent::Drag(DragEvent {
+                kind,
+                target: parse_target(t.next()?)??,
+                position: parse_point(t)?,
+            })
+        }
+        "key" => {
+            let key = parse_key(t.next()?)?;
+            let code = KeyCode(t.next()?.parse().ok()?);
+            let flags: Vec<bool> = t.next()?.chars().map(|c| c != '-').collect();
+            let [shift, ctrl, alt, logo, pressed, repeat] = flags[..] else {
+                return None;
+            };
+            InputEvent::Key(KeyEvent {
+                key,
+                code,
+                modifiers: Modifiers {
+                    shift,
+                    ctrl,
+                    alt,
+                    logo,
+                },
+                pressed,
+                repeat,
+                target: parse_target(t.next()?)?,
+            })
+        }
+        "text" => InputEvent::Text(TextEvent {
+            text: parse_text(t.next()?)?,
+            target: parse_target(t.next()?)?,
+        }),
+        "composition" => {
+            let text = parse_text(t.next()?)?;
+            let cursor = match t.next()? {
+                "-" => None,
+                range => {
+                    let (start, end) = range.split_once("..")?;
+                    Some(start.parse().ok()?..end.parse().ok()?)
+                }
+            };
+            InputEvent::Composition(Composition {
+                text,
+                cursor,
+                target: parse_target(t.next()?)?,
+            })
+        }
+        "scroll" => InputEvent::Scroll(ScrollEvent {
+            target: parse_target(t.next()?)??,
+            delta: parse_point(t)?,
+            position: parse_point(t)?,
+        }),
+        "gesture" => {
+            let kind = match t.next()? {
+                "tap" => GestureKind::Tap,
+                "drag" => GestureKind::Drag {
+                    delta: parse_point(t)?,
+                },
+                "dragend" => GestureKind::DragEnd,
+                "swipe" => GestureKind::Swipe {
+                    velocity: parse_point(t)?,
+                },
+                "pinch" => GestureKind::Pinch {
+                    scale: t.next()?.parse().ok()?,
+                    rotation: t.next()?.parse().ok()?,
+                    pan: parse_point(t)?,
+                },
+                "pinchend" => GestureKind::PinchEnd,
+                "longpress" => GestureKind::LongPress,
+                _ => return None,
+            };
+            InputEvent::Gesture(GestureEvent {
+                kind,
+                target: parse_target(t.next()?)?,
+                position: parse_point(t)?,
+            })
+        }
+        #[cfg(feature = "gamepad")]
+        "gamepad" => {
+            let gamepad = t.next()?.parse().ok()?;
+            let kind = match t.next()? {
+                "connected" => GamepadEventKind::Connected,
+                "disconnected" => GamepadEventKind::Disconnected,
+                "press" => GamepadEventKind::Press(parse_gamepad_button(t.next()?)?),
+                "release" => GamepadEventKind::Release(parse_gamepad_button(t.next()?)?),
+                "axis" => {
+                    let axis = parse_gamepad_axis(t.next()?)?;
+                    GamepadEventKind::Axis(axis, t.next()?.parse().ok()?)
+                }
+                _ => return None,
+            };
+            InputEvent::Gamepad(GamepadEvent { gamepad, kind })
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+fn write_button(out: &mut String, kind: &str, button: MouseButton) {
+    let _ = match button {
+        MouseButton::Left => write!(out, " {kind} left"),
+        MouseButton::Right => write!(out, " {kind} right"),
+        MouseButton::Middle => write!(out, " {kind} middle"),
+        MouseButton::Other(n) => write!(out, " {kind} {n}"),
+    };
+}
+
+fn parse_button(token: &str) -> Option<MouseButton> {
+    let button = match token {
+        "left" => MouseButton::Left,
+        "right" => MouseButton::Right,
+        "middle" => MouseButton::Middle,
+        n => MouseButton::Other(n.parse().ok()?),
+    };
+    Some(button)
+}
+
+fn write_target(out: &mut String, target: Option<WidgetId>) {
+    let _ = match target {
+        Some(id) => write!(out, " {:x}", id.0),
+        None => write!(out, " -"),
+    };
+}
+
+/// A widget id or `-` for none; `None` if it is neither
+fn parse_target(token: &str) -> Option<Option<WidgetId>> {
+    match token {
+        "-" => Some(None),
+        id => Some(Some(WidgetId(u64::from_str_radix(id, 16).ok()?))),
+    }
+}
+
+fn write_point(out: &mut String, p: Point) {
+    let _ = write!(out, " {} {}", p.x, p.y);
+}
+
+fn parse_point<'a>(t: &mut impl Iterator<Item = &'a str>) -> Option<Point> {
+    Some(Point::new(t.next()?.parse().ok()?, t.next()?.parse().ok()?))
+}
+
+/// Text as one token, with spaces, `%` and other unprintable bytes as `%XX`
+fn write_text(out: &mut String, text: &str) {
+    if text.is_empty() {
+        out.push('%');
+    }
+    for byte in text.bytes() {
+        match byte {
+            b'!'..=b'~' if byte != b'%' => out.push(byte as char),
+            _ => {
+                let _ = write!(out, "%{byte:02X}");
+            }
+        }
+    }
+}
+
+fn parse_text(token: &str) -> Option<String> {
+    if token == "%" {
+        return Some(String::new());
+    }
+    let mut bytes = Vec::new();
+    let mut rest = token.as_bytes();
+    while let [first, tail @ ..] = rest {
+        if *first == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(*first);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn parse_key(token: &str) -> Option<Key> {
+    if let Some(n) = token.strip_prefix("Character(") {
+        return Some(Key::Character(char::from_u32(
+            n.strip_suffix(')')?.parse().ok()?,
+        )?));
+    }
+    if let Some(n) = token.strip_prefix("F(") {
+        return Some(Key::F(n.strip_suffix(')')?.parse().ok()?));
+    }
+    use Key::*;
+    let named = [
+        Enter,
+        Tab,
+        Space,
+        Backspace,
+        Delete,
+        Insert,
+        Escape,
+        ArrowLeft,
+        ArrowRight,
+        ArrowUp,
+        ArrowDown,
+        Home,
+        End,
+        PageUp,
+        PageDown,
+        Shift,
+        Control,
+        Alt,
+        Super,
+        Unidentified,
+    ];
+    named.into_iter().find(|key| format!("{key:?}") == token)
+}
+
+#[cfg(feature = "gamepad")]
+fn parse_gamepad_button(token: &str) -> Option<GamepadButton> {
+    use GamepadButton::*;
+    let buttons = [
+        South,
+        East,
+        North,
+        West,
+        LeftBumper,
+        RightBumper,
+        LeftTrigger,
+        RightTrigger,
+        Select,
+        Start,
+        Mode,
+        LeftStick,
+        RightStick,
+        DPadUp,
+        DPadDown,
+        DPadLeft,
+        DPadRight,
+        Unknown,
+    ];
+    buttons.into_iter().find(|b| format!("{b:?}") == token)
+}
+
+#[cfg(feature = "gamepad")]
+fn parse_gamepad_axis(token: &str) -> Option<GamepadAxis> {
+    use GamepadAxis::*;
+    let axes = [LeftStickX, LeftStickY, RightStickX, RightStickY, Unknown];
+    axes.into_iter().find(|a| format!("{a:?}") == token)
+}
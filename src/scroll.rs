@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+
+use crate::geometry::Point;
+use crate::widget::WidgetId;
+
+/// Momentum below this speed in frame pixels per second stops
+const MIN_SPEED: f32 = 20.0;
+/// Smoothed scrolling this close to done finishes at once
+const SNAP: f32 = 0.5;
+/// Seconds without trackpad movement after which a swipe has no momentum
+const STILL: f32 = 0.1;
+
+/// How wheel and trackpad input scrolls, in `WindowOptions::scroll`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollOptions {
+    /// Frame pixels scrolled per wheel notch
+    pub line_height: f32,
+    /// About how long a wheel notch takes to scroll, zero to jump at once
+    pub smoothing: Duration,
+    /// Keep scrolling after a trackpad swipe ends, slowing down
+    ///
+    /// macOS adds momentum of its own, so it is off there by default.
+    pub momentum: bool,
+    /// Fraction of momentum lost per second
+    pub friction: f32,
+}
+
+impl Default for ScrollOptions {
+    fn default() -> Self {
+        Self {
+            line_height: 40.0,
+            smoothing: Duration::from_millis(100),
+            momentum: !cfg!(target_os = "macos"),
+            friction: 0.95,
+        }
+    }
+}
+
+/// The wheel or trackpad scrolled a widget, delivered through
+/// `Application::scroll_event`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollEvent {
+    /// Innermost widget under the pointer made scrollable with
+    /// `Context::set_scrollable`
+    pub target: WidgetId,
+    /// How far the content moves in frame pixels, positive down and right as
+    /// when scrolling up, e.g. for `Viewport::pan`; subtract it from a scroll
+    /// offset
+    pub delta: Point,
+    /// Pointer position in frame pixels
+    pub position: Point,
+}
+
+/// Turns wheel and trackpad input into smoothed scroll events
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scroller {
+    pub(crate) options: ScrollOptions,
+    target: Option<WidgetId>,
+    position: Point,
+    /// Wheel scrolling not yet delivered
+    pending: Point,
+    /// Momentum in frame pixels per second
+    velocity: Point,
+    /// Trackpad is being touched, so momentum waits for the swipe to end
+    touching: bool,
+    last: Option<Instant>,
+}
+
+impl Scroller {
+    fn event(&self, target: WidgetId, delta: Point) -> Option<ScrollEvent> {
+        (delta != Point::ZERO).then_some(ScrollEvent {
+            target,
+            delta,
+            position: self.position,
+        })
+    }
+
+    /// Start scrolling another widget, dropping what was left for the last
+    fn retarget(&mut self, target: WidgetId, position: Point) {
+        if self.target != Some(target) {
+            self.target = Some(target);
+            self.pending = Point::ZERO;
+            self.velocity = Point::ZERO;
+        }
+        self.position = position;
+    }
+
+    /// The wheel turned by `lines` notches
+    pub(crate) fn lines(
+        &mut self,
+        target: WidgetId,
+        position: Point,
+        lines: Point,
+        now: Instant,
+    ) -> Option<ScrollEvent> {
+        self.retarget(target, position);
+        let h = self.options.line_height;
+        let delta = Point::new(lines.x * h, lines.y * h);
+        if self.options.smoothing.is_zero() {
+            return self.event(target, delta);
+        }
+        if !self.is_animating() {
+            self.last = Some(now);
+        }
+        self.pending = Point::new(self.pending.x + delta.x, self.pending.y + delta.y);
+        self.velocity = Point::ZERO;
+        None
+    }
+
+    /// The trackpad moved by `delta` frame pixels; `touching` is false once
+    /// the fingers lift
+    pub(crate) fn pixels(
+        &mut self,
+        target: WidgetId,
+        position: Point,
+        delta: Point,
+        touching: bool,
+        now: Instant,
+    ) -> Option<ScrollEvent> {
+        self.retarget(target, position);
+        if self.options.momentum {
+            let dt = self
+                .last
+                .map_or(f32::INFINITY, |last| now.duration_since(last).as_secs_f32());
+            if dt > STILL {
+                // The fingers rested, or this is a new swipe
+                self.velocity = Point::ZERO;
+            } else if dt > 0.0 && delta != Point::ZERO {
+                // Average out uneven event timing
+                let speed = Point::new(delta.x / dt, delta.y / dt);
+                self.velocity = self.velocity.lerp(speed, 0.5);
+            }
+            self.last = Some(now);
+        }
+        self.touching = touching;
+        self.event(target, delta)
+    }
+
+    /// Whether scrolling goes on without input, so `tick` should run every frame
+    pub(crate) fn is_animating(&self) -> bool {
+        self.pending != Point::ZERO || (!self.touching && self.velocity != Point::ZERO)
+    }
+
+    /// Deliver smoothed scrolling and momentum up to `now`
+    pub(crate) fn tick(&mut self, now: Instant) -> Option<ScrollEvent> {
+        let target = self.target?;
+        if !self.is_animating() {
+            return None;
+        }
+        let dt = self
+            .last
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f32());
+        self.last = Some(now);
+
+        let mut delta = Point::ZERO;
+        if self.pending != Point::ZERO {
+            let smoothing = self.options.smoothing.as_secs_f32();
+            // Most of the way in `smoothing`, slowing down towards the end
+            let step = 1.0 - (-3.0 * dt / smoothing).exp();
+            delta = Point::ZERO.lerp(self.pending, step);
+            if self.pending.distance(delta) < SNAP {
+                delta = self.pending;
+            }
+            self.pending = Point::new(self.pending.x - delta.x, self.pending.y - delta.y);
+        }
+        if !self.touching && self.velocity != Point::ZERO {
+            delta.x += self.velocity.x * dt;
+            delta.y += self.velocity.y * dt;
+            let decay = (1.0 - self.options.friction).powf(dt);
+            self.velocity = Point::new(self.velocity.x * decay, self.velocity.y * decay);
+            if self.velocity.distance(Point::ZERO) < MIN_SPEED {
+                self.velocity = Point::ZERO;
+            }
+        }
+        self.event(target, delta)
+    }
+
+    /// Stop smoothing and momentum, as when the pointer is pressed
+    pub(crate) fn stop(&mut self) {
+        self.pending = Point::ZERO;
+        self.velocity = Point::ZERO;
+        self.last = None;
+    }
+}
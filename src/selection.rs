@@ -0,0 +1,264 @@
+use std::ops::Range;
+
+use crate::clipboard::Clipboard;
+use crate::color::Color;
+use crate::draw_list::LayerRecorder;
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::text::TextLayout;
+use crate::theme::Theme;
+
+/// A step the caret takes when a selection is extended from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaretMove {
+    /// One character back in reading order
+    Left,
+    /// One character forward in reading order
+    Right,
+    Up,
+    Down,
+    LineStart,
+    LineEnd,
+    TextStart,
+    TextEnd,
+}
+
+/// Selected text within a `TextLayout` drawn in some rectangle
+///
+/// Feed it pointer presses, drags, double and triple clicks and shift+arrow
+/// keys, draw the highlight behind the text with `draw_theme_selection`, or
+/// `draw_selection` in a color of its own, and `copy` it on Ctrl+C. Positions are byte offsets into the layout's text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextSelection {
+    /// Where the selection was started
+    pub anchor: usize,
+    /// Where the selection ends, with the caret
+    pub focus: usize,
+    dragging: bool,
+}
+
+impl TextSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selected bytes, from whichever end comes first
+    pub fn range(&self) -> Range<usize> {
+        self.anchor.min(self.focus)..self.anchor.max(self.focus)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    /// Drop the selection, leaving the caret where it was
+    pub fn collapse(&mut self) {
+        self.anchor = self.focus;
+    }
+
+    pub fn select_all(&mut self, layout: &TextLayout) {
+        (self.anchor, self.focus) = (0, layout.text().len());
+    }
+
+    /// The selected part of the layout's text, empty if the text has since changed under it
+    pub fn selected_text<'a>(&self, layout: &'a TextLayout) -> &'a str {
+        layout.text().get(self.range()).unwrap_or_default()
+    }
+
+    /// Put the selected text on the clipboard, unless nothing is selected
+    pub fn copy(&self, layout: &TextLayout, clipboard: &mut Clipboard) -> Result<(), Error> {
+        match self.selected_text(layout) {
+            "" => Ok(()),
+            text => clipboard.set_text(text),
+        }
+    }
+
+    /// Start selecting at the pointer position `p`, if it is over the layout drawn in `rect`
+    pub fn begin_drag(&mut self, layout: &TextLayout, rect: Rect, p: Point) -> bool {
+        if layout.block_rect(rect).contains(p) {
+            self.focus = layout.hit_test_point(rect, p);
+            self.anchor = self.focus;
+            self.dragging = true;
+        }
+        self.dragging
+    }
+
+    /// Move the selected end to the pointer, `false` if not dragging
+    pub fn drag_to(&mut self, layout: &TextLayout, rect: Rect, p: Point) -> bool {
+        if self.dragging {
+            self.focus = layout.hit_test_point(rect, p);
+        }
+        self.dragging
+    }
+
+    /// Select the word at the pointer position `p`, as on a double click
+    ///
+    /// A run of whitespace or of punctuation counts as a word of its own.
+    pub fn select_word(&mut self, layout: &TextLayout, rect: Rect, p: Point) -> bool {
+        if !layout.block_rect(rect).contains(p) {
+            return false;
+        }
+        let text = layout.text();
+        let at = layout.hit_test_point(rect, p);
+        // Past the last character of a line the word before the pointer is meant
+        let class = text[at..]
+            .chars()
+            .next()
+            .filter(|c| *c != '\n')
+            .or_else(|| text[..at].chars().next_back())
+            .map_or(CharClass::Space, CharClass::of);
+        let start = text[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| CharClass::of(*c) == class)
+            .last()
+            .map_or(at, |(i, _)| i);
+        let end = text[at..]
+            .char_indices()
+            .find(|(_, c)| CharClass::of(*c) != class)
+            .map_or(text.len(), |(i, _)| at + i);
+        (self.anchor, self.focus) = (start, end);
+        true
+    }
+
+    /// Select the line at the pointer position `p`, as on a triple click
+    pub fn select_line(&mut self, layout: &TextLayout, rect: Rect, p: Point) -> bool {
+        if !layout.block_rect(rect).contains(p) {
+            return false;
+        }
+        let line = layout.line_at(layout.hit_test_point(rect, p));
+        if let Some(line) = layout.lines().get(line) {
+            (self.anchor, self.focus) = (line.range.start, line.range.end);
+        }
+        true
+    }
+
+    pub fn end_drag(&mut self) {
+        self.dragging = false;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Move the selected end by `motion`, keeping the anchor, as for shift+arrow keys
+    pub fn extend(&mut self, layout: &TextLayout, rect: Rect, motion: CaretMove) {
+        let text = layout.text();
+        // The text may have changed under the selection since it was made
+        let focus = floor_boundary(text, self.focus);
+        self.anchor = floor_boundary(text, self.anchor);
+        let lines = layout.lines();
+        let line = layout.line_at(focus);
+        self.focus = match motion {
+            CaretMove::Left => text[..focus]
+                .char_indices()
+                .next_back()
+                .map_or(0, |(i, _)| i),
+            CaretMove::Right => text[focus..]
+                .chars()
+                .next()
+                .map_or(focus, |c| focus + c.len_utf8()),
+            CaretMove::Up | CaretMove::Down => {
+                let target = match motion {
+                    CaretMove::Up => line.checked_sub(1),
+                    _ => Some(line + 1).filter(|&l| l < lines.len()),
+                };
+                match target {
+                    Some(target) => layout.offset_on_line(rect, focus, target),
+                    None if motion == CaretMove::Up => 0,
+                    None => text.len(),
+                }
+            }
+            CaretMove::LineStart => lines.get(line).map_or(0, |l| l.range.start),
+            CaretMove::LineEnd => lines.get(line).map_or(text.len(), |l| l.range.end),
+            CaretMove::TextStart => 0,
+            CaretMove::TextEnd => text.len(),
+        };
+    }
+}
+
+/// `offset` within `text`, moved back to the start of the character it is in
+fn floor_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Kinds of characters a word selection does not cross between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Space,
+    Punctuation,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_alphanumeric() || c == '_' {
+            Self::Word
+        } else if c.is_whitespace() {
+            Self::Space
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
+impl Painter<'_> {
+    /// Highlight the selected part of `layout` as drawn in `rect`; draw it before the text
+    pub fn draw_selection(
+        &mut self,
+        layout: &TextLayout,
+        rect: Rect,
+        selection: &TextSelection,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        for r in layout.range_rects(rect, selection.range()) {
+            self.fill_rect(r, color);
+        }
+    }
+
+    /// Highlight the selected part of `layout` in the theme's selection color
+    pub fn draw_theme_selection(
+        &mut self,
+        layout: &TextLayout,
+        rect: Rect,
+        selection: &TextSelection,
+        theme: &Theme,
+    ) {
+        self.draw_selection(layout, rect, selection, theme.selection());
+    }
+}
+
+impl LayerRecorder<'_> {
+    /// Record the highlight of the selected part of `layout` as drawn in `rect`
+    pub fn draw_selection(
+        &mut self,
+        layout: &TextLayout,
+        rect: Rect,
+        selection: &TextSelection,
+        color: impl Into<Color>,
+    ) -> &mut Self {
+        let color = color.into();
+        for r in layout.range_rects(rect, selection.range()) {
+            self.fill_rect(r, color);
+        }
+        self
+    }
+
+    /// Record the highlight of the selected part of `layout` in the theme's
+    /// selection color
+    pub fn draw_theme_selection(
+        &mut self,
+        layout: &TextLayout,
+        rect: Rect,
+        selection: &TextSelection,
+        theme: &Theme,
+    ) -> &mut Self {
+        self.draw_selection(layout, rect, selection, theme.selection())
+    }
+}
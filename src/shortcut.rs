@@ -0,0 +1,238 @@
+use std::any::Any;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::keyboard::{Key, KeyEvent, Modifiers};
+use crate::widget::WidgetId;
+
+/// A key combination, parsed from text like `"Ctrl+Shift+S"` or `"F5"`
+///
+/// `Ctrl` and `Cmd` both stand for the command modifier, Command on macOS and
+/// Ctrl elsewhere, so one shortcut suits every platform. Use `Control` for
+/// the Ctrl key itself on macOS. `Alt` (or `Option`), `Shift` and `Super`
+/// (or `Win`, `Meta`) work as usual.
+///
+/// Displayed the way the platform shows shortcuts in menus, e.g. `⇧⌘S` on
+/// macOS and `Ctrl+Shift+S` elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Whether pressing the key of `event` triggers this shortcut
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        event.pressed && event.key == self.key && event.modifiers == self.modifiers
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Character(c.to_ascii_lowercase()));
+    }
+    let key = match name.to_ascii_lowercase().as_str() {
+        "enter" | "return" => Key::Enter,
+        "tab" => Key::Tab,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "insert" | "ins" => Key::Insert,
+        "escape" | "esc" => Key::Escape,
+        "left" => Key::ArrowLeft,
+        "right" => Key::ArrowRight,
+        "up" => Key::ArrowUp,
+        "down" => Key::ArrowDown,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "plus" => Key::Character('+'),
+        f => match f.strip_prefix('f')?.parse() {
+            Ok(n @ 1..=24) => Key::F(n),
+            _ => return None,
+        },
+    };
+    Some(key)
+}
+
+impl FromStr for Shortcut {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidShortcut(s.to_string());
+        // A trailing "++" names the plus key
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        let mut shortcut =
+            Shortcut::new(Modifiers::NONE, parse_key(key.trim()).ok_or_else(invalid)?);
+        let m = &mut shortcut.modifiers;
+        for name in modifiers.split('+').filter(|name| !name.is_empty()) {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" if cfg!(target_os = "macos") => m.logo = true,
+                "ctrl" | "cmd" | "command" | "control" => m.ctrl = true,
+                "shift" => m.shift = true,
+                "alt" | "option" => m.alt = true,
+                "super" | "win" | "meta" => m.logo = true,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(shortcut)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.modifiers;
+        if cfg!(target_os = "macos") {
+            let symbols = [(m.ctrl, "⌃"), (m.alt, "⌥"), (m.shift, "⇧"), (m.logo, "⌘")];
+            for (_, symbol) in symbols.iter().filter(|(held, _)| *held) {
+                f.write_str(symbol)?;
+            }
+        } else {
+            let names = [
+                (m.ctrl, "Ctrl+"),
+                (m.alt, "Alt+"),
+                (m.shift, "Shift+"),
+                (m.logo, "Super+"),
+            ];
+            for (_, name) in names.iter().filter(|(held, _)| *held) {
+                f.write_str(name)?;
+            }
+        }
+        match self.key {
+            Key::Character(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            Key::F(n) => write!(f, "F{n}"),
+            Key::ArrowLeft => f.write_str("Left"),
+            Key::ArrowRight => f.write_str("Right"),
+            Key::ArrowUp => f.write_str("Up"),
+            Key::ArrowDown => f.write_str("Down"),
+            Key::Escape => f.write_str("Esc"),
+            key => write!(f, "{key:?}"),
+        }
+    }
+}
+
+/// A shortcut triggered, delivered through `Application::shortcut`
+#[derive(Debug, Clone)]
+pub struct ShortcutEvent {
+    pub shortcut: Shortcut,
+    /// Widget with keyboard focus, set with `Context::focus`; `None` for
+    /// system-wide hotkeys
+    pub target: Option<WidgetId>,
+    action: Rc<dyn Any>,
+}
+
+impl ShortcutEvent {
+    pub(crate) fn new(shortcut: Shortcut, target: Option<WidgetId>, action: Rc<dyn Any>) -> Self {
+        Self {
+            shortcut,
+            target,
+            action,
+        }
+    }
+
+    /// The action registered for the shortcut, if it is a `T`
+    pub fn action<T: Any>(&self) -> Option<&T> {
+        self.action.downcast_ref()
+    }
+}
+
+struct Entry {
+    shortcut: Shortcut,
+    scope: Option<WidgetId>,
+    action: Rc<dyn Any>,
+}
+
+/// Keyboard shortcuts of the application, e.g.
+/// `ctx.shortcuts_mut().register("Ctrl+S", Msg::Save)`
+///
+/// A pressed shortcut is delivered through `Application::shortcut` in place of
+/// its key event. Shortcuts registered for a scope only work while focus is in
+/// that widget or its children, and win over those of outer scopes and
+/// unscoped ones.
+#[derive(Default)]
+pub struct Shortcuts {
+    entries: Vec<Entry>,
+}
+
+impl Shortcuts {
+    /// Run `action` on `shortcut` wherever focus is
+    ///
+    /// Fails if the text is no shortcut or it already has an unscoped action.
+    pub fn register(&mut self, shortcut: &str, action: impl Any) -> Result<(), Error> {
+        self.insert(shortcut.parse()?, None, Rc::new(action))
+    }
+
+    /// Run `action` on `shortcut` while focus is in `scope` or its children
+    ///
+    /// Fails if the text is no shortcut or it already has an action in `scope`.
+    pub fn register_in(
+        &mut self,
+        scope: WidgetId,
+        shortcut: &str,
+        action: impl Any,
+    ) -> Result<(), Error> {
+        self.insert(shortcut.parse()?, Some(scope), Rc::new(action))
+    }
+
+    fn insert(
+        &mut self,
+        shortcut: Shortcut,
+        scope: Option<WidgetId>,
+        action: Rc<dyn Any>,
+    ) -> Result<(), Error> {
+        if self
+            .entries
+            .iter()
+            .any(|e| (e.shortcut, e.scope) == (shortcut, scope))
+        {
+            return Err(Error::ShortcutConflict(shortcut.to_string()));
+        }
+        self.entries.push(Entry {
+            shortcut,
+            scope,
+            action,
+        });
+        Ok(())
+    }
+
+    /// Remove every shortcut running `action`
+    pub fn unregister<T: Any + PartialEq>(&mut self, action: &T) {
+        self.entries
+            .retain(|e| e.action.downcast_ref::<T>() != Some(action));
+    }
+
+    /// The first shortcut registered for `action`, e.g. for a menu to show
+    /// `shortcut.to_string()` beside the item
+    pub fn shortcut_for<T: Any + PartialEq>(&self, action: &T) -> Option<Shortcut> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.action.downcast_ref::<T>() == Some(action));
+        Some(entry?.shortcut)
+    }
+
+    /// The shortcut `event` triggers with focus in `scopes`, the focused
+    /// widget and its ancestors, innermost first
+    pub(crate) fn find(&self, event: &KeyEvent, scopes: &[WidgetId]) -> Option<ShortcutEvent> {
+        let matches = || self.entries.iter().filter(|e| e.shortcut.matches(event));
+        let entry = (scopes.iter())
+            .find_map(|&scope| matches().find(|e| e.scope == Some(scope)))
+            .or_else(|| matches().find(|e| e.scope.is_none()))?;
+        Some(ShortcutEvent::new(
+            entry.shortcut,
+            event.target,
+            entry.action.clone(),
+        ))
+    }
+}
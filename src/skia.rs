@@ -0,0 +1,348 @@
+//! Shape rasterization with tiny-skia, behind the `skia` feature
+//!
+//! Fills and strokes go through tiny-skia for its anti-aliasing and gradients.
+//! Images, text and effects are drawn by the software painter into the same frame.
+
+use std::borrow::Cow;
+
+use pixels::Pixels;
+use winit::window::Window;
+
+use crate::backend::{
+    self, CommandState, FramePresenter, PostProcess, PostProcessor, PresentMode, RenderBackend,
+    Scaling, Supersampler,
+};
+use crate::bitmap::Bitmap;
+use crate::blend::BlendMode;
+use crate::brush::{Brush, ColorStop};
+use crate::color::Color;
+use crate::draw_list::{DrawCmd, DrawList};
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::path::{Path, PathEl};
+use crate::stroke::{LineCap, LineJoin, Stroke};
+use crate::transform::Transform;
+
+/// tiny-skia rendering into a `pixels` frame buffer, scaled to the window
+pub struct SkiaBackend {
+    pixels: Pixels,
+    presenter: FramePresenter,
+    width: u32,
+    height: u32,
+    clear_color: Color,
+    post: PostProcessor,
+    supersampler: Option<Supersampler>,
+    render_scale: f32,
+}
+
+impl SkiaBackend {
+    pub fn new(
+        window: &Window,
+        width: u32,
+        height: u32,
+        clear_color: Color,
+        present_mode: PresentMode,
+    ) -> Result<Self, Error> {
+        let pixels = backend::open_pixels(window, width, height, present_mode)?;
+        Ok(Self {
+            presenter: FramePresenter::new(&pixels, window),
+            pixels,
+            width,
+            height,
+            clear_color,
+            post: PostProcessor::default(),
+            supersampler: None,
+            render_scale: 1.0,
+        })
+    }
+}
+
+impl RenderBackend for SkiaBackend {
+    fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.pixels.resize_surface(width, height);
+        self.presenter.resize_surface(width, height);
+        Ok(())
+    }
+
+    fn resize_buffer(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.pixels.resize_buffer(width, height);
+        self.presenter.resize_buffer();
+        (self.width, self.height) = (width, height);
+        self.post.resize(self.pixels.get_frame().len());
+        self.supersampler = Supersampler::new(self.render_scale, width, height);
+        Ok(())
+    }
+
+    fn render(&mut self, list: &DrawList, damage: &[Rect]) -> Result<(), Error> {
+        let (width, height) = (self.width, self.height);
+        let frame = self.post.target(self.pixels.get_frame_mut());
+        for region in damage {
+            match &mut self.supersampler {
+                Some(ss) => {
+                    let (area, scale) = (ss.region(*region), ss.scale());
+                    let (buffer, w, h) = ss.buffer();
+                    draw_region(buffer, w, h, area, scale, self.clear_color, list);
+                    ss.resolve(*region, frame, width, height);
+                }
+                None => draw_region(frame, width, height, *region, 1.0, self.clear_color, list),
+            }
+        }
+        self.post.finish(self.pixels.get_frame_mut(), width, height);
+        self.presenter
+            .present(&self.pixels, (width, height), self.clear_color)
+    }
+
+    fn frame_position(&self, x: f32, y: f32) -> Point {
+        self.presenter
+            .frame_position((self.width, self.height), x, y)
+    }
+
+    fn capture(&mut self) -> Bitmap {
+        let frame = self.pixels.get_frame().to_vec();
+        Bitmap::from_premultiplied(self.width, self.height, frame)
+    }
+
+    fn set_post_process(&mut self, hook: Option<PostProcess>) {
+        self.post.set(hook, self.pixels.get_frame_mut());
+    }
+
+    fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    fn set_scaling(&mut self, scaling: Scaling) {
+        self.presenter.set_scaling(scaling);
+    }
+
+    fn set_render_scale(&mut self, scale: f32) {
+        self.render_scale = scale;
+        self.supersampler = Supersampler::new(scale, self.width, self.height);
+    }
+}
+
+/// Repaint `region` of a frame from `list`, with everything drawn `scale` times larger
+fn draw_region(
+    frame: &mut [u8],
+    width: u32,
+    height: u32,
+    region: Rect,
+    scale: f32,
+    clear_color: Color,
+    list: &DrawList,
+) {
+    let mut painter = Painter::new(frame, width, height);
+    painter.push_clip(region);
+    painter.clear(clear_color);
+    let mut state = CommandState::new(painter.clip_rect());
+    if scale != 1.0 {
+        state.update(&DrawCmd::PushTransform(Transform::scale(scale, scale)));
+    }
+    let mut clip = ClipCache::default();
+    // Offscreen buffers of the open opacity groups, innermost last
+    let mut groups: Vec<(Vec<u8>, f32)> = Vec::new();
+    list.visit(|cmd| {
+        match cmd {
+            DrawCmd::PushOpacity(opacity) => {
+                groups.push((vec![0; frame.len()], *opacity));
+                return;
+            }
+            DrawCmd::PopOpacity => {
+                if let Some((pixels, opacity)) = groups.pop() {
+                    let target = groups.last_mut().map_or(&mut *frame, |g| &mut g.0);
+                    let bitmap = Bitmap::from_premultiplied(width, height, pixels);
+                    let mut painter = Painter::new(target, width, height);
+                    painter.push_clip(state.clip().unwrap_or_default());
+                    painter.push_blend_mode(state.blend_mode());
+                    painter.with_opacity(opacity, |p| p.blit(&bitmap, 0, 0));
+                }
+                return;
+            }
+            _ => {}
+        }
+        if state.update(cmd) || state.visible_area(cmd).is_none() {
+            return;
+        }
+        let target = groups.last_mut().map_or(&mut *frame, |g| &mut g.0);
+        if !draw_shape(target, width, height, &state, &mut clip, cmd) {
+            let mut painter = Painter::new(target, width, height);
+            painter.push_clip(state.clip().unwrap_or_default());
+            painter.push_transform(state.transform());
+            painter.push_blend_mode(state.blend_mode());
+            cmd.apply(&mut painter);
+        }
+    });
+}
+
+/// Mask for the last clip rectangle, rebuilt only when the clip changes
+#[derive(Default)]
+struct ClipCache {
+    rect: Option<Rect>,
+    mask: Option<tiny_skia::ClipMask>,
+}
+
+impl ClipCache {
+    fn get(&mut self, rect: Rect, width: u32, height: u32) -> Option<&tiny_skia::ClipMask> {
+        if self.rect != Some(rect) {
+            self.rect = Some(rect);
+            let frame = Rect::new(0.0, 0.0, width as f32, height as f32);
+            self.mask = if rect == frame {
+                None
+            } else {
+                let mut mask = tiny_skia::ClipMask::new();
+                tiny_skia::Rect::from_xywh(rect.x, rect.y, rect.width, rect.height)
+                    .map(tiny_skia::PathBuilder::from_rect)
+                    .and_then(|path| {
+                        mask.set_path(width, height, &path, tiny_skia::FillRule::Winding, false)
+                    })
+                    .map(|_| mask)
+            };
+        }
+        self.mask.as_ref()
+    }
+}
+
+/// Fill or stroke `cmd` with tiny-skia, `false` if it is not a shape
+fn draw_shape(
+    frame: &mut [u8],
+    width: u32,
+    height: u32,
+    state: &CommandState,
+    clip: &mut ClipCache,
+    cmd: &DrawCmd,
+) -> bool {
+    let (path, brush, stroke) = match cmd {
+        DrawCmd::FillRect { rect, brush } => (Cow::Owned(Path::rect(*rect)), brush, None),
+        DrawCmd::FillRoundedRect {
+            rect,
+            radius,
+            brush,
+        } => (Cow::Owned(Path::rounded_rect(*rect, *radius)), brush, None),
+        DrawCmd::FillPath { path, brush } => (Cow::Borrowed(path), brush, None),
+        DrawCmd::StrokePath {
+            path,
+            stroke,
+            brush,
+        } => (Cow::Borrowed(path), brush, Some(stroke)),
+        _ => return false,
+    };
+    if let Brush::Pattern(_) = brush {
+        return false;
+    }
+    let (Some(path), Some(shader), Some(clip_rect)) =
+        (to_path(&path), to_shader(brush), state.clip())
+    else {
+        return true;
+    };
+    let Some(mut pixmap) = tiny_skia::PixmapMut::from_bytes(frame, width, height) else {
+        return true;
+    };
+    let paint = tiny_skia::Paint {
+        shader,
+        blend_mode: to_blend_mode(state.blend_mode()),
+        anti_alias: true,
+        ..tiny_skia::Paint::default()
+    };
+    let transform = to_transform(state.transform());
+    let mask = clip.get(clip_rect, width, height);
+    match stroke {
+        Some(stroke) => {
+            pixmap.stroke_path(&path, &paint, &to_stroke(stroke), transform, mask);
+        }
+        None => {
+            pixmap.fill_path(&path, &paint, tiny_skia::FillRule::Winding, transform, mask);
+        }
+    }
+    true
+}
+
+fn to_path(path: &Path) -> Option<tiny_skia::Path> {
+    let mut pb = tiny_skia::PathBuilder::new();
+    for el in path.elements() {
+        match *el {
+            PathEl::MoveTo(p) => pb.move_to(p.x, p.y),
+            PathEl::LineTo(p) => pb.line_to(p.x, p.y),
+            PathEl::QuadTo(c, p) => pb.quad_to(c.x, c.y, p.x, p.y),
+            PathEl::CubicTo(c1, c2, p) => pb.cubic_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y),
+            PathEl::Close => pb.close(),
+        }
+    }
+    pb.finish()
+}
+
+fn to_shader(brush: &Brush) -> Option<tiny_skia::Shader<'static>> {
+    match brush {
+        // Only patterns need to borrow pixels, so `draw_shape` leaves them to the painter
+        Brush::Pattern(_) => None,
+        Brush::Solid(c) => Some(tiny_skia::Shader::SolidColor(to_color(*c))),
+        Brush::Linear(g) => tiny_skia::LinearGradient::new(
+            to_point(g.start),
+            to_point(g.end),
+            to_stops(&g.stops),
+            tiny_skia::SpreadMode::Pad,
+            tiny_skia::Transform::identity(),
+        ),
+        Brush::Radial(g) => tiny_skia::RadialGradient::new(
+            to_point(g.center),
+            to_point(g.center),
+            g.radius,
+            to_stops(&g.stops),
+            tiny_skia::SpreadMode::Pad,
+            tiny_skia::Transform::identity(),
+        ),
+    }
+}
+
+fn to_stops(stops: &[ColorStop]) -> Vec<tiny_skia::GradientStop> {
+    stops
+        .iter()
+        .map(|s| tiny_skia::GradientStop::new(s.offset, to_color(s.color)))
+        .collect()
+}
+
+fn to_stroke(stroke: &Stroke) -> tiny_skia::Stroke {
+    tiny_skia::Stroke {
+        width: stroke.width,
+        miter_limit: stroke.miter_limit,
+        line_cap: match stroke.cap {
+            LineCap::Butt => tiny_skia::LineCap::Butt,
+            LineCap::Round => tiny_skia::LineCap::Round,
+            LineCap::Square => tiny_skia::LineCap::Square,
+        },
+        line_join: match stroke.join {
+            LineJoin::Miter => tiny_skia::LineJoin::Miter,
+            LineJoin::Round => tiny_skia::LineJoin::Round,
+            LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+        },
+        dash: stroke
+            .dash_pattern()
+            .and_then(|pattern| tiny_skia::StrokeDash::new(pattern, stroke.dash_offset)),
+    }
+}
+
+fn to_blend_mode(mode: BlendMode) -> tiny_skia::BlendMode {
+    match mode {
+        BlendMode::SourceOver => tiny_skia::BlendMode::SourceOver,
+        BlendMode::DestinationOver => tiny_skia::BlendMode::DestinationOver,
+        BlendMode::SourceAtop => tiny_skia::BlendMode::SourceAtop,
+        BlendMode::DestinationOut => tiny_skia::BlendMode::DestinationOut,
+        BlendMode::Xor => tiny_skia::BlendMode::Xor,
+        BlendMode::Plus => tiny_skia::BlendMode::Plus,
+        BlendMode::Multiply => tiny_skia::BlendMode::Multiply,
+        BlendMode::Screen => tiny_skia::BlendMode::Screen,
+        BlendMode::Darken => tiny_skia::BlendMode::Darken,
+        BlendMode::Lighten => tiny_skia::BlendMode::Lighten,
+    }
+}
+
+fn to_transform(t: Transform) -> tiny_skia::Transform {
+    tiny_skia::Transform::from_row(t.a, t.b, t.c, t.d, t.e, t.f)
+}
+
+fn to_point(p: Point) -> tiny_skia::Point {
+    tiny_skia::Point::from_xy(p.x, p.y)
+}
+
+fn to_color(c: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba8(c.r, c.g, c.b, c.a)
+}
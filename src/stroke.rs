@@ -0,0 +1,331 @@
+use crate::brush::Brush;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::path::Path;
+use crate::raster;
+
+/// Shape of the ends of open subpaths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LineCap {
+    /// Ends exactly at the end point
+    #[default]
+    Butt,
+    Round,
+    /// Extends half the width past the end point
+    Square,
+}
+
+/// Shape of the corners between segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LineJoin {
+    /// Sharp corner, beveled once it grows longer than the miter limit
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// How the outline of a path is drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// Longest miter allowed, in multiples of half the width
+    pub miter_limit: f32,
+    /// Alternating dash and gap lengths, solid when empty
+    pub dash: Vec<f32>,
+    /// Distance into the dash pattern at which each subpath starts
+    pub dash_offset: f32,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dash: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Dash the stroke, starting `offset` into `pattern`
+    ///
+    /// An odd-length pattern is repeated to make it even, as in SVG.
+    pub fn dash(mut self, pattern: impl Into<Vec<f32>>, offset: f32) -> Self {
+        self.dash = pattern.into();
+        self.dash_offset = offset;
+        self
+    }
+
+    /// Round dots spaced 2 widths apart
+    pub fn dotted(self) -> Self {
+        let gap = self.width * 2.0;
+        self.cap(LineCap::Round).dash([0.0, gap], 0.0)
+    }
+
+    /// The dash pattern with an even number of entries, `None` when it draws solid
+    pub(crate) fn dash_pattern(&self) -> Option<Vec<f32>> {
+        let total: f32 = self.dash.iter().sum();
+        if self.dash.iter().any(|d| !d.is_finite() || *d < 0.0) || total <= 0.0 {
+            return None;
+        }
+        let mut pattern = self.dash.clone();
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_within(..);
+        }
+        Some(pattern)
+    }
+
+    /// How far the stroke can reach outside the path's bounds
+    pub fn extent(&self) -> f32 {
+        self.width / 2.0 * self.miter_limit.max(std::f32::consts::SQRT_2)
+    }
+
+    /// Polygons covering the stroked outline of `path`, for the non-zero rasterizer
+    ///
+    /// Every polygon is wound the same way, so overlapping pieces never cancel out.
+    pub fn outline(&self, path: &Path) -> Vec<Vec<Point>> {
+        let hw = self.width / 2.0;
+        let mut out = Vec::new();
+        if hw <= 0.0 {
+            return out;
+        }
+        let pattern = self.dash_pattern();
+        for (mut points, closed) in path.flatten_subpaths() {
+            points.dedup();
+            if closed && points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            match &pattern {
+                Some(pattern) if points.len() > 1 => {
+                    for mut dash in dashes(pattern, self.dash_offset, points, closed) {
+                        dash.dedup();
+                        self.outline_polyline(&mut out, &dash, false, hw);
+                    }
+                }
+                _ => self.outline_polyline(&mut out, &points, closed, hw),
+            }
+        }
+        for polygon in &mut out {
+            if signed_area(polygon) < 0.0 {
+                polygon.reverse();
+            }
+        }
+        out
+    }
+
+    fn outline_polyline(&self, out: &mut Vec<Vec<Point>>, points: &[Point], closed: bool, hw: f32) {
+        if points.len() == 1 {
+            let p = points[0];
+            match self.cap {
+                LineCap::Butt => {}
+                LineCap::Round => out.push(circle(p, hw)),
+                LineCap::Square => out.push(vec![
+                    Point::new(p.x - hw, p.y - hw),
+                    Point::new(p.x + hw, p.y - hw),
+                    Point::new(p.x + hw, p.y + hw),
+                    Point::new(p.x - hw, p.y + hw),
+                ]),
+            }
+            return;
+        }
+        let n = points.len();
+        let segments = if closed { n } else { n - 1 };
+        for i in 0..segments {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            let off = scale(normal(a, b), hw);
+            out.push(vec![add(a, off), add(b, off), sub(b, off), sub(a, off)]);
+        }
+        let joins = if closed { 0..n } else { 1..n - 1 };
+        for i in joins {
+            let prev = points[(i + n - 1) % n];
+            let (p, next) = (points[i], points[(i + 1) % n]);
+            self.join_at(out, prev, p, next, hw);
+        }
+        if !closed {
+            self.cap_at(out, points[1], points[0], hw);
+            self.cap_at(out, points[n - 2], points[n - 1], hw);
+        }
+    }
+
+    fn join_at(&self, out: &mut Vec<Vec<Point>>, prev: Point, p: Point, next: Point, hw: f32) {
+        let (d0, d1) = (direction(prev, p), direction(p, next));
+        let cross = d0.x * d1.y - d0.y * d1.x;
+        if cross.abs() < 1e-6 && d0.x * d1.x + d0.y * d1.y > 0.0 {
+            return;
+        }
+        // The outer side of the corner is opposite the way the path turns
+        let side = if cross > 0.0 { -hw } else { hw };
+        let (n0, n1) = (normal(prev, p), normal(p, next));
+        let (a, b) = (add(p, scale(n0, side)), add(p, scale(n1, side)));
+        match self.join {
+            LineJoin::Round => out.push(circle(p, hw)),
+            LineJoin::Bevel => out.push(vec![p, a, b]),
+            LineJoin::Miter => {
+                let bisector = Point::new(n0.x + n1.x, n0.y + n1.y);
+                let len = (bisector.x * bisector.x + bisector.y * bisector.y).sqrt();
+                // Ratio of the miter length to half the width is 1 / cos(angle / 2)
+                let cos_half = len / 2.0;
+                if cos_half <= 1e-6 || 1.0 / cos_half > self.miter_limit {
+                    out.push(vec![p, a, b]);
+                } else {
+                    let tip = add(p, scale(bisector, side / (len * cos_half)));
+                    out.push(vec![p, a, tip, b]);
+                }
+            }
+        }
+    }
+
+    fn cap_at(&self, out: &mut Vec<Vec<Point>>, from: Point, end: Point, hw: f32) {
+        match self.cap {
+            LineCap::Butt => {}
+            LineCap::Round => out.push(circle(end, hw)),
+            LineCap::Square => {
+                let (d, off) = (
+                    scale(direction(from, end), hw),
+                    scale(normal(from, end), hw),
+                );
+                let tip = add(end, d);
+                out.push(vec![
+                    add(end, off),
+                    add(tip, off),
+                    sub(tip, off),
+                    sub(end, off),
+                ]);
+            }
+        }
+    }
+}
+
+impl Painter<'_> {
+    pub fn stroke_path(&mut self, path: &Path, stroke: &Stroke, brush: impl Into<Brush>) {
+        let t = self.transform();
+        let mut polygons = stroke.outline(path);
+        for p in polygons.iter_mut().flatten() {
+            *p = t.apply(*p);
+        }
+        if let Some(mask) = raster::rasterize(&polygons, self.clip_rect()) {
+            self.fill_mask(&mask, &brush.into());
+        }
+    }
+
+    pub fn stroke_rect(&mut self, rect: Rect, stroke: &Stroke, brush: impl Into<Brush>) {
+        self.stroke_path(&Path::rect(rect), stroke, brush);
+    }
+
+    pub fn draw_line(&mut self, from: Point, to: Point, stroke: &Stroke, brush: impl Into<Brush>) {
+        self.stroke_path(&Path::line(from, to), stroke, brush);
+    }
+}
+
+/// Split a polyline into the open pieces an even-length dash pattern keeps
+fn dashes(pattern: &[f32], offset: f32, mut points: Vec<Point>, closed: bool) -> Vec<Vec<Point>> {
+    if closed {
+        points.push(points[0]);
+    }
+    let total: f32 = pattern.iter().sum();
+    let (mut index, mut skip) = (0, offset.rem_euclid(total));
+    while skip > pattern[index] {
+        skip -= pattern[index];
+        index = (index + 1) % pattern.len();
+    }
+    let mut remaining = pattern[index] - skip;
+    let mut on = index % 2 == 0;
+    let mut current = if on { vec![points[0]] } else { Vec::new() };
+    let mut out = Vec::new();
+    for w in points.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let len = a.distance(b);
+        let mut pos = 0.0;
+        while pos + remaining <= len {
+            pos += remaining;
+            let t = pos / len;
+            let p = Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+            current.push(p);
+            if on {
+                out.push(std::mem::take(&mut current));
+            }
+            on = !on;
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+        remaining -= len - pos;
+        if on {
+            current.push(b);
+        }
+    }
+    if on && !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+fn add(a: Point, b: Point) -> Point {
+    Point::new(a.x + b.x, a.y + b.y)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    Point::new(a.x - b.x, a.y - b.y)
+}
+
+fn scale(p: Point, s: f32) -> Point {
+    Point::new(p.x * s, p.y * s)
+}
+
+/// Unit vector from `a` towards `b`
+fn direction(a: Point, b: Point) -> Point {
+    let len = a.distance(b);
+    if len == 0.0 {
+        return Point::ZERO;
+    }
+    Point::new((b.x - a.x) / len, (b.y - a.y) / len)
+}
+
+/// Unit vector perpendicular to the segment from `a` to `b`
+fn normal(a: Point, b: Point) -> Point {
+    let d = direction(a, b);
+    Point::new(-d.y, d.x)
+}
+
+fn circle(center: Point, radius: f32) -> Vec<Point> {
+    let segments = ((radius * 4.0).ceil() as usize).clamp(8, 64);
+    (0..segments)
+        .map(|i| {
+            let a = i as f32 / segments as f32 * std::f32::consts::TAU;
+            Point::new(center.x + radius * a.cos(), center.y + radius * a.sin())
+        })
+        .collect()
+}
+
+fn signed_area(polygon: &[Point]) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f32>()
+        / 2.0
+}
@@ -0,0 +1,606 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::color::Color;
+use crate::error::Error;
+use crate::theme::{Token, Tokens};
+use crate::transition::{Easing, Transition};
+use crate::widget::WidgetId;
+
+/// How a widget looks, from stylesheets and overrides, e.g.
+/// `Style::default().background(red).radius(6.0)`
+///
+/// Properties nothing sets are `None`, left to the widget and the theme.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub background: Option<Color>,
+    /// Color of text
+    pub color: Option<Color>,
+    pub border_color: Option<Color>,
+    pub border_width: Option<f32>,
+    /// Corner radius
+    pub radius: Option<f32>,
+    pub padding: Option<f32>,
+    pub font_size: Option<f32>,
+    pub opacity: Option<f32>,
+    /// Properties that change gradually rather than at once, e.g. on hover
+    pub transitions: Vec<(Property, Transition)>,
+    /// Properties for while the widget is in a state, see `Style::on`
+    pub variants: Vec<(State, Style)>,
+}
+
+impl Style {
+    pub fn background(self, background: Color) -> Self {
+        Self {
+            background: Some(background),
+            ..self
+        }
+    }
+
+    pub fn color(self, color: Color) -> Self {
+        Self {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    pub fn border(self, width: f32, color: Color) -> Self {
+        Self {
+            border_width: Some(width),
+            border_color: Some(color),
+            ..self
+        }
+    }
+
+    pub fn radius(self, radius: f32) -> Self {
+        Self {
+            radius: Some(radius),
+            ..self
+        }
+    }
+
+    pub fn padding(self, padding: f32) -> Self {
+        Self {
+            padding: Some(padding),
+            ..self
+        }
+    }
+
+    pub fn font_size(self, font_size: f32) -> Self {
+        Self {
+            font_size: Some(font_size),
+            ..self
+        }
+    }
+
+    pub fn opacity(self, opacity: f32) -> Self {
+        Self {
+            opacity: Some(opacity),
+            ..self
+        }
+    }
+
+    /// Animate changes of `property` over `duration`, e.g.
+    /// `style.transition(Property::Background, millis(150), Easing::EaseOut)`
+    /// with `Duration::from_millis` as `millis`
+    pub fn transition(mut self, property: Property, duration: Duration, easing: Easing) -> Self {
+        self.set_transition(property, Transition { duration, easing });
+        self
+    }
+
+    /// How changes of `property` animate, if they do
+    pub fn transition_of(&self, property: Property) -> Option<Transition> {
+        (self.transitions.iter()).find_map(|&(p, transition)| (p == property).then_some(transition))
+    }
+
+    fn set_transition(&mut self, property: Property, transition: Transition) {
+        self.transitions.retain(|&(p, _)| p != property);
+        self.transitions.push((property, transition));
+    }
+
+    /// Properties for while the widget is in `state`, over the others, e.g.
+    /// `style.on(State::Hover, Style::default().background(lighter))`
+    pub fn on(mut self, state: State, style: Style) -> Self {
+        self.set_variant(state, &style);
+        self
+    }
+
+    fn set_variant(&mut self, state: State, style: &Style) {
+        match self.variants.iter_mut().find(|(s, _)| *s == state) {
+            Some((_, variant)) => variant.merge(style),
+            None => self.variants.push((state, style.clone())),
+        }
+    }
+
+    /// Take the properties of the variants for `states`, in the order of
+    /// `State::ALL`, dropping all variants
+    pub(crate) fn resolve_states(&mut self, states: &[State]) {
+        let variants = std::mem::take(&mut self.variants);
+        for state in State::ALL.into_iter().filter(|s| states.contains(s)) {
+            if let Some((_, variant)) = variants.iter().find(|(s, _)| *s == state) {
+                self.merge(variant);
+            }
+        }
+    }
+
+    /// The properties widgets take from the widget they are inside, as in
+    /// CSS: text color and size
+    pub fn inherited(&self) -> Style {
+        Style {
+            color: self.color,
+            font_size: self.font_size,
+            ..Style::default()
+        }
+    }
+
+    /// Take every property `other` sets
+    pub fn merge(&mut self, other: &Style) {
+        let Style {
+            background,
+            color,
+            border_color,
+            border_width,
+            radius,
+            padding,
+            font_size,
+            opacity,
+            transitions,
+            variants,
+        } = other;
+        self.background = background.or(self.background);
+        self.color = color.or(self.color);
+        self.border_color = border_color.or(self.border_color);
+        self.border_width = border_width.or(self.border_width);
+        self.radius = radius.or(self.radius);
+        self.padding = padding.or(self.padding);
+        self.font_size = font_size.or(self.font_size);
+        self.opacity = opacity.or(self.opacity);
+        for &(property, transition) in transitions {
+            self.set_transition(property, transition);
+        }
+        for (state, variant) in variants {
+            self.set_variant(*state, variant);
+        }
+    }
+}
+
+/// How the user is interacting with a widget, styled like CSS
+/// pseudo-classes, e.g. `Button:hover` in a stylesheet or
+/// `Style::on(State::Hover, ..)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    /// The pointer is over it or one of its children
+    Hover,
+    /// It has keyboard focus
+    Focus,
+    /// A pointer button was pressed on it and is still held
+    Active,
+    /// Marked with `Context::set_disabled`, which rules out the other states
+    Disabled,
+}
+
+impl State {
+    /// In the order variants apply, each over the ones before
+    pub const ALL: [State; 4] = [State::Hover, State::Focus, State::Active, State::Disabled];
+
+    /// The state of CSS pseudo-class `name`
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "hover" => State::Hover,
+            "focus" => State::Focus,
+            "active" => State::Active,
+            "disabled" => State::Disabled,
+            _ => return None,
+        })
+    }
+}
+
+/// A property of `Style` that can be animated, see `Style::transition`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Property {
+    Background,
+    Color,
+    BorderColor,
+    BorderWidth,
+    Radius,
+    Padding,
+    FontSize,
+    Opacity,
+}
+
+impl Property {
+    pub const ALL: [Property; 8] = [
+        Property::Background,
+        Property::Color,
+        Property::BorderColor,
+        Property::BorderWidth,
+        Property::Radius,
+        Property::Padding,
+        Property::FontSize,
+        Property::Opacity,
+    ];
+
+    /// The property of CSS name `name`
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "background" => Property::Background,
+            "color" => Property::Color,
+            "border-color" => Property::BorderColor,
+            "border-width" => Property::BorderWidth,
+            "radius" | "border-radius" => Property::Radius,
+            "padding" => Property::Padding,
+            "font-size" => Property::FontSize,
+            "opacity" => Property::Opacity,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn differs(self, a: &Style, b: &Style) -> bool {
+        match self {
+            Property::Background => a.background != b.background,
+            Property::Color => a.color != b.color,
+            Property::BorderColor => a.border_color != b.border_color,
+            Property::BorderWidth => a.border_width != b.border_width,
+            Property::Radius => a.radius != b.radius,
+            Property::Padding => a.padding != b.padding,
+            Property::FontSize => a.font_size != b.font_size,
+            Property::Opacity => a.opacity != b.opacity,
+        }
+    }
+
+    /// Set the property of `style` to `t` of the way from its value in
+    /// `from` to that in `to`; a value that is unset at either end jumps
+    pub(crate) fn blend(self, style: &mut Style, from: &Style, to: &Style, t: f32) {
+        let color = |a: Option<Color>, b: Option<Color>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.lerp(b, t)),
+            _ => b,
+        };
+        let length = |a: Option<f32>, b: Option<f32>| match (a, b) {
+            (Some(a), Some(b)) => Some(a + (b - a) * t),
+            _ => b,
+        };
+        match self {
+            Property::Background => style.background = color(from.background, to.background),
+            Property::Color => style.color = color(from.color, to.color),
+            Property::BorderColor => style.border_color = color(from.border_color, to.border_color),
+            Property::BorderWidth => {
+                style.border_width = length(from.border_width, to.border_width)
+            }
+            Property::Radius => style.radius = length(from.radius, to.radius),
+            Property::Padding => style.padding = length(from.padding, to.padding),
+            Property::FontSize => style.font_size = length(from.font_size, to.font_size),
+            Property::Opacity => style.opacity = length(from.opacity, to.opacity),
+        }
+    }
+}
+
+/// A property set by a stylesheet rule
+#[derive(Debug, Clone, PartialEq)]
+enum Declaration {
+    Background(Color),
+    Color(Color),
+    BorderColor(Color),
+    BorderWidth(Length),
+    Radius(Length),
+    Padding(Length),
+    FontSize(Length),
+    Opacity(f32),
+    Transitions(Vec<(Property, Transition)>),
+}
+
+impl Declaration {
+    /// The property of CSS name `name` set to the text `value`
+    fn parse(name: &str, value: &str) -> Option<Self> {
+        Some(match name {
+            "background" => Declaration::Background(color(value)?),
+            "color" => Declaration::Color(color(value)?),
+            "border-color" => Declaration::BorderColor(color(value)?),
+            "border-width" => Declaration::BorderWidth(Length::parse(value)?),
+            "radius" | "border-radius" => Declaration::Radius(Length::parse(value)?),
+            "padding" => Declaration::Padding(Length::parse(value)?),
+            "font-size" => Declaration::FontSize(Length::parse(value)?),
+            "opacity" => Declaration::Opacity(value.parse().ok()?),
+            "transition" => Declaration::Transitions(transitions(value)?),
+            _ => return None,
+        })
+    }
+
+    fn apply(&self, style: &mut Style, tokens: &Tokens) {
+        match *self {
+            Declaration::Background(c) => style.background = Some(c),
+            Declaration::Color(c) => style.color = Some(c),
+            Declaration::BorderColor(c) => style.border_color = Some(c),
+            Declaration::BorderWidth(l) => style.border_width = Some(l.resolve(tokens)),
+            Declaration::Radius(l) => style.radius = Some(l.resolve(tokens)),
+            Declaration::Padding(l) => style.padding = Some(l.resolve(tokens)),
+            Declaration::FontSize(l) => style.font_size = Some(l.resolve(tokens)),
+            Declaration::Opacity(o) => style.opacity = Some(o),
+            Declaration::Transitions(ref transitions) => {
+                for &(property, transition) in transitions {
+                    style.set_transition(property, transition);
+                }
+            }
+        }
+    }
+}
+
+/// As in CSS, a comma-separated list of a property or `all`, a duration in
+/// `ms` or `s` and an optional easing, e.g. `background 150ms ease-out`
+fn transitions(value: &str) -> Option<Vec<(Property, Transition)>> {
+    let mut transitions = Vec::new();
+    for transition in value.split(',') {
+        let mut words = transition.split_whitespace();
+        let (name, duration) = (words.next()?, duration(words.next()?)?);
+        let easing = words
+            .next()
+            .map_or(Some(Easing::default()), Easing::parse)?;
+        if words.next().is_some() {
+            return None;
+        }
+        let properties = match name {
+            "all" => Property::ALL.to_vec(),
+            _ => vec![Property::parse(name)?],
+        };
+        let transition = Transition { duration, easing };
+        transitions.extend(properties.into_iter().map(|p| (p, transition)));
+    }
+    Some(transitions)
+}
+
+fn duration(value: &str) -> Option<Duration> {
+    let seconds: f64 = match value.strip_suffix("ms") {
+        Some(ms) => ms.parse::<f64>().ok()? / 1000.0,
+        None => value.strip_suffix('s')?.parse().ok()?,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// `#rrggbb` and the other hex forms, or `transparent`
+fn color(value: &str) -> Option<Color> {
+    match value {
+        "transparent" => Some(Color::TRANSPARENT),
+        _ => Color::from_hex(value.strip_prefix('#')?),
+    }
+}
+
+/// A length in a stylesheet, kept as a token until a widget is styled so
+/// it follows changes to the tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Length {
+    /// Logical pixels, written with or without `px`
+    Px(f32),
+    Token(Token),
+}
+
+impl Length {
+    fn parse(value: &str) -> Option<Self> {
+        match Token::parse(value) {
+            Some(token) => Some(Length::Token(token)),
+            None => Some(Length::Px(
+                value.strip_suffix("px").unwrap_or(value).parse().ok()?,
+            )),
+        }
+    }
+
+    fn resolve(self, tokens: &Tokens) -> f32 {
+        match self {
+            Length::Px(px) => px,
+            Length::Token(token) => tokens.get(token),
+        }
+    }
+}
+
+/// Which widgets a rule applies to, e.g. `Button.primary`, `#save` or
+/// `Button:hover`
+#[derive(Debug, Clone, PartialEq)]
+struct Selector {
+    /// Widget type, `None` for any
+    kind: Option<String>,
+    id: Option<WidgetId>,
+    classes: Vec<String>,
+    states: Vec<State>,
+}
+
+impl Selector {
+    /// A type or `*`, followed by any number of `.class`, `#id` and `:state`
+    fn parse(text: &str) -> Option<Self> {
+        let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        let end = text.find(|c: char| !is_name(c)).unwrap_or(text.len());
+        let (kind, mut rest) = text.split_at(end);
+        let mut selector = Selector {
+            kind: (!kind.is_empty()).then(|| kind.to_string()),
+            id: None,
+            classes: Vec::new(),
+            states: Vec::new(),
+        };
+        if selector.kind.is_none() {
+            rest = rest.strip_prefix('*').unwrap_or(rest);
+        }
+        while let Some(marker) = rest.chars().next() {
+            let name = &rest[marker.len_utf8()..];
+            let end = name.find(|c: char| !is_name(c)).unwrap_or(name.len());
+            let (name, tail) = name.split_at(end);
+            if name.is_empty() {
+                return None;
+            }
+            match marker {
+                '.' => selector.classes.push(name.to_string()),
+                '#' => selector.id = Some(WidgetId::new(name)),
+                ':' => selector.states.push(State::parse(name)?),
+                _ => return None,
+            }
+            rest = tail;
+        }
+        (!text.is_empty()).then_some(selector)
+    }
+
+    fn matches(&self, kind: &str, id: WidgetId, classes: &[&str], states: &[State]) -> bool {
+        self.kind.as_ref().is_none_or(|k| k == kind)
+            && self.id.is_none_or(|i| i == id)
+            && (self.classes.iter()).all(|class| classes.contains(&class.as_str()))
+            && self.states.iter().all(|state| states.contains(state))
+    }
+
+    /// As in CSS, ids outweigh classes and states, which outweigh types
+    fn specificity(&self) -> (usize, usize, usize) {
+        let id = self.id.is_some() as usize;
+        let classes = self.classes.len() + self.states.len();
+        (id, classes, self.kind.is_some() as usize)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    selector: Selector,
+    declarations: Vec<Declaration>,
+}
+
+/// Styles for widgets by type, id and class, written like CSS, e.g.
+///
+/// ```css
+/// Button.primary { background: #3478f6; color: #fff; radius: 6px; }
+/// #save { padding: 12px; }
+/// Button:hover { background: #4a8af8; }
+/// ```
+///
+/// Ids are the keys widgets were given with `WidgetId::new`. Selectors only
+/// name a single widget; there are no combinators, and the states are
+/// `:hover`, `:focus`, `:active` and `:disabled`. Lengths may name design
+/// tokens, e.g. `padding: space-3; radius: radius-md`, see `Tokens`, and
+/// `transition: background 150ms ease-out, color 0.2s` animates changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+    /// File it was loaded from and when that last changed, to reload it
+    source: Option<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Stylesheet {
+    /// Fails with the line of the first rule or declaration it cannot read
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let text = strip_comments(text);
+        let line_at = |offset: usize| text[..offset].matches('\n').count() + 1;
+        let mut rules = Vec::new();
+        let mut start = 0;
+        while let Some(open) = text[start..].find('{').map(|i| start + i) {
+            let line = line_at(open);
+            let close =
+                (text[open..].find('}').map(|i| open + i)).ok_or(Error::InvalidStylesheet(line))?;
+            let selectors = (text[start..open].split(','))
+                .map(|selector| Selector::parse(selector.trim()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(Error::InvalidStylesheet(line))?;
+            let mut declarations = Vec::new();
+            let mut offset = open + 1;
+            for declaration in text[open + 1..close].split(';') {
+                let line = line_at(offset + declaration.len() - declaration.trim_start().len());
+                offset += declaration.len() + 1;
+                if declaration.trim().is_empty() {
+                    continue;
+                }
+                let declaration = (declaration.split_once(':'))
+                    .and_then(|(name, value)| Declaration::parse(name.trim(), value.trim()))
+                    .ok_or(Error::InvalidStylesheet(line))?;
+                declarations.push(declaration);
+            }
+            rules.extend(selectors.into_iter().map(|selector| Rule {
+                selector,
+                declarations: declarations.clone(),
+            }));
+            start = close + 1;
+        }
+        let rest = &text[start..];
+        if !rest.trim().is_empty() {
+            let trailing = rest.len() - rest.trim_start().len();
+            return Err(Error::InvalidStylesheet(line_at(start + trailing)));
+        }
+        Ok(Self {
+            rules,
+            source: None,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let modified = modified(path);
+        let sheet = Stylesheet::parse(&std::fs::read_to_string(path)?)?;
+        Ok(Self {
+            source: Some((path.to_path_buf(), modified)),
+            ..sheet
+        })
+    }
+
+    /// Load the file again if it changed since, whether `Ok(true)` it did
+    ///
+    /// A file that fails leaves the rules as they were and is not tried
+    /// again until it changes anew.
+    pub(crate) fn reload(&mut self) -> Result<bool, Error> {
+        let Some((path, loaded)) = &mut self.source else {
+            return Ok(false);
+        };
+        let now = modified(path);
+        if now == *loaded {
+            return Ok(false);
+        }
+        *loaded = now;
+        self.rules = Stylesheet::parse(&std::fs::read_to_string(path)?)?.rules;
+        Ok(true)
+    }
+
+    /// Properties of a widget of type `kind` in `states`, e.g.
+    /// `sheet.style(&tokens, "Button", id, &["primary"], &[State::Hover])`
+    pub fn style(
+        &self,
+        tokens: &Tokens,
+        kind: &str,
+        id: WidgetId,
+        classes: &[&str],
+        states: &[State],
+    ) -> Style {
+        cascade([self], tokens, kind, id, classes, states)
+    }
+}
+
+/// Properties of a widget from the rules of `sheets` that match it
+///
+/// More specific rules win, and among equally specific ones the last
+/// written, in the last sheet.
+pub(crate) fn cascade<'a>(
+    sheets: impl IntoIterator<Item = &'a Stylesheet>,
+    tokens: &Tokens,
+    kind: &str,
+    id: WidgetId,
+    classes: &[&str],
+    states: &[State],
+) -> Style {
+    let mut rules: Vec<&Rule> = (sheets.into_iter())
+        .flat_map(|sheet| &sheet.rules)
+        .filter(|rule| rule.selector.matches(kind, id, classes, states))
+        .collect();
+    // A stable sort, so equals keep their order
+    rules.sort_by_key(|rule| rule.selector.specificity());
+    let mut style = Style::default();
+    for declaration in rules.into_iter().flat_map(|rule| &rule.declarations) {
+        declaration.apply(&mut style, tokens);
+    }
+    style
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Blank out `/* comments */`, keeping their line breaks so lines still count
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        let comment = &rest[start..];
+        let end = comment.find("*/").map_or(comment.len(), |i| i + 2);
+        out.extend(comment[..end].chars().filter(|&c| c == '\n'));
+        rest = &comment[end..];
+    }
+    out.push_str(rest);
+    out
+}
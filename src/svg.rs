@@ -0,0 +1,195 @@
+//! Vector icons rasterized on demand, behind the `svg` feature
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{LazyLock, Mutex};
+
+use crate::atlas::{self, AtlasAllocator, Region};
+use crate::bitmap::{Bitmap, FilterMode};
+use crate::error::Error;
+use crate::geometry::{Rect, Size};
+use crate::painter::Painter;
+
+/// Number of rasterized sizes kept per SVG
+const RASTER_CACHE_SIZE: usize = 4;
+/// Side of the bitmap rasterized icons are packed into
+const ICON_PAGE_SIZE: u32 = 1024;
+
+static ICON_ATLAS: LazyLock<Mutex<IconAtlas>> = LazyLock::new(Mutex::default);
+
+type RasterCache = Vec<((u32, u32), Raster)>;
+
+/// Where one size of an SVG was rasterized to
+#[derive(Clone)]
+enum Raster {
+    /// A region of the icon atlas, until it is emptied
+    Atlas { region: Region, generation: u64 },
+    /// A bitmap of its own, for icons too big for the atlas
+    Own(Bitmap),
+}
+
+/// Every SVG at the sizes it was drawn, packed into one bitmap that is
+/// emptied once full
+struct IconAtlas {
+    allocator: AtlasAllocator,
+    page: Bitmap,
+    /// Counts the times it was emptied, so stale regions are told apart
+    generation: u64,
+}
+
+impl Default for IconAtlas {
+    fn default() -> Self {
+        let side = ICON_PAGE_SIZE;
+        Self {
+            allocator: AtlasAllocator::new(side, side),
+            page: Bitmap::from_premultiplied(side, side, vec![0; (side * side * 4) as usize]),
+            generation: 0,
+        }
+    }
+}
+
+impl IconAtlas {
+    fn holds(&self, raster: &Raster) -> bool {
+        match raster {
+            Raster::Atlas { generation, .. } => *generation == self.generation,
+            Raster::Own(_) => true,
+        }
+    }
+
+    /// Copy `bitmap` in, emptying the atlas first if it is full
+    fn insert(&mut self, bitmap: Bitmap) -> Raster {
+        let (width, height) = (bitmap.width(), bitmap.height());
+        let fits = |side: u32| side > 0 && side + atlas::PADDING <= ICON_PAGE_SIZE;
+        if !fits(width) || !fits(height) {
+            return Raster::Own(bitmap);
+        }
+        let region = self.allocator.allocate(width, height).or_else(|| {
+            self.allocator.clear();
+            self.generation += 1;
+            self.allocator.allocate(width, height)
+        });
+        let Some(region) = region else {
+            return Raster::Own(bitmap);
+        };
+        let stride = (ICON_PAGE_SIZE * 4) as usize;
+        let page = self.page.pixels_mut();
+        for (y, row) in bitmap
+            .pixels()
+            .chunks_exact((width * 4) as usize)
+            .enumerate()
+        {
+            let start = (region.y as usize + y) * stride + (region.x * 4) as usize;
+            page[start..start + row.len()].copy_from_slice(row);
+        }
+        Raster::Atlas {
+            region,
+            generation: self.generation,
+        }
+    }
+}
+
+/// A parsed SVG document that rasterizes to bitmaps at the size it is drawn
+#[derive(Clone)]
+pub struct Svg {
+    tree: Rc<usvg::Tree>,
+    cache: Rc<RefCell<RasterCache>>,
+}
+
+impl Svg {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_data(&std::fs::read(path)?)
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default())?;
+        Ok(Self {
+            tree: Rc::new(tree),
+            cache: Rc::default(),
+        })
+    }
+
+    /// Size the document declares for itself
+    pub fn size(&self) -> Size {
+        Size::new(
+            self.tree.size.width() as f32,
+            self.tree.size.height() as f32,
+        )
+    }
+
+    /// Render to a `width` x `height` bitmap, reusing earlier renders of the same size
+    pub fn rasterize(&self, width: u32, height: u32) -> Bitmap {
+        let mut atlas = ICON_ATLAS.lock().unwrap_or_else(|e| e.into_inner());
+        match self.raster(&mut atlas, width, height) {
+            Raster::Atlas { region, .. } => {
+                let Region {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = region;
+                atlas.page.crop(x, y, width, height)
+            }
+            Raster::Own(bitmap) => bitmap,
+        }
+    }
+
+    /// Where it was rasterized at this size, rendering it into the atlas if
+    /// it is not there
+    fn raster(&self, atlas: &mut IconAtlas, width: u32, height: u32) -> Raster {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut cache = self.cache.borrow_mut();
+        cache.retain(|(_, raster)| atlas.holds(raster));
+        if let Some((_, raster)) = cache.iter().find(|(k, _)| *k == (width, height)) {
+            return raster.clone();
+        }
+        let raster = atlas.insert(self.render(width, height));
+        if cache.len() >= RASTER_CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push(((width, height), raster.clone()));
+        raster
+    }
+
+    fn render(&self, width: u32, height: u32) -> Bitmap {
+        match tiny_skia::Pixmap::new(width, height) {
+            Some(mut pixmap) => {
+                resvg::render(
+                    &self.tree,
+                    usvg::FitTo::Size(width, height),
+                    tiny_skia::Transform::default(),
+                    pixmap.as_mut(),
+                );
+                // tiny-skia already stores premultiplied RGBA
+                Bitmap::from_premultiplied(width, height, pixmap.take())
+            }
+            None => Bitmap::from_premultiplied(0, 0, Vec::new()),
+        }
+    }
+}
+
+impl Painter<'_> {
+    /// Draw an SVG into `dst`, rasterized at the pixel size it covers on screen
+    pub fn draw_svg(&mut self, svg: &Svg, dst: Rect) {
+        let t = self.transform();
+        let scale_x = (t.a * t.a + t.b * t.b).sqrt();
+        let scale_y = (t.c * t.c + t.d * t.d).sqrt();
+        let width = (dst.width * scale_x).round() as u32;
+        let height = (dst.height * scale_y).round() as u32;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let mut atlas = ICON_ATLAS.lock().unwrap_or_else(|e| e.into_inner());
+        match svg.raster(&mut atlas, width, height) {
+            Raster::Atlas { region, .. } => {
+                // Unscaled, the region is copied across pixel for pixel
+                let dst = match t.is_translation() {
+                    true => Rect::new(dst.x, dst.y, width as f32, height as f32),
+                    false => dst,
+                };
+                self.draw_image_region(&atlas.page, region, dst, FilterMode::Bilinear);
+            }
+            Raster::Own(bitmap) => self.draw_image(&bitmap, dst),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use crate::bitmap::{Bitmap, FilterMode};
+use crate::draw_list::DrawList;
+use crate::geometry::{Rect, Size};
+use crate::painter::Painter;
+
+/// An offscreen premultiplied RGBA buffer to paint into and draw later
+///
+/// Useful for caching expensive content, fading a group of overlapping
+/// shapes as one, and comparing rendered output in tests.
+#[derive(Debug, Clone)]
+pub struct RenderTarget {
+    width: u32,
+    height: u32,
+    pixels: Arc<Vec<u8>>,
+}
+
+impl RenderTarget {
+    /// A fully transparent target
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: Arc::new(vec![0; (width * height * 4) as usize]),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width as f32, self.height as f32)
+    }
+
+    /// Premultiplied RGBA bytes, row-major
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Paint directly into the target
+    ///
+    /// Bitmaps taken with `to_bitmap` keep the pixels they were taken with.
+    pub fn painter(&mut self) -> Painter<'_> {
+        let pixels = Arc::make_mut(&mut self.pixels);
+        Painter::new(pixels, self.width, self.height)
+    }
+
+    /// Make every pixel transparent
+    pub fn clear(&mut self) {
+        Arc::make_mut(&mut self.pixels).fill(0);
+    }
+
+    /// Replay `list` into the target, on top of what it already holds
+    pub fn render(&mut self, list: &DrawList) {
+        list.replay(&mut self.painter());
+    }
+
+    /// The current contents as a bitmap, sharing the pixels until the target is painted again
+    pub fn to_bitmap(&self) -> Bitmap {
+        Bitmap::from_shared(self.width, self.height, self.pixels.clone())
+    }
+}
+
+impl Painter<'_> {
+    /// Draw the contents of `target` stretched to fill `dst`, faded by `opacity`
+    pub fn draw_target(&mut self, target: &RenderTarget, dst: Rect, opacity: f32) {
+        let bitmap = target.to_bitmap();
+        self.with_opacity(opacity, |p| {
+            p.draw_image_filtered(&bitmap, dst, FilterMode::Bilinear)
+        });
+    }
+}
@@ -0,0 +1,144 @@
+use winit::window::Window;
+
+/// Progress of a long operation, shown on the window's taskbar button, passed
+/// to `Context::set_progress`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress {
+    /// Share of the work done, from 0.0 to 1.0
+    Fraction(f32),
+    /// Busy for a time that cannot be told; Linux docks show nothing for it
+    Indeterminate,
+}
+
+/// Steps of a Windows progress bar, which counts in whole numbers
+#[cfg(windows)]
+const STEPS: u64 = 10_000;
+
+/// Object the Linux launcher signals are sent from, which docks ignore
+#[cfg(target_os = "linux")]
+const LAUNCHER_PATH: &str = "/com/canonical/unity/launcherentry/wixe";
+
+/// The window's button in the taskbar or dock
+///
+/// Showing progress is best effort: a desktop without a taskbar that shows it
+/// just leaves the button as it is.
+#[derive(Default)]
+pub(crate) struct Taskbar {
+    progress: Option<Progress>,
+    /// The progress changed since it was last shown
+    dirty: bool,
+    /// Created with the first progress shown
+    #[cfg(windows)]
+    list: Option<windows::Win32::UI::Shell::ITaskbarList3>,
+    /// Name of the `.desktop` file the dock knows the application by
+    #[cfg(target_os = "linux")]
+    pub(crate) app_id: Option<String>,
+    #[cfg(target_os = "linux")]
+    connection: Option<zbus::blocking::Connection>,
+}
+
+impl Taskbar {
+    pub(crate) fn progress(&self) -> Option<Progress> {
+        self.progress
+    }
+
+    pub(crate) fn set_progress(&mut self, progress: Option<Progress>) {
+        if self.progress != progress {
+            self.progress = progress;
+            self.dirty = true;
+        }
+    }
+
+    /// Show the progress on the window's button if it changed
+    pub(crate) fn apply(&mut self, window: &Window) {
+        if std::mem::take(&mut self.dirty) {
+            let _ = self.show(window);
+        }
+    }
+
+    #[cfg(windows)]
+    fn show(&mut self, window: &Window) -> windows::core::Result<()> {
+        use std::ffi::c_void;
+
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::UI::Shell::{TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL};
+        use winit::platform::windows::WindowExtWindows;
+
+        if self.list.is_none() {
+            self.list = Some(taskbar_list()?);
+        }
+        let Some(list) = &self.list else {
+            return Ok(());
+        };
+        let hwnd = HWND(window.hwnd() as *mut c_void);
+        unsafe {
+            match self.progress {
+                Some(Progress::Fraction(done)) => {
+                    list.SetProgressState(hwnd, TBPF_NORMAL)?;
+                    let done = (done.clamp(0.0, 1.0) * STEPS as f32) as u64;
+                    list.SetProgressValue(hwnd, done, STEPS)
+                }
+                Some(Progress::Indeterminate) => list.SetProgressState(hwnd, TBPF_INDETERMINATE),
+                None => list.SetProgressState(hwnd, TBPF_NOPROGRESS),
+            }
+        }
+    }
+
+    /// Sends the Unity launcher's `Update` signal, which the docks of Ubuntu,
+    /// KDE Plasma and others follow
+    #[cfg(target_os = "linux")]
+    fn show(&mut self, _window: &Window) -> zbus::Result<()> {
+        use std::collections::HashMap;
+
+        use zbus::names::BusName;
+        use zbus::zvariant::Value;
+
+        let Some(app_id) = &self.app_id else {
+            return Ok(());
+        };
+        if self.connection.is_none() {
+            self.connection = Some(zbus::blocking::Connection::session()?);
+        }
+        let Some(connection) = &self.connection else {
+            return Ok(());
+        };
+        let (done, visible) = match self.progress {
+            Some(Progress::Fraction(done)) => (done.clamp(0.0, 1.0) as f64, true),
+            Some(Progress::Indeterminate) | None => (0.0, false),
+        };
+        let properties = HashMap::from([
+            ("progress", Value::from(done)),
+            ("progress-visible", Value::from(visible)),
+        ]);
+        connection.emit_signal(
+            None::<BusName>,
+            LAUNCHER_PATH,
+            "com.canonical.Unity.LauncherEntry",
+            "Update",
+            &(format!("application://{app_id}.desktop"), properties),
+        )
+    }
+
+    /// macOS shows progress only in the Dock tile, which belongs to the
+    /// application bundle rather than to a window
+    #[cfg(not(any(windows, target_os = "linux")))]
+    fn show(&mut self, _window: &Window) -> Result<(), std::convert::Infallible> {
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+fn taskbar_list() -> windows::core::Result<windows::Win32::UI::Shell::ITaskbarList3> {
+    use windows::Win32::System::Com::{
+        CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx,
+    };
+    use windows::Win32::UI::Shell::{ITaskbarList3, TaskbarList};
+
+    unsafe {
+        // winit has usually set COM up on this thread already, which is fine
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let list: ITaskbarList3 = CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)?;
+        list.HrInit()?;
+        Ok(list)
+    }
+}
@@ -0,0 +1,2010 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use ab_glyph::{Font as _, FontArc, Glyph, GlyphId, PxScale, ScaleFont, point};
+use rustybuzz::{Direction, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
+use unicode_linebreak::BreakOpportunity;
+
+use crate::atlas::{self, AtlasAllocator, Region};
+use crate::blend::BlendMode;
+use crate::color::Color;
+use crate::effects::{self, Shadow};
+use crate::font::{self, Font, FontId, FontWeight, GlyphImage};
+use crate::geometry::{Point, Rect, Size};
+use crate::painter::Painter;
+use crate::path::Path;
+use crate::raster::{self, Mask};
+use crate::transform::Transform;
+
+/// Subpixel positions a glyph is rasterized at, per pixel and axis
+const SUBPIXEL_STEPS: i32 = 4;
+/// Weights spreading each LCD subpixel sample over its neighbours, so colour
+/// fringes stay faint
+const LCD_FILTER: [f32; 5] = [0.03125, 0.30078, 0.33594, 0.30078, 0.03125];
+/// Appended to text cut short by `TextLayout::truncated`
+const ELLIPSIS: &str = "\u{2026}";
+/// Horizontal shift per pixel of height of faked italics
+const OBLIQUE_SKEW: f32 = 0.2;
+/// Rasterized glyphs kept before the cache is emptied
+const GLYPH_CACHE_SIZE: usize = 4096;
+/// Side of a page of the glyph atlas, in coverage samples
+const GLYPH_PAGE_SIZE: u32 = 1024;
+/// Pages of the glyph atlas filled before the cache is emptied
+const GLYPH_PAGES: usize = 4;
+
+static GLYPH_CACHE: LazyLock<Mutex<GlyphCache>> = LazyLock::new(Mutex::default);
+
+/// Coverage of one glyph as rasterized, offset from its pixel-snapped pen
+/// position, before it goes into the atlas
+#[derive(Debug)]
+struct GlyphRaster {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    /// One value per pixel, or red, green and blue values for LCD glyphs
+    coverage: Vec<f32>,
+    lcd: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: usize,
+    glyph: u16,
+    size: u32,
+    offset: (i32, i32),
+    /// Faked bold in pixels and faked italics
+    synthesis: (u32, bool),
+    lcd: bool,
+    /// `FaceData::variation_key` of a variable face
+    variation: u64,
+    /// Growth and blur in pixels, for outlines and shadows
+    spread: (u32, u32),
+}
+
+/// A glyph in the atlas, offset from its pixel-snapped pen position
+#[derive(Debug, Clone, Copy)]
+struct CachedGlyph {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    /// Red, green and blue samples side by side for each pixel
+    lcd: bool,
+    page: usize,
+    region: Region,
+}
+
+/// Coverage of every cached glyph, packed into a few shared pages at one
+/// byte per sample
+#[derive(Default)]
+struct GlyphAtlas {
+    pages: Vec<(AtlasAllocator, Vec<u8>)>,
+    /// Regions filled on each page, in order, for backends keeping a copy
+    #[cfg(feature = "gpu")]
+    filled: Vec<Vec<Region>>,
+    /// Times the atlas was emptied
+    #[cfg(feature = "gpu")]
+    generation: u64,
+}
+
+impl GlyphAtlas {
+    /// Copy `raster` in, `None` once every page is full
+    ///
+    /// A glyph too big for a page gets one of its own.
+    fn insert(&mut self, raster: &GlyphRaster) -> Option<(usize, Region)> {
+        let columns = raster.width * if raster.lcd { 3 } else { 1 };
+        let (w, h) = (columns as u32, raster.height as u32);
+        let free = (self.pages.iter_mut().enumerate())
+            .find_map(|(i, (allocator, _))| Some((i, allocator.allocate(w, h)?)));
+        let (page, region) = match free {
+            Some(free) => free,
+            None if self.pages.len() < GLYPH_PAGES => {
+                let side = GLYPH_PAGE_SIZE.max(w.max(h) + atlas::PADDING);
+                let mut allocator = AtlasAllocator::new(side, side);
+                let region = allocator.allocate(w, h)?;
+                self.pages
+                    .push((allocator, vec![0; (side * side) as usize]));
+                #[cfg(feature = "gpu")]
+                self.filled.push(Vec::new());
+                (self.pages.len() - 1, region)
+            }
+            None => return None,
+        };
+        let (allocator, samples) = &mut self.pages[page];
+        let stride = allocator.width() as usize;
+        for (y, row) in raster.coverage.chunks_exact(columns.max(1)).enumerate() {
+            let start = (region.y as usize + y) * stride + region.x as usize;
+            for (out, c) in samples[start..start + columns].iter_mut().zip(row) {
+                *out = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+        #[cfg(feature = "gpu")]
+        self.filled[page].push(region);
+        Some((page, region))
+    }
+
+    /// Sample `x` of row `y` of `glyph`, counting each LCD subpixel
+    fn at(&self, glyph: &CachedGlyph, x: usize, y: usize) -> f32 {
+        let (allocator, samples) = &self.pages[glyph.page];
+        let (region, stride) = (glyph.region, allocator.width() as usize);
+        samples[(region.y as usize + y) * stride + region.x as usize + x] as f32 / 255.0
+    }
+
+    fn clear(&mut self) {
+        self.pages.clear();
+        #[cfg(feature = "gpu")]
+        {
+            self.filled.clear();
+            self.generation += 1;
+        }
+    }
+}
+
+/// How much of the glyph atlas a backend has copied to its own textures
+#[cfg(feature = "gpu")]
+#[derive(Debug, Default)]
+pub(crate) struct GlyphAtlasCopy {
+    generation: u64,
+    /// Regions copied from each page
+    copied: Vec<usize>,
+}
+
+/// Times the glyph atlas was emptied, which leaves every region recorded
+/// before stale
+#[cfg(feature = "gpu")]
+pub(crate) fn glyph_atlas_generation() -> u64 {
+    GLYPH_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .atlas
+        .generation
+}
+
+/// A glyph to copy into a backend's copy of the glyph atlas
+#[cfg(feature = "gpu")]
+pub(crate) struct GlyphUpload<'a> {
+    pub page: usize,
+    /// Side of the page
+    pub side: u32,
+    /// Set for the first glyph on a page since it was created, when the copy
+    /// should start over from an empty page
+    pub new_page: bool,
+    pub region: Region,
+    /// Premultiplied white at the glyph's coverage
+    pub rgba: &'a [u8],
+}
+
+/// Hand every glyph added to the atlas since `copy` last saw it to `upload`
+#[cfg(feature = "gpu")]
+pub(crate) fn copy_glyph_atlas(copy: &mut GlyphAtlasCopy, mut upload: impl FnMut(GlyphUpload)) {
+    let cache = GLYPH_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let atlas = &cache.atlas;
+    if copy.generation != atlas.generation {
+        copy.generation = atlas.generation;
+        copy.copied.clear();
+    }
+    copy.copied.resize(atlas.pages.len(), 0);
+    for (page, ((allocator, samples), filled)) in atlas.pages.iter().zip(&atlas.filled).enumerate()
+    {
+        let side = allocator.width();
+        let mut rgba = Vec::new();
+        for (i, region) in filled.iter().enumerate().skip(copy.copied[page]) {
+            rgba.clear();
+            for y in region.y..region.y + region.height {
+                let start = (y * side + region.x) as usize;
+                for &c in &samples[start..start + region.width as usize] {
+                    rgba.extend_from_slice(&[c; 4]);
+                }
+            }
+            upload(GlyphUpload {
+                page,
+                side,
+                new_page: i == 0,
+                region: *region,
+                rgba: &rgba,
+            });
+        }
+        copy.copied[page] = filled.len();
+    }
+}
+
+/// Text drawn by a painter that records it, for backends that fill glyphs
+/// from their own copy of the glyph atlas
+///
+/// Positions are local to `transform`, and colors already faded by the
+/// painter's opacity.
+#[cfg(feature = "gpu")]
+#[derive(Clone)]
+pub(crate) enum TextDraw {
+    /// Coverage of atlas page `page` at `uv`, in page coordinates from 0 to
+    /// 1, filling `rect`
+    Glyph {
+        page: usize,
+        uv: Rect,
+        rect: Rect,
+        color: Color,
+        transform: Transform,
+    },
+    /// An underline or strikethrough
+    Rect {
+        rect: Rect,
+        color: Color,
+        transform: Transform,
+    },
+    /// A color bitmap glyph
+    Image {
+        bitmap: crate::bitmap::Bitmap,
+        rect: Rect,
+        opacity: f32,
+        transform: Transform,
+    },
+}
+
+/// Rasterized glyphs shared by every painter, so static text is not redrawn from outlines
+#[derive(Default)]
+struct GlyphCache {
+    /// `None` for glyphs with no pixels, such as spaces
+    glyphs: HashMap<GlyphKey, Option<CachedGlyph>>,
+    atlas: GlyphAtlas,
+    /// Decoded color bitmaps by face, glyph and pixels per em
+    images: HashMap<(usize, u16, u16), Option<Arc<GlyphImage>>>,
+}
+
+impl GlyphCache {
+    /// Coverage of `glyph` from `font`'s face `index` with its pen position
+    /// rounded to a subpixel step, sampled three times across each pixel for `lcd`
+    ///
+    /// Variable faces set to other than their default axis values are drawn
+    /// from their varied outlines. Coverage is grown then blurred by `spread`
+    /// pixels for outlines and shadows.
+    fn get(
+        &mut self,
+        font: &Font,
+        index: usize,
+        glyph: &Glyph,
+        lcd: bool,
+        spread: (f32, f32),
+    ) -> Option<(i32, i32, CachedGlyph)> {
+        let (face, synthesis) = (&font.faces()[index], font.synthesis(index));
+        let varied = font.face_data(index).filter(|d| !d.variations().is_empty());
+        let (x, dx) = subpixel(glyph.position.x);
+        let (y, dy) = subpixel(glyph.position.y);
+        let size = glyph.scale.y;
+        let embolden = synthesis.embolden * size;
+        let key = GlyphKey {
+            font: font.face_id(index),
+            glyph: glyph.id.0,
+            size: size.to_bits(),
+            offset: (dx, dy),
+            synthesis: (embolden.to_bits(), synthesis.oblique),
+            lcd,
+            variation: varied.map_or(0, |d| d.variation_key()),
+            spread: (spread.0.to_bits(), spread.1.to_bits()),
+        };
+        if let Some(cached) = self.glyphs.get(&key) {
+            return cached.map(|g| (x, y, g));
+        }
+        self.make_room();
+        let render = || {
+            let step = SUBPIXEL_STEPS as f32;
+            // LCD glyphs are drawn stretched to one column per subpixel, then filtered
+            let n = if lcd { 3.0 } else { 1.0 };
+            let scale = PxScale {
+                x: glyph.scale.x * n,
+                y: glyph.scale.y,
+            };
+            let pen = Point::new(n * dx as f32 / step, dy as f32 / step);
+            let mut raster = match varied {
+                Some(data) => {
+                    let outline = data.glyph_outline(glyph.id, (scale.x, scale.y), pen)?;
+                    rasterize_outline(&outline)?
+                }
+                None => rasterize(
+                    face,
+                    glyph.id.with_scale_and_position(scale, point(pen.x, pen.y)),
+                )?,
+            };
+            if embolden > 0.0 {
+                raster = thicken(raster, embolden * n);
+            }
+            if synthesis.oblique {
+                raster = shear(raster, OBLIQUE_SKEW * n);
+            }
+            if spread.0 > 0.0 {
+                raster = dilate(raster, spread.0);
+            }
+            if spread.1 > 0.0 {
+                raster = blur(raster, spread.1);
+            }
+            if lcd {
+                raster = filter_lcd(raster);
+            }
+            Some(raster)
+        };
+        let cached = render().and_then(|raster| {
+            let placed = self.atlas.insert(&raster).or_else(|| {
+                self.clear();
+                self.atlas.insert(&raster)
+            });
+            placed.map(|(page, region)| CachedGlyph {
+                left: raster.left,
+                top: raster.top,
+                width: raster.width,
+                height: raster.height,
+                lcd: raster.lcd,
+                page,
+                region,
+            })
+        });
+        self.glyphs.insert(key, cached);
+        cached.map(|g| (x, y, g))
+    }
+
+    /// The embedded color bitmap of glyph `id` of `font`'s face `index`, decoded once per size
+    fn image(
+        &mut self,
+        font: &Font,
+        index: usize,
+        id: GlyphId,
+        size: f32,
+    ) -> Option<Arc<GlyphImage>> {
+        let key = (font.face_id(index), id.0, size.round() as u16);
+        if !self.images.contains_key(&key) {
+            self.make_room();
+        }
+        let data = font.face_data(index)?;
+        let image = self
+            .images
+            .entry(key)
+            .or_insert_with(|| data.glyph_image(id, size).map(Arc::new));
+        image.clone()
+    }
+
+    /// Empty the cache once full, before an entry is added
+    fn make_room(&mut self) {
+        if self.glyphs.len() + self.images.len() >= GLYPH_CACHE_SIZE {
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.glyphs.clear();
+        self.images.clear();
+        self.atlas.clear();
+    }
+}
+
+/// Whole pixel and subpixel step nearest to `v`
+fn subpixel(v: f32) -> (i32, i32) {
+    let steps = (v * SUBPIXEL_STEPS as f32).round() as i32;
+    (
+        steps.div_euclid(SUBPIXEL_STEPS),
+        steps.rem_euclid(SUBPIXEL_STEPS),
+    )
+}
+
+fn rasterize(face: &FontArc, glyph: Glyph) -> Option<GlyphRaster> {
+    let outline = face.outline_glyph(glyph)?;
+    let bb = outline.px_bounds();
+    let (width, height) = (bb.width() as usize, bb.height() as usize);
+    let mut coverage = vec![0.0; width * height];
+    outline.draw(|gx, gy, gv| coverage[gy as usize * width + gx as usize] = gv);
+    Some(GlyphRaster {
+        left: bb.min.x as i32,
+        top: bb.min.y as i32,
+        width,
+        height,
+        coverage,
+        lcd: false,
+    })
+}
+
+/// Coverage of a glyph outline in pixels, with the same antialiasing as paths
+fn rasterize_outline(outline: &Path) -> Option<GlyphRaster> {
+    let mask = raster::rasterize(&outline.flatten(), outline.bounds().inset(-1.0))?;
+    Some(GlyphRaster {
+        left: mask.x,
+        top: mask.y,
+        width: mask.width,
+        height: mask.height,
+        coverage: mask.data,
+        lcd: false,
+    })
+}
+
+/// Fold a glyph rasterized at three columns per pixel into red, green and blue
+/// coverage, each subpixel blurred over its neighbours by `LCD_FILTER`
+fn filter_lcd(glyph: GlyphRaster) -> GlyphRaster {
+    let reach = LCD_FILTER.len() as i32 / 2;
+    let left = (glyph.left - reach).div_euclid(3);
+    let right = (glyph.left + glyph.width as i32 + reach + 2).div_euclid(3);
+    let width = (right - left) as usize;
+    let mut coverage = vec![0.0; width * glyph.height * 3];
+    for y in 0..glyph.height {
+        let row = &glyph.coverage[y * glyph.width..(y + 1) * glyph.width];
+        let at = |s: i32| {
+            usize::try_from(s - glyph.left)
+                .ok()
+                .and_then(|i| row.get(i))
+                .map_or(0.0, |c| *c)
+        };
+        for (i, out) in coverage[y * width * 3..(y + 1) * width * 3]
+            .iter_mut()
+            .enumerate()
+        {
+            let s = left * 3 + i as i32;
+            *out = LCD_FILTER
+                .iter()
+                .enumerate()
+                .map(|(k, w)| w * at(s + k as i32 - reach))
+                .sum::<f32>()
+                .min(1.0);
+        }
+    }
+    GlyphRaster {
+        left,
+        width,
+        coverage,
+        lcd: true,
+        ..glyph
+    }
+}
+
+/// Widen strokes to the right by `amount` pixels, for faked bold
+fn thicken(glyph: GlyphRaster, amount: f32) -> GlyphRaster {
+    let (whole, frac) = (amount.floor() as usize, amount.fract());
+    let width = glyph.width + amount.ceil() as usize;
+    let mut coverage = vec![0.0; width * glyph.height];
+    for y in 0..glyph.height {
+        let row = &glyph.coverage[y * glyph.width..(y + 1) * glyph.width];
+        let at = |x: usize, k: usize| {
+            x.checked_sub(k)
+                .and_then(|x| row.get(x))
+                .map_or(0.0, |c| *c)
+        };
+        for x in 0..width {
+            let solid = (0..=whole).map(|k| at(x, k)).fold(0.0, f32::max);
+            coverage[y * width + x] = solid.max(at(x, whole + 1) * frac);
+        }
+    }
+    GlyphRaster {
+        width,
+        coverage,
+        ..glyph
+    }
+}
+
+/// Grow coverage by `radius` pixels in every direction, for outlines
+fn dilate(glyph: GlyphRaster, radius: f32) -> GlyphRaster {
+    let reach = radius.ceil() as i32;
+    let pad = 2 * reach as usize;
+    let (width, height) = (glyph.width + pad, glyph.height + pad);
+    // Offsets within reach, fading out past the radius
+    let disc: Vec<_> = (-reach..=reach)
+        .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+        .filter_map(|(dx, dy)| {
+            let weight = (radius + 1.0 - ((dx * dx + dy * dy) as f32).sqrt()).min(1.0);
+            (weight > 0.0).then_some((dx, dy, weight))
+        })
+        .collect();
+    let mut coverage = vec![0.0f32; width * height];
+    for y in 0..glyph.height {
+        for x in 0..glyph.width {
+            let c = glyph.coverage[y * glyph.width + x];
+            if c <= 0.0 {
+                continue;
+            }
+            for &(dx, dy, weight) in &disc {
+                let (ox, oy) = (x as i32 + reach + dx, y as i32 + reach + dy);
+                let out = &mut coverage[oy as usize * width + ox as usize];
+                *out = out.max(c * weight);
+            }
+        }
+    }
+    GlyphRaster {
+        left: glyph.left - reach,
+        top: glyph.top - reach,
+        width,
+        height,
+        coverage,
+        ..glyph
+    }
+}
+
+/// Blur coverage with the same radius as box shadows, for text shadows
+fn blur(glyph: GlyphRaster, blur: f32) -> GlyphRaster {
+    let mask = Mask {
+        x: glyph.left,
+        y: glyph.top,
+        width: glyph.width,
+        height: glyph.height,
+        data: glyph.coverage,
+    };
+    let mut mask = mask.pad(effects::blur_extent(blur) as usize);
+    mask.blur(blur);
+    GlyphRaster {
+        left: mask.x,
+        top: mask.y,
+        width: mask.width,
+        height: mask.height,
+        coverage: mask.data,
+        lcd: false,
+    }
+}
+
+/// Slant rows right in proportion to their height above the baseline, for faked italics
+fn shear(glyph: GlyphRaster, skew: f32) -> GlyphRaster {
+    let shift = |row: usize| -(glyph.top as f32 + row as f32 + 0.5) * skew;
+    // The bottom row moves least, so it sets the new left edge
+    let least = shift(glyph.height - 1).floor();
+    let width = glyph.width + (shift(0) - least).ceil() as usize + 1;
+    let mut coverage = vec![0.0; width * glyph.height];
+    for y in 0..glyph.height {
+        let s = shift(y) - least;
+        let (whole, frac) = (s.floor() as usize, s.fract());
+        for x in 0..glyph.width {
+            let c = glyph.coverage[y * glyph.width + x];
+            coverage[y * width + x + whole] += c * (1.0 - frac);
+            coverage[y * width + x + whole + 1] += c * frac;
+        }
+    }
+    GlyphRaster {
+        left: glyph.left + least as i32,
+        width,
+        coverage,
+        ..glyph
+    }
+}
+
+/// Advance width of the widest line of `text`, shaped in `font`'s style
+pub fn text_width(font: &Font, font_size: f32, text: &str) -> f32 {
+    let (weight, italic) = font.style();
+    let style = TextStyle::new(font_size).weight(weight).italic(italic);
+    TextLayout::new(text, font, &style, None).size().width
+}
+
+/// How glyph edges are smoothed, see `Painter::set_text_antialiasing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextAntialiasing {
+    /// Coverage alone, right on any display and background
+    #[default]
+    Grayscale,
+    /// Subpixel antialiasing for LCDs whose pixels are red, green, blue from left to right
+    Rgb,
+    /// Subpixel antialiasing for LCDs with the blue subpixel on the left
+    Bgr,
+}
+
+/// Horizontal placement of lines within their box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretch the gaps between words so lines fill the width, except the
+    /// last line of each paragraph
+    Justify,
+}
+
+/// Vertical placement of a block of text within its box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Direction lines run in
+///
+/// Vertical text is laid out as horizontal text turned a quarter clockwise:
+/// lines become columns read top to bottom and stacked right to left, `align`
+/// places text along each column, with `Left` at the top, and `valign` places
+/// the columns, with `Top` at the right edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WritingMode {
+    #[default]
+    Horizontal,
+    /// Columns of upright glyphs, one em apart, as for CJK text; underlines and
+    /// strikethroughs are not drawn
+    Vertical,
+    /// Columns of glyphs turned with the line, as for rotated chart labels
+    VerticalRotated,
+}
+
+/// A solid edge around glyphs, keeping text readable over busy backgrounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOutline {
+    /// How far the edge reaches past the glyph, in pixels
+    pub width: f32,
+    pub color: Color,
+}
+
+impl TextOutline {
+    pub fn new(width: f32, color: impl Into<Color>) -> Self {
+        Self {
+            width,
+            color: color.into(),
+        }
+    }
+}
+
+/// How text is laid out and placed within its box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    /// Registered font to measure with through `Fonts`; layouts given a `Font`
+    /// directly use that instead
+    pub font: FontId,
+    /// Font size in pixels
+    pub size: f32,
+    pub weight: FontWeight,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// Multiple of the font's own line height, the extra split above and below
+    /// each line; the paragraph's style sets it for rich text
+    pub line_height: f32,
+    /// Extra pixels after every character
+    pub letter_spacing: f32,
+    /// Extra pixels after every space, on top of `letter_spacing`
+    pub word_spacing: f32,
+    /// Distance between tab stops in widths of a space, measured from the start
+    /// of the paragraph
+    pub tab_size: f32,
+    /// Edge drawn around the glyphs, beneath them
+    pub outline: Option<TextOutline>,
+    /// Blurred copy of the glyphs and their outline, beneath both
+    pub shadow: Option<Shadow>,
+    pub align: TextAlign,
+    pub valign: VerticalAlign,
+    /// The paragraph's style sets it for rich text
+    pub writing_mode: WritingMode,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font: FontId::DEFAULT,
+            size: 16.0,
+            weight: FontWeight::NORMAL,
+            italic: false,
+            underline: false,
+            strikethrough: false,
+            line_height: 1.0,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            tab_size: 8.0,
+            outline: None,
+            shadow: None,
+            align: TextAlign::default(),
+            valign: VerticalAlign::default(),
+            writing_mode: WritingMode::default(),
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn new(size: f32) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    pub fn font(mut self, font: FontId) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn weight(mut self, weight: FontWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.weight(FontWeight::BOLD)
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    pub fn underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    pub fn line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    pub fn letter_spacing(mut self, spacing: f32) -> Self {
+        self.letter_spacing = spacing;
+        self
+    }
+
+    pub fn word_spacing(mut self, spacing: f32) -> Self {
+        self.word_spacing = spacing;
+        self
+    }
+
+    pub fn tab_size(mut self, spaces: f32) -> Self {
+        self.tab_size = spaces;
+        self
+    }
+
+    pub fn outline(mut self, width: f32, color: impl Into<Color>) -> Self {
+        self.outline = Some(TextOutline::new(width, color));
+        self
+    }
+
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn valign(mut self, valign: VerticalAlign) -> Self {
+        self.valign = valign;
+        self
+    }
+
+    pub fn writing_mode(mut self, mode: WritingMode) -> Self {
+        self.writing_mode = mode;
+        self
+    }
+}
+
+/// A run of text with its own style inside a rich `TextLayout`
+///
+/// The style's alignment is ignored; the paragraph's applies.
+#[derive(Clone)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: TextStyle,
+    /// Font of the run, the paragraph's when `None`
+    pub font: Option<Font>,
+    /// Color of the run, the color the layout is drawn with when `None`
+    pub color: Option<Color>,
+}
+
+impl TextSpan {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self {
+            text: text.into(),
+            style,
+            font: None,
+            color: None,
+        }
+    }
+
+    pub fn font(mut self, font: &Font) -> Self {
+        self.font = Some(font.clone());
+        self
+    }
+
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+}
+
+/// One line of a `TextLayout`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    /// Byte range of the line in the laid-out text, without the whitespace it broke at
+    pub range: Range<usize>,
+    /// Advance width of the line
+    pub width: f32,
+    /// Whether the line ends at a newline or the end of the text rather than a wrap
+    pub ends_paragraph: bool,
+    /// Distance from the top of the layout to the top of the line
+    pub top: f32,
+    /// Distance from the top of the layout to the line's baseline
+    pub baseline: f32,
+    /// Distance from the top of the line to the top of the next, fitting its
+    /// largest text
+    pub height: f32,
+    glyphs: Vec<LineGlyph>,
+}
+
+/// A glyph placed on a line, its position relative to the line's start and baseline
+///
+/// A line's glyphs are kept in display order, left to right.
+#[derive(Debug, Clone, PartialEq)]
+struct LineGlyph {
+    /// Start of the glyph's cluster in the laid-out text
+    byte: usize,
+    rtl: bool,
+    span: usize,
+    face: usize,
+    id: GlyphId,
+    c: char,
+    x: f32,
+    advance: f32,
+    /// Shift from the pen position, positive up, e.g. to place marks
+    offset: (f32, f32),
+}
+
+/// A run of text resolved to the font and style it is laid out with
+#[derive(Clone)]
+struct LayoutSpan {
+    range: Range<usize>,
+    /// Styled for the span's weight and slant
+    font: Font,
+    style: TextStyle,
+    color: Option<Color>,
+}
+
+/// A glyph with its advance, before lines are broken
+///
+/// Shaping can join several characters into one glyph or split one into
+/// several; `byte` and `c` are the first character of the glyph's cluster.
+/// Glyphs stay in reading order, right-to-left runs included.
+struct Shaped {
+    byte: usize,
+    c: char,
+    span: usize,
+    face: usize,
+    id: GlyphId,
+    /// Pen position from the start of the text, kerning included
+    start: f32,
+    advance: f32,
+    offset: (f32, f32),
+}
+
+/// Size of a block of laid-out text, for fitting elements around it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMetrics {
+    /// Width of the widest line by the height of all lines
+    pub size: Size,
+    pub line_count: usize,
+    /// Advance width of each line, top to bottom
+    pub line_widths: Vec<f32>,
+}
+
+/// Text broken into lines, measured and ready to draw
+#[derive(Clone)]
+pub struct TextLayout {
+    text: String,
+    spans: Vec<LayoutSpan>,
+    style: TextStyle,
+    lines: Vec<TextLine>,
+    truncated: bool,
+}
+
+impl TextLayout {
+    /// Break `text` at newlines and, given `max_width`, at the last break
+    /// opportunity that fits
+    ///
+    /// Opportunities follow the Unicode line breaking algorithm: after spaces
+    /// and hyphens and between ideographs, but never at a non-breaking space.
+    /// Words wider than `max_width` on their own are broken between characters.
+    /// Lines are broken in reading order, then right-to-left runs such as
+    /// Arabic or Hebrew are reversed for display by the Unicode bidi algorithm.
+    pub fn new(text: &str, font: &Font, style: &TextStyle, max_width: Option<f32>) -> Self {
+        Self::rich(&[TextSpan::new(text, *style)], font, style, max_width)
+    }
+
+    /// Lay out runs of differently styled text as one paragraph, wrapping like `new`
+    ///
+    /// Each line is as tall as the largest text on it, and all text on a line
+    /// shares its baseline. `font` is used for spans without their own and
+    /// `style` aligns the block.
+    pub fn rich(
+        spans: &[TextSpan],
+        font: &Font,
+        style: &TextStyle,
+        max_width: Option<f32>,
+    ) -> Self {
+        let mut text = String::new();
+        let spans: Vec<_> = spans
+            .iter()
+            .map(|span| {
+                let start = text.len();
+                text.push_str(&span.text);
+                let font = span.font.as_ref().unwrap_or(font);
+                LayoutSpan {
+                    range: start..text.len(),
+                    font: font.styled(span.style.weight, span.style.italic),
+                    style: span.style,
+                    color: span.color,
+                }
+            })
+            .collect();
+        let bidi = BidiInfo::new(&text, None);
+        let upright = style.writing_mode == WritingMode::Vertical;
+        let shaped = shape(&bidi, &spans, upright);
+        // Where lines may or must break, as the index in `shaped` of the glyph after
+        let opportunities: Vec<_> = unicode_linebreak::linebreaks(&text)
+            .map(|(byte, kind)| {
+                let at = shaped.partition_point(|s| s.byte < byte);
+                (at, kind == BreakOpportunity::Mandatory)
+            })
+            .collect();
+        // Ranges of `shaped` on each line, and whether the line ends its paragraph
+        let mut breaks = Vec::new();
+        let mut start = 0;
+        while start <= shaped.len() {
+            let end = shaped[start..]
+                .iter()
+                .position(|s| s.c == '\n')
+                .map_or(shaped.len(), |i| start + i);
+            let inside = opportunities
+                .iter()
+                .copied()
+                .filter(|&(at, _)| at > start && at < end);
+            break_paragraph(&shaped, start..end, inside, max_width, &mut breaks);
+            start = end + 1;
+        }
+        let paragraph = (font.styled(style.weight, style.italic), *style);
+        let mut lines = Vec::with_capacity(breaks.len());
+        let mut top = 0.0;
+        for (range, ends_paragraph) in breaks {
+            let line = build_line(&bidi, &shaped, &spans, &paragraph, range, top);
+            top += line.height;
+            lines.push(TextLine {
+                ends_paragraph,
+                ..line
+            });
+        }
+        Self {
+            text,
+            spans,
+            style: *style,
+            lines,
+            truncated: false,
+        }
+    }
+
+    /// Lay out the first line of `text`, cut short with an ellipsis if it does not fit
+    /// `max_width`
+    ///
+    /// The ellipsis itself is measured, so the result is never wider than
+    /// `max_width`; it is dropped too when even the ellipsis alone does not fit.
+    pub fn truncated(text: &str, font: &Font, style: &TextStyle, max_width: f32) -> Self {
+        let line = text.split('\n').next().unwrap_or_default();
+        let fits = |s: &str| Self::new(s, font, style, None).size().width <= max_width;
+        if line.len() == text.len() && fits(line.trim_end()) {
+            return Self::new(line, font, style, None);
+        }
+        let shorten = |end: usize| format!("{}{ELLIPSIS}", line[..end].trim_end());
+        let ends: Vec<_> = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([line.len()])
+            .collect();
+        // Prefixes only get wider, so search for the longest that still fits
+        let keep = ends.partition_point(|&end| fits(&shorten(end)));
+        let shown = match keep {
+            0 => String::new(),
+            n => shorten(ends[n - 1]),
+        };
+        Self {
+            truncated: true,
+            ..Self::new(&shown, font, style, None)
+        }
+    }
+
+    /// All the laid-out text, spans joined
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    /// Whether `truncated` had to cut the text short
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn lines(&self) -> &[TextLine] {
+        &self.lines
+    }
+
+    /// Width of the widest line by the height of all lines, or for vertical
+    /// text the width of all columns by the height of the longest
+    pub fn size(&self) -> Size {
+        let size = self.extent();
+        match self.style.writing_mode {
+            WritingMode::Horizontal => size,
+            _ => Size::new(size.height, size.width),
+        }
+    }
+
+    /// Size of the lines before vertical text is turned
+    fn extent(&self) -> Size {
+        let width = self.lines.iter().map(|l| l.width).fold(0.0, f32::max);
+        let height = self.lines.last().map_or(0.0, |l| l.top + l.height);
+        Size::new(width, height)
+    }
+
+    /// The box lines are laid out in for the layout drawn in `rect`, and the
+    /// mapping from it onto `rect`
+    fn frame(&self, rect: Rect) -> (Rect, Transform) {
+        match self.style.writing_mode {
+            WritingMode::Horizontal => (rect, Transform::IDENTITY),
+            _ => {
+                let frame = Rect::new(0.0, 0.0, rect.height, rect.width);
+                let to_rect = Transform {
+                    a: 0.0,
+                    b: 1.0,
+                    c: -1.0,
+                    d: 0.0,
+                    e: rect.max_x(),
+                    f: rect.y,
+                };
+                (frame, to_rect)
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> TextMetrics {
+        TextMetrics {
+            size: self.size(),
+            line_count: self.lines.len(),
+            line_widths: self.lines.iter().map(|l| l.width).collect(),
+        }
+    }
+
+    /// Area the lines cover when aligned within `rect`
+    pub fn block_rect(&self, rect: Rect) -> Rect {
+        let (frame, to_rect) = self.frame(rect);
+        to_rect.map_rect(self.frame_block(frame))
+    }
+
+    /// Area the lines cover within `frame`, before vertical text is turned
+    fn frame_block(&self, rect: Rect) -> Rect {
+        let (size, style) = (self.extent(), &self.style);
+        let x = match style.align {
+            TextAlign::Left | TextAlign::Justify => rect.x,
+            TextAlign::Center => rect.x + (rect.width - size.width) / 2.0,
+            TextAlign::Right => rect.max_x() - size.width,
+        };
+        let y = match style.valign {
+            VerticalAlign::Top => rect.y,
+            VerticalAlign::Middle => rect.y + (rect.height - size.height) / 2.0,
+            VerticalAlign::Bottom => rect.max_y() - size.height,
+        };
+        let width = match style.align {
+            TextAlign::Justify => size.width.max(rect.width),
+            _ => size.width,
+        };
+        Rect::new(x, y, width, size.height)
+    }
+
+    /// Where `line` starts when the layout is aligned within `rect`, and how far
+    /// justification shifts each of its glyphs
+    fn place_line(&self, line: &TextLine, rect: Rect) -> (f32, Option<Vec<f32>>) {
+        let x = match self.style.align {
+            TextAlign::Left | TextAlign::Justify => rect.x,
+            TextAlign::Center => rect.x + (rect.width - line.width) / 2.0,
+            TextAlign::Right => rect.max_x() - line.width,
+        };
+        let shifts = match self.style.align {
+            TextAlign::Justify if !line.ends_paragraph => {
+                justify(&line.glyphs, rect.width - line.width)
+            }
+            _ => None,
+        };
+        (x, shifts)
+    }
+
+    /// Left and right edge of each glyph of `line`, in display order
+    fn glyph_edges(&self, line: &TextLine, rect: Rect) -> Vec<(f32, f32)> {
+        let (x, shifts) = self.place_line(line, rect);
+        let shift = |i: usize| shifts.as_ref().map_or(0.0, |s| s[i]);
+        (line.glyphs.iter().enumerate())
+            .map(|(i, g)| {
+                let left = x + g.x + shift(i);
+                (left, left + g.advance)
+            })
+            .collect()
+    }
+
+    /// Pen position of each glyph of `line`, in display order, for the layout
+    /// aligned within `frame`
+    ///
+    /// `upright` glyphs are centered across their column once mapped onto the screen.
+    fn glyph_pens(&self, line: &TextLine, frame: Rect, upright: Option<Transform>) -> Vec<Point> {
+        let block = self.frame_block(frame);
+        let (x, shifts) = self.place_line(line, frame);
+        let shift = |i: usize| shifts.as_ref().map_or(0.0, |s| s[i]);
+        let baseline = block.y + line.baseline;
+        let column = block.y + line.top + line.height / 2.0;
+        (line.glyphs.iter().enumerate())
+            .map(|(i, g)| {
+                let (dx, dy) = g.offset;
+                let Some(to_rect) = upright else {
+                    return Point::new(x + g.x + shift(i) + dx, baseline - dy);
+                };
+                let span = &self.spans[g.span];
+                let size = span.style.size;
+                let face = span.font.faces()[g.face].as_scaled(size);
+                // The em box starts where the glyph does along the column
+                let top = to_rect.apply(Point::new(x + g.x + shift(i), column));
+                let width = face.h_advance(g.id);
+                let drop = (size - face.ascent() + face.descent()) / 2.0 + face.ascent();
+                Point::new(top.x - width / 2.0 + dx, top.y + drop - dy)
+            })
+            .collect()
+    }
+
+    /// How far glyphs can reach past the lines' boxes, e.g. italics, descenders
+    /// and shadows
+    pub(crate) fn overhang(&self) -> f32 {
+        (self.spans.iter().map(|s| &s.style))
+            .map(|style| {
+                let outline = style.outline.map_or(0.0, |o| o.width);
+                let shadow = style.shadow.map_or(0.0, |s| {
+                    s.offset.x.abs().max(s.offset.y.abs()) + effects::blur_extent(s.blur)
+                });
+                style.size / 2.0 + outline + shadow
+            })
+            .fold(self.style.size / 2.0, f32::max)
+    }
+
+    /// Index of the line holding byte `offset`, the last one if it is past the end
+    pub(crate) fn line_at(&self, offset: usize) -> usize {
+        self.lines
+            .partition_point(|l| l.range.start <= offset)
+            .saturating_sub(1)
+    }
+
+    /// Byte offset of the caret position nearest to `p`, for the layout aligned within `rect`
+    ///
+    /// Points above or below the text land on its first or last line, and
+    /// points beside a line on its nearest end.
+    pub fn hit_test_point(&self, rect: Rect, p: Point) -> usize {
+        let (frame, to_rect) = self.frame(rect);
+        let p = to_rect.inverse().map_or(p, |t| t.apply(p));
+        self.frame_offset_at(frame, p)
+    }
+
+    fn frame_offset_at(&self, rect: Rect, p: Point) -> usize {
+        let y = p.y - self.frame_block(rect).y;
+        let Some(line) = (self.lines.iter())
+            .find(|l| y < l.top + l.height)
+            .or(self.lines.last())
+        else {
+            return 0;
+        };
+        let edges = self.glyph_edges(line, rect);
+        for (g, &(left, right)) in line.glyphs.iter().zip(&edges) {
+            // The half nearer the pointer decides, and right-to-left glyphs start on the right
+            let (before, after) = (g.byte, cluster_end(line, g.byte));
+            if p.x < (left + right) / 2.0 {
+                return if g.rtl { after } else { before };
+            }
+            if p.x < right {
+                return if g.rtl { before } else { after };
+            }
+        }
+        match line.glyphs.last() {
+            Some(g) if g.rtl => g.byte,
+            _ => line.range.end,
+        }
+    }
+
+    /// The one pixel wide caret before byte `offset`, as tall as its line, for
+    /// the layout aligned within `rect`
+    ///
+    /// Vertical text has a one pixel tall caret across its column.
+    pub fn caret_rect(&self, rect: Rect, offset: usize) -> Rect {
+        let (frame, to_rect) = self.frame(rect);
+        let block = self.frame_block(frame);
+        let caret = match self.caret_at(frame, offset) {
+            Some((line, x)) => {
+                let line = &self.lines[line];
+                Rect::new(x, block.y + line.top, 1.0, line.height)
+            }
+            None => Rect::new(block.x, block.y, 1.0, block.height),
+        };
+        to_rect.map_rect(caret)
+    }
+
+    /// Offset on line `target` nearest to the caret at byte `offset`, for moving
+    /// the caret between lines
+    pub(crate) fn offset_on_line(&self, rect: Rect, offset: usize, target: usize) -> usize {
+        let (frame, _) = self.frame(rect);
+        let (Some((_, x)), Some(line)) = (self.caret_at(frame, offset), self.lines.get(target))
+        else {
+            return offset;
+        };
+        let y = self.frame_block(frame).y + line.top + line.height / 2.0;
+        self.frame_offset_at(frame, Point::new(x, y))
+    }
+
+    /// Line and position along it of the caret at byte `offset`, within `frame`
+    fn caret_at(&self, rect: Rect, offset: usize) -> Option<(usize, f32)> {
+        let index = self.line_at(offset);
+        let line = self.lines.get(index)?;
+        let edges = self.glyph_edges(line, rect);
+        let glyphs = line.glyphs.iter().zip(&edges);
+        let x = glyphs
+            .clone()
+            .find(|(g, _)| g.byte == offset)
+            .map(|(g, &(left, right))| if g.rtl { right } else { left })
+            .or_else(|| {
+                let mut ending = glyphs.filter(|(g, _)| cluster_end(line, g.byte) == offset);
+                ending
+                    .next()
+                    .map(|(g, &(left, right))| if g.rtl { left } else { right })
+            })
+            .unwrap_or_else(|| self.place_line(line, rect).0);
+        Some((index, x))
+    }
+
+    /// Highlight boxes covering the text in `range`, for the layout aligned within `rect`
+    pub(crate) fn range_rects(&self, rect: Rect, range: Range<usize>) -> Vec<Rect> {
+        let (frame, to_rect) = self.frame(rect);
+        (self.frame_range_rects(frame, range).into_iter())
+            .map(|r| to_rect.map_rect(r))
+            .collect()
+    }
+
+    fn frame_range_rects(&self, rect: Rect, range: Range<usize>) -> Vec<Rect> {
+        let block = self.frame_block(rect);
+        let mut rects: Vec<Rect> = Vec::new();
+        for line in &self.lines {
+            let edges = self.glyph_edges(line, rect);
+            let mut last: Option<usize> = None;
+            for (g, &(left, right)) in line.glyphs.iter().zip(&edges) {
+                if !range.contains(&g.byte) {
+                    last = None;
+                    continue;
+                }
+                // Neighbouring selected glyphs share one box
+                match last.and_then(|i| rects.get_mut(i)) {
+                    Some(r) => r.width = right - r.x,
+                    None => {
+                        last = Some(rects.len());
+                        let top = block.y + line.top;
+                        rects.push(Rect::new(left, top, right - left, line.height));
+                    }
+                }
+            }
+        }
+        rects
+    }
+}
+
+/// End of the cluster starting at `byte` on `line`, where the next one in reading order starts
+fn cluster_end(line: &TextLine, byte: usize) -> usize {
+    (line.glyphs.iter())
+        .map(|g| g.byte)
+        .filter(|&b| b > byte)
+        .min()
+        .unwrap_or(line.range.end)
+}
+
+/// Turn the text of every span into glyphs from its font, run by run
+///
+/// `upright` glyphs of vertical text advance by one em down the column.
+fn shape(bidi: &BidiInfo<'_>, spans: &[LayoutSpan], upright: bool) -> Vec<Shaped> {
+    let mut shaped = Vec::with_capacity(bidi.text.len());
+    let mut caret = 0.0;
+    for (index, span) in spans.iter().enumerate() {
+        for (run, face) in runs(bidi, &span.font, span.range.clone()) {
+            let rtl = bidi.levels[run.start].is_rtl();
+            let face_at_size = span.font.faces()[face].as_scaled(span.style.size);
+            let space = face_at_size.glyph_id(' ');
+            let mut run = Run {
+                text: bidi.text,
+                range: run,
+                span: index,
+                face,
+                size: span.style.size,
+                spacing: (span.style.letter_spacing, span.style.word_spacing),
+                upright,
+                tab: (face_at_size.h_advance(space) * span.style.tab_size, space),
+                out: &mut shaped,
+                caret: &mut caret,
+            };
+            // Newlines are left to the simple path so they keep a glyph of their own
+            match span.font.face_data(face) {
+                Some(data) if bidi.text.as_bytes()[run.range.start] != b'\n' => {
+                    run.shape(&span.font, data, rtl)
+                }
+                _ => run.shape_simple(&span.font),
+            }
+        }
+    }
+    shaped
+}
+
+/// Split `range` into runs that share a face and a bidi level, newlines on their own
+///
+/// A run keeps its face for characters it has a glyph for, so marks and
+/// spaces stay with the text around them.
+fn runs(bidi: &BidiInfo<'_>, font: &Font, range: Range<usize>) -> Vec<(Range<usize>, usize)> {
+    let text = bidi.text;
+    let mut runs: Vec<(Range<usize>, usize)> = Vec::new();
+    for (i, c) in text[range.clone()].char_indices() {
+        let at = range.start + i;
+        let end = at + c.len_utf8();
+        if let Some((run, face)) = runs.last_mut()
+            && c != '\n'
+            && text.as_bytes()[run.end - 1] != b'\n'
+            && bidi.levels[at] == bidi.levels[run.start]
+            && font.has_glyph(*face, c)
+        {
+            run.end = end;
+            continue;
+        }
+        runs.push((at..end, font.face_for(c)));
+    }
+    runs
+}
+
+/// One run of text being shaped onto the end of the glyphs so far
+struct Run<'a> {
+    text: &'a str,
+    range: Range<usize>,
+    span: usize,
+    face: usize,
+    size: f32,
+    /// Letter and word spacing
+    spacing: (f32, f32),
+    upright: bool,
+    /// Distance between tab stops, and the space glyph drawn for tabs
+    tab: (f32, GlyphId),
+    out: &'a mut Vec<Shaped>,
+    caret: &'a mut f32,
+}
+
+impl Run<'_> {
+    /// Add a glyph, spaced out unless it is a mark sitting on the one before
+    ///
+    /// Tabs advance to the next tab stop instead.
+    fn push(&mut self, byte: usize, id: GlyphId, advance: f32, offset: (f32, f32)) {
+        let c = self.text[byte..].chars().next().unwrap_or_default();
+        let (stop, space) = self.tab;
+        if c == '\t' && stop > 0.0 {
+            let paragraph = (self.out.iter().rev())
+                .find(|s| s.c == '\n')
+                .map_or(0.0, |s| s.start + s.advance);
+            let advance = stop - (*self.caret - paragraph).rem_euclid(stop);
+            return self.push_glyph(byte, c, space, advance, (0.0, 0.0));
+        }
+        let (letter, word) = self.spacing;
+        let advance = match self.upright && advance > 0.0 {
+            true => self.size,
+            false => advance,
+        };
+        let advance = match advance > 0.0 {
+            true if c == ' ' || c == '\u{a0}' => advance + letter + word,
+            true => advance + letter,
+            false => advance,
+        };
+        self.push_glyph(byte, c, id, advance, offset);
+    }
+
+    fn push_glyph(&mut self, byte: usize, c: char, id: GlyphId, advance: f32, offset: (f32, f32)) {
+        self.out.push(Shaped {
+            byte,
+            c,
+            span: self.span,
+            face: self.face,
+            id,
+            start: *self.caret,
+            advance,
+            offset,
+        });
+        *self.caret += advance;
+    }
+
+    /// Shape with the face's OpenType tables, for ligatures, contextual forms and mark placement
+    fn shape(&mut self, font: &Font, data: &font::FaceData, rtl: bool) {
+        let Some(mut face) = rustybuzz::Face::from_slice(&data.bytes, data.index) else {
+            return self.shape_simple(font);
+        };
+        let variations: Vec<_> = (data.variations().iter())
+            .map(|&(tag, value)| rustybuzz::Variation { tag, value })
+            .collect();
+        face.set_variations(&variations);
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(&self.text[self.range.clone()]);
+        buffer.set_direction(match rtl {
+            true => Direction::RightToLeft,
+            false => Direction::LeftToRight,
+        });
+        let output = rustybuzz::shape(&face, &[], buffer);
+        let scale = self.size / face.units_per_em() as f32;
+        let embolden = font.synthesis(self.face).embolden * self.size;
+        let glyphs = output.glyph_infos().iter().zip(output.glyph_positions());
+        let mut glyphs: Vec<_> = glyphs.collect();
+        // Right-to-left output is in display order; layout wants reading order
+        if rtl {
+            glyphs.reverse();
+        }
+        for (info, pos) in glyphs {
+            let byte = self.range.start + info.cluster as usize;
+            let offset = (pos.x_offset as f32 * scale, pos.y_offset as f32 * scale);
+            let advance = pos.x_advance as f32 * scale;
+            let advance = if advance > 0.0 {
+                advance + embolden
+            } else {
+                advance
+            };
+            self.push(byte, GlyphId(info.glyph_id as u16), advance, offset);
+        }
+    }
+
+    /// One glyph per character with pair kerning only, for faces without data
+    fn shape_simple(&mut self, font: &Font) {
+        let face = font.faces()[self.face].as_scaled(self.size);
+        let embolden = font.synthesis(self.face).embolden * self.size;
+        let mut last = None;
+        for (i, c) in self.text[self.range.clone()].char_indices() {
+            let id = face.glyph_id(c);
+            if let Some(last) = last
+                && !self.upright
+            {
+                *self.caret += face.kern(last, id);
+            }
+            last = Some(id);
+            let advance = face.h_advance(id) + embolden;
+            self.push(self.range.start + i, id, advance, (0.0, 0.0));
+        }
+    }
+}
+
+/// Advance width of `shaped[start..end]` set on a line of its own
+fn run_width(shaped: &[Shaped], start: usize, end: usize) -> f32 {
+    if end <= start {
+        return 0.0;
+    }
+    let last = &shaped[end - 1];
+    last.start + last.advance - shaped[start].start
+}
+
+/// Advance width of `shaped[start..end]` set on a line of its own, trailing
+/// whitespace dropped
+fn line_width(shaped: &[Shaped], start: usize, mut end: usize) -> f32 {
+    while end > start && shaped[end - 1].c.is_whitespace() {
+        end -= 1;
+    }
+    run_width(shaped, start, end)
+}
+
+/// Break one paragraph, a range of `shaped`, into lines at every mandatory
+/// opportunity and at the last allowed one that fits `max_width`
+///
+/// Opportunities are the index of the glyph after the break and whether it is
+/// mandatory. Text with no opportunity that fits is broken between clusters.
+fn break_paragraph(
+    shaped: &[Shaped],
+    range: Range<usize>,
+    opportunities: impl Iterator<Item = (usize, bool)>,
+    max_width: Option<f32>,
+    lines: &mut Vec<(Range<usize>, bool)>,
+) {
+    let fits = |start, end| max_width.is_none_or(|max| line_width(shaped, start, end) <= max);
+    let mut start = range.start;
+    let mut last = None;
+    for (at, mandatory) in opportunities.chain([(range.end, true)]) {
+        if !fits(start, at) {
+            if let Some(cut) = last.take() {
+                lines.push((start..cut, false));
+                start = cut;
+            }
+            while !fits(start, at) {
+                // The longest prefix that fits, but at least one cluster
+                let clusters = (start + 1..=at)
+                    .filter(|&cut| cut == at || shaped[cut].byte != shaped[cut - 1].byte);
+                let mut clusters = clusters.peekable();
+                let first = clusters.peek().copied().unwrap_or(at);
+                let cut = clusters
+                    .take_while(|&cut| fits(start, cut))
+                    .last()
+                    .unwrap_or(first);
+                // A lone cluster too wide to fit ends the line at `at` as any other would
+                if cut == at {
+                    break;
+                }
+                lines.push((start..cut, false));
+                start = cut;
+            }
+        }
+        last = Some(at);
+        if mandatory {
+            lines.push((start..at, true));
+            (start, last) = (at, None);
+        }
+    }
+}
+
+/// Measure and place the glyphs of `shaped[range]`, trailing whitespace dropped
+fn build_line(
+    bidi: &BidiInfo<'_>,
+    shaped: &[Shaped],
+    spans: &[LayoutSpan],
+    (font, style): &(Font, TextStyle),
+    range: Range<usize>,
+    top: f32,
+) -> TextLine {
+    let mut end = range.end;
+    while end > range.start && shaped[end - 1].c.is_whitespace() {
+        end -= 1;
+    }
+    let run = &shaped[range.start..end];
+    // Empty lines take the height of the text they sit in
+    let metrics: Vec<_> = match shaped.get(range.start).or(shaped.last()) {
+        _ if !run.is_empty() => run
+            .iter()
+            .map(|s| {
+                let span = &spans[s.span];
+                v_metrics(&span.font.faces()[s.face], span.style.size)
+            })
+            .collect(),
+        Some(s) => {
+            let span = &spans[s.span];
+            vec![v_metrics(&span.font.faces()[0], span.style.size)]
+        }
+        None => vec![v_metrics(&font.faces()[0], style.size)],
+    };
+    let ascent = metrics.iter().map(|v| v.0).fold(0.0, f32::max);
+    let descent = metrics.iter().map(|v| v.1).fold(0.0, f32::min);
+    let line_gap = metrics.iter().map(|v| v.2).fold(0.0, f32::max);
+    let natural = ascent - descent + line_gap;
+    let height = natural * style.line_height;
+    let bytes = |i: usize| shaped.get(i).map_or(bidi.text.len(), |s| s.byte);
+    let range = bytes(range.start)..bytes(end);
+    let (glyphs, width) = place(bidi, run, range.clone());
+    TextLine {
+        range,
+        width,
+        ends_paragraph: false,
+        top,
+        baseline: top + (height - natural) / 2.0 + ascent,
+        height,
+        glyphs,
+    }
+}
+
+/// Ascent, descent and line gap of `face` at `size`, descent negative
+fn v_metrics(face: &FontArc, size: f32) -> (f32, f32, f32) {
+    let face = face.as_scaled(size);
+    (face.ascent(), face.descent(), face.line_gap())
+}
+
+/// Lay out a line's glyphs left to right in display order, returning them and
+/// their total advance
+///
+/// Each directional run keeps its own spacing; right-to-left runs are mirrored
+/// within it.
+fn place(bidi: &BidiInfo<'_>, run: &[Shaped], bytes: Range<usize>) -> (Vec<LineGlyph>, f32) {
+    let glyph = |s: &Shaped, x: f32, rtl: bool| LineGlyph {
+        byte: s.byte,
+        rtl,
+        span: s.span,
+        face: s.face,
+        id: s.id,
+        c: s.c,
+        x,
+        advance: s.advance,
+        offset: s.offset,
+    };
+    let paragraph = bidi
+        .paragraphs
+        .iter()
+        .find(|p| p.range.contains(&bytes.start));
+    let Some(paragraph) = paragraph else {
+        let origin = run.first().map_or(0.0, |s| s.start);
+        let glyphs = run
+            .iter()
+            .map(|s| glyph(s, s.start - origin, false))
+            .collect();
+        return (glyphs, run_width(run, 0, run.len()));
+    };
+    let (levels, visual_runs) = bidi.visual_runs(paragraph, bytes);
+    let mut glyphs = Vec::with_capacity(run.len());
+    let mut caret = 0.0;
+    for visual in visual_runs {
+        let from = run.partition_point(|s| s.byte < visual.start);
+        let to = run.partition_point(|s| s.byte < visual.end);
+        if to <= from {
+            continue;
+        }
+        let width = run_width(run, from, to);
+        let offset = |s: &Shaped| s.start - run[from].start;
+        if levels[visual.start].is_rtl() {
+            let mirrored = |s: &Shaped| glyph(s, caret + width - offset(s) - s.advance, true);
+            glyphs.extend(run[from..to].iter().rev().map(mirrored));
+        } else {
+            glyphs.extend(
+                run[from..to]
+                    .iter()
+                    .map(|s| glyph(s, caret + offset(s), false)),
+            );
+        }
+        caret += width;
+    }
+    (glyphs, caret)
+}
+
+impl Painter<'_> {
+    /// Draw text centered at `center`
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        font: &Font,
+        font_size: f32,
+        center: Point,
+        color: impl Into<Color>,
+    ) {
+        let (weight, italic) = font.style();
+        let style = TextStyle::new(font_size).weight(weight).italic(italic);
+        let layout = TextLayout::new(text, font, &style, None);
+        let origin = Point::new(
+            center.x - layout.size().width / 2.0,
+            center.y + font_size / 2.0,
+        );
+        self.draw_text_layout(&layout, origin, color);
+    }
+
+    /// Draw a laid-out block of text with its top-left corner at `origin`
+    pub fn draw_text_layout(
+        &mut self,
+        layout: &TextLayout,
+        origin: Point,
+        color: impl Into<Color>,
+    ) {
+        let size = layout.size();
+        let rect = Rect::new(origin.x, origin.y, size.width, size.height);
+        self.draw_text_in(layout, rect, color);
+    }
+
+    /// Draw a laid-out block of text aligned within `rect` as its style says
+    ///
+    /// Lines wider than `rect` overflow it; clip to keep them inside.
+    pub fn draw_text_in(&mut self, layout: &TextLayout, rect: Rect, color: impl Into<Color>) {
+        let color = color.into();
+        let (frame, to_rect) = layout.frame(rect);
+        match layout.style.writing_mode {
+            WritingMode::Horizontal => self.draw_lines(layout, frame, color, None),
+            WritingMode::Vertical => self.draw_lines(layout, frame, color, Some(to_rect)),
+            WritingMode::VerticalRotated => {
+                self.with_transform(to_rect, |p| p.draw_lines(layout, frame, color, None))
+            }
+        }
+    }
+
+    /// Draw the lines of `layout` aligned within `frame`, or with each glyph
+    /// stood `upright` in its column once mapped onto the screen
+    fn draw_lines(
+        &mut self,
+        layout: &TextLayout,
+        frame: Rect,
+        color: Color,
+        upright: Option<Transform>,
+    ) {
+        let block = layout.frame_block(frame);
+        let mut cache = GLYPH_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let styles = layout.spans.iter().map(|s| &s.style);
+        if styles
+            .clone()
+            .any(|s| s.shadow.is_some() || s.outline.is_some())
+        {
+            self.draw_text_effects(&mut cache, layout, frame, upright);
+        }
+        for line in &layout.lines {
+            let (x, shifts) = layout.place_line(line, frame);
+            let shift = |i: usize| shifts.as_ref().map_or(0.0, |s| s[i]);
+            let baseline = block.y + line.baseline;
+            let pens = layout.glyph_pens(line, frame, upright);
+            for (g, pen) in line.glyphs.iter().zip(pens) {
+                let span = &layout.spans[g.span];
+                let color = span.color.unwrap_or(color);
+                if !self.draw_color_glyph(&mut cache, span, g.face, g.id, pen, color) {
+                    let glyph = g.id.with_scale(span.style.size);
+                    self.draw_glyph(&mut cache, &span.font, g.face, glyph, pen, color.into());
+                }
+            }
+            if upright.is_some() {
+                continue;
+            }
+            // Decorations follow each span, so they change with its size and color
+            let mut start = 0;
+            while start < line.glyphs.len() {
+                let span_index = line.glyphs[start].span;
+                let end = line.glyphs[start..]
+                    .iter()
+                    .position(|g| g.span != span_index)
+                    .map_or(line.glyphs.len(), |n| start + n);
+                let span = &layout.spans[span_index];
+                let (first, last) = (&line.glyphs[start], &line.glyphs[end - 1]);
+                let left = x + first.x + shift(start);
+                let width = last.x + shift(end - 1) + last.advance - first.x - shift(start);
+                let (metrics, size) = (span.font.line_metrics(), span.style.size);
+                let color = span.color.unwrap_or(color);
+                if span.style.underline {
+                    let top = baseline - metrics.underline_offset * size;
+                    let thickness = metrics.underline_thickness * size;
+                    self.fill_decoration(left, top, width, thickness, color);
+                }
+                if span.style.strikethrough {
+                    let top = baseline - metrics.strikeout_offset * size;
+                    let thickness = metrics.strikeout_thickness * size;
+                    self.fill_decoration(left, top, width, thickness, color);
+                }
+                start = end;
+            }
+        }
+    }
+
+    /// Draw every shadow of `layout`, then every outline, so neither covers a
+    /// neighbouring glyph
+    ///
+    /// Color glyphs are outlined by the shapes of their layers; bitmap glyphs get neither.
+    fn draw_text_effects(
+        &mut self,
+        cache: &mut GlyphCache,
+        layout: &TextLayout,
+        frame: Rect,
+        upright: Option<Transform>,
+    ) {
+        for shadows in [true, false] {
+            for line in &layout.lines {
+                let pens = layout.glyph_pens(line, frame, upright);
+                for (g, pen) in line.glyphs.iter().zip(pens) {
+                    let span = &layout.spans[g.span];
+                    let (outline, shadow) = (span.style.outline, span.style.shadow);
+                    let grow = outline.map_or(0.0, |o| o.width);
+                    let (pen, ink) = match (shadows, shadow, outline) {
+                        (true, Some(s), _) => {
+                            let ink = Ink {
+                                color: s.color,
+                                spread: (grow, s.blur),
+                            };
+                            (Point::new(pen.x + s.offset.x, pen.y + s.offset.y), ink)
+                        }
+                        (false, _, Some(o)) => {
+                            let ink = Ink {
+                                color: o.color,
+                                spread: (grow, 0.0),
+                            };
+                            (pen, ink)
+                        }
+                        _ => continue,
+                    };
+                    let font = &span.font;
+                    let layers = (font.face_data(g.face))
+                        .and_then(|d| d.color_layers(g.id))
+                        .map_or(vec![g.id], |l| l.into_iter().map(|(id, _)| id).collect());
+                    for id in layers {
+                        let glyph = id.with_scale(span.style.size);
+                        self.draw_glyph(cache, font, g.face, glyph, pen, ink);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fill a decoration line from its top edge, snapped to whole pixels so it stays crisp
+    fn fill_decoration(&mut self, x: f32, top: f32, width: f32, thickness: f32, color: Color) {
+        let rect = Rect::new(x.round(), top.round(), width, thickness.round().max(1.0));
+        #[cfg(feature = "gpu")]
+        if let Some(color) = self.recorded_color(color) {
+            let transform = self.transform();
+            if let Some(draws) = self.text_draws() {
+                draws.push(TextDraw::Rect {
+                    rect,
+                    color,
+                    transform,
+                });
+            }
+            return;
+        }
+        self.fill_rect(rect, color);
+    }
+
+    /// `color` faded by the painter's opacity when text is recorded, `None`
+    /// when it is drawn
+    #[cfg(feature = "gpu")]
+    fn recorded_color(&mut self, color: Color) -> Option<Color> {
+        let alpha = (color.a as f32 * self.opacity()).round() as u8;
+        self.text_draws().is_some().then(|| color.with_alpha(alpha))
+    }
+
+    /// Draw glyph `id` of a span from a color font's layers or bitmaps, `false`
+    /// if it has neither
+    ///
+    /// Layers without a palette color of their own take `color`.
+    fn draw_color_glyph(
+        &mut self,
+        cache: &mut GlyphCache,
+        span: &LayoutSpan,
+        face: usize,
+        id: GlyphId,
+        pen: Point,
+        color: Color,
+    ) -> bool {
+        let (font, size) = (&span.font, span.style.size);
+        let Some(data) = font.face_data(face).filter(|d| d.has_color()) else {
+            return false;
+        };
+        if let Some(layers) = data.color_layers(id) {
+            for (layer, layer_color) in layers {
+                let glyph = layer.with_scale(size);
+                let ink = layer_color.unwrap_or(color).into();
+                self.draw_glyph(cache, font, face, glyph, pen, ink);
+            }
+            return true;
+        }
+        let Some(image) = cache.image(font, face, id, size) else {
+            return false;
+        };
+        let r = image.rect;
+        let rect = Rect::new(
+            pen.x + r.x * size,
+            pen.y + r.y * size,
+            r.width * size,
+            r.height * size,
+        );
+        #[cfg(feature = "gpu")]
+        if let Some(faded) = self.recorded_color(color) {
+            let transform = self.transform();
+            if let Some(draws) = self.text_draws() {
+                draws.push(TextDraw::Image {
+                    bitmap: image.bitmap.clone(),
+                    rect,
+                    opacity: faded.a as f32 / 255.0,
+                    transform,
+                });
+            }
+            return true;
+        }
+        self.push_opacity(color.a as f32 / 255.0);
+        self.draw_image(&image.bitmap, rect);
+        self.pop_opacity();
+        true
+    }
+
+    /// Fill one glyph with its pen at `pen`
+    ///
+    /// Under a plain translation the glyph is rasterized at the pen's subpixel
+    /// offset on the frame, so spacing stays even wherever text lands.
+    fn draw_glyph(
+        &mut self,
+        cache: &mut GlyphCache,
+        font: &Font,
+        face: usize,
+        glyph: Glyph,
+        pen: Point,
+        ink: Ink,
+    ) {
+        let color = ink.color;
+        let t = self.transform();
+        let shift = match t.is_translation() {
+            true => Point::new(t.e, t.f),
+            false => Point::ZERO,
+        };
+        #[cfg(feature = "gpu")]
+        let recording = self.text_draws().is_some();
+        #[cfg(not(feature = "gpu"))]
+        let recording = false;
+        let lcd = t.is_translation()
+            && !recording
+            && ink.spread == (0.0, 0.0)
+            && self.blend_mode() == BlendMode::SourceOver
+            && self.text_antialiasing() != TextAntialiasing::Grayscale;
+        let glyph = Glyph {
+            position: point(pen.x + shift.x, pen.y + shift.y),
+            ..glyph
+        };
+        let Some((x, y, g)) = cache.get(font, face, &glyph, lcd, ink.spread) else {
+            return;
+        };
+        let (x, y) = (x + g.left, y + g.top);
+        #[cfg(feature = "gpu")]
+        if let Some(color) = self.recorded_color(color) {
+            let rect = Rect::new(
+                x as f32 - shift.x,
+                y as f32 - shift.y,
+                g.width as f32,
+                g.height as f32,
+            );
+            let side = cache.atlas.pages[g.page].0.width() as f32;
+            let r = g.region;
+            let uv = Rect::new(
+                r.x as f32 / side,
+                r.y as f32 / side,
+                r.width as f32 / side,
+                r.height as f32 / side,
+            );
+            if let Some(draws) = self.text_draws() {
+                draws.push(TextDraw::Glyph {
+                    page: g.page,
+                    uv,
+                    rect,
+                    color,
+                    transform: t,
+                });
+            }
+            return;
+        }
+        let atlas = &cache.atlas;
+        if !g.lcd {
+            let origin = Point::new(x as f32 - shift.x, y as f32 - shift.y);
+            let at = |gx, gy| atlas.at(&g, gx, gy);
+            self.fill_coverage_with(origin, g.width, g.height, at, color);
+            return;
+        }
+        let bgr = self.text_antialiasing() == TextAntialiasing::Bgr;
+        for gy in 0..g.height {
+            for gx in 0..g.width {
+                let mut c = [0, 1, 2].map(|i| atlas.at(&g, gx * 3 + i, gy));
+                if bgr {
+                    c.reverse();
+                }
+                if c.iter().any(|&c| c > 0.0) {
+                    self.blend_subpixels(x + gx as i32, y + gy as i32, color, c);
+                }
+            }
+        }
+    }
+}
+
+/// What a glyph is painted with: a color, and how far its coverage is grown
+/// then blurred first, for outlines and shadows
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Ink {
+    color: Color,
+    spread: (f32, f32),
+}
+
+impl From<Color> for Ink {
+    fn from(color: Color) -> Self {
+        Self {
+            color,
+            spread: (0.0, 0.0),
+        }
+    }
+}
+
+/// Horizontal shift of each glyph spreading `extra` pixels evenly over the gaps
+/// between words, `None` if there are none to stretch
+fn justify(glyphs: &[LineGlyph], extra: f32) -> Option<Vec<f32>> {
+    let starts_word =
+        |i: usize| !glyphs[i].c.is_whitespace() && (i == 0 || glyphs[i - 1].c.is_whitespace());
+    let gaps = (0..glyphs.len())
+        .filter(|&i| starts_word(i))
+        .count()
+        .saturating_sub(1);
+    if gaps == 0 || extra <= 0.0 {
+        return None;
+    }
+    let per_gap = extra / gaps as f32;
+    let (mut shift, mut seen_word) = (0.0, false);
+    let shifts = (0..glyphs.len())
+        .map(|i| {
+            if starts_word(i) {
+                if seen_word {
+                    shift += per_gap;
+                }
+                seen_word = true;
+            }
+            shift
+        })
+        .collect();
+    Some(shifts)
+}
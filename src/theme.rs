@@ -0,0 +1,429 @@
+#[cfg(feature = "system-theme")]
+use std::sync::mpsc::{self, Receiver, Sender};
+
+#[cfg(feature = "system-theme")]
+use winit::event_loop::EventLoopProxy;
+use winit::window::Window;
+
+use crate::color::Color;
+
+/// Whether the system, or the application, prefers light or dark colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Appearance {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// The system's preference, if it can be told
+#[cfg(feature = "system-theme")]
+pub(crate) fn system_appearance(_window: &Window) -> Option<Appearance> {
+    match dark_light::detect() {
+        Ok(dark_light::Mode::Dark) => Some(Appearance::Dark),
+        Ok(dark_light::Mode::Light) => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+/// Without the `system-theme` feature only Windows reveals it, through the
+/// window
+#[cfg(all(not(feature = "system-theme"), windows))]
+pub(crate) fn system_appearance(window: &Window) -> Option<Appearance> {
+    use winit::platform::windows::WindowExtWindows;
+
+    Some(appearance_of(window.theme()))
+}
+
+#[cfg(not(any(feature = "system-theme", windows)))]
+pub(crate) fn system_appearance(_window: &Window) -> Option<Appearance> {
+    None
+}
+
+/// Reads the accent color the user picked in the system's settings on a
+/// thread of its own, as that takes a process on macOS and a D-Bus round trip
+/// on Linux, and wakes the event loop with each color read
+///
+/// Windows keeps it in the registry, macOS in the global defaults, and GNOME
+/// 47, KDE Plasma and other Linux desktops share it through the desktop
+/// portal, which also reports when it changes.
+#[cfg(feature = "system-theme")]
+pub(crate) struct AccentWatcher {
+    requests: Sender<()>,
+    accents: Receiver<Option<Color>>,
+}
+
+#[cfg(feature = "system-theme")]
+impl AccentWatcher {
+    /// Start reading, with a first read right away
+    pub(crate) fn start(proxy: EventLoopProxy<()>) -> Self {
+        let (requests, pending) = mpsc::channel::<()>();
+        let (sender, accents) = mpsc::channel();
+        #[cfg(target_os = "linux")]
+        watch_portal(sender.clone(), proxy.clone());
+        std::thread::spawn(move || {
+            let mut reader = AccentReader::default();
+            for () in pending.iter() {
+                // One read answers every request made while it was waiting
+                pending.try_iter().for_each(drop);
+                if sender.send(reader.read()).is_err() {
+                    break;
+                }
+                let _ = proxy.send_event(());
+            }
+        });
+        let _ = requests.send(());
+        Self { requests, accents }
+    }
+
+    /// Read it again, as when the window gains focus after the user may have
+    /// changed it
+    pub(crate) fn refresh(&self) {
+        let _ = self.requests.send(());
+    }
+
+    /// The color read last since the last poll, if any was
+    pub(crate) fn poll(&self) -> Option<Option<Color>> {
+        self.accents.try_iter().last()
+    }
+}
+
+#[cfg(feature = "system-theme")]
+#[derive(Default)]
+struct AccentReader {
+    /// The session bus, connected on the first read and kept for the next
+    #[cfg(target_os = "linux")]
+    connection: Option<zbus::blocking::Connection>,
+}
+
+#[cfg(all(feature = "system-theme", windows))]
+impl AccentReader {
+    fn read(&mut self) -> Option<Color> {
+        use std::ffi::c_void;
+
+        use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW};
+        use windows::core::w;
+
+        let mut abgr = 0u32;
+        let mut size = size_of::<u32>() as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                w!("Software\\Microsoft\\Windows\\DWM"),
+                w!("AccentColor"),
+                RRF_RT_REG_DWORD,
+                None,
+                Some(&mut abgr as *mut u32 as *mut c_void),
+                Some(&mut size),
+            )
+        };
+        status.is_ok().then(|| {
+            let [r, g, b, _] = abgr.to_le_bytes();
+            Color::rgb(r, g, b)
+        })
+    }
+}
+
+/// macOS names one of a few accent colors, and none for "multicolor", which
+/// leaves the accent to each application
+#[cfg(all(feature = "system-theme", target_os = "macos"))]
+impl AccentReader {
+    fn read(&mut self) -> Option<Color> {
+        let output = std::process::Command::new("defaults")
+            .args(["read", "-g", "AppleAccentColor"])
+            .output()
+            .ok()?;
+        let accent: i32 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .ok()?;
+        Some(match accent {
+            -1 => Color::rgb(140, 140, 140),
+            0 => Color::rgb(224, 56, 62),
+            1 => Color::rgb(247, 130, 27),
+            2 => Color::rgb(255, 198, 0),
+            3 => Color::rgb(98, 186, 70),
+            4 => Color::rgb(0, 122, 255),
+            5 => Color::rgb(149, 61, 150),
+            6 => Color::rgb(247, 79, 158),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(all(feature = "system-theme", target_os = "linux"))]
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+#[cfg(all(feature = "system-theme", target_os = "linux"))]
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+#[cfg(all(feature = "system-theme", target_os = "linux"))]
+const SETTINGS: &str = "org.freedesktop.portal.Settings";
+
+#[cfg(all(feature = "system-theme", target_os = "linux"))]
+impl AccentReader {
+    fn read(&mut self) -> Option<Color> {
+        if self.connection.is_none() {
+            self.connection = zbus::blocking::Connection::session().ok();
+        }
+        let reply = self
+            .connection
+            .as_ref()?
+            .call_method(
+                Some(PORTAL),
+                PORTAL_PATH,
+                Some(SETTINGS),
+                "ReadOne",
+                &("org.freedesktop.appearance", "accent-color"),
+            )
+            .ok()?;
+        portal_accent(reply.body().deserialize().ok()?)
+    }
+}
+
+/// Follow the portal's `SettingChanged` signal, so a new accent shows without
+/// waiting for the window to be focused again
+#[cfg(all(feature = "system-theme", target_os = "linux"))]
+fn watch_portal(sender: Sender<Option<Color>>, proxy: EventLoopProxy<()>) {
+    use zbus::zvariant::OwnedValue;
+
+    std::thread::spawn(move || -> zbus::Result<()> {
+        let connection = zbus::blocking::Connection::session()?;
+        let settings = zbus::blocking::Proxy::new(&connection, PORTAL, PORTAL_PATH, SETTINGS)?;
+        for message in settings.receive_signal("SettingChanged")? {
+            let (namespace, key, value): (String, String, OwnedValue) =
+                message.body().deserialize()?;
+            if namespace != "org.freedesktop.appearance" || key != "accent-color" {
+                continue;
+            }
+            if sender.send(portal_accent(value)).is_err() {
+                break;
+            }
+            let _ = proxy.send_event(());
+        }
+        Ok(())
+    });
+}
+
+#[cfg(all(feature = "system-theme", target_os = "linux"))]
+fn portal_accent(value: zbus::zvariant::OwnedValue) -> Option<Color> {
+    let (r, g, b): (f64, f64, f64) = value.try_into().ok()?;
+    // Channels outside 0.0..=1.0 mean no accent was picked
+    let channel = |c: f64| (0.0..=1.0).contains(&c).then(|| (c * 255.0).round() as u8);
+    Some(Color::rgb(channel(r)?, channel(g)?, channel(b)?))
+}
+
+#[cfg(all(
+    feature = "system-theme",
+    not(any(windows, target_os = "macos", target_os = "linux"))
+))]
+impl AccentReader {
+    fn read(&mut self) -> Option<Color> {
+        None
+    }
+}
+
+pub(crate) fn appearance_of(theme: winit::window::Theme) -> Appearance {
+    match theme {
+        winit::window::Theme::Light => Appearance::Light,
+        winit::window::Theme::Dark => Appearance::Dark,
+    }
+}
+
+/// Colors an application's widgets are drawn with, read through
+/// `Context::theme`
+///
+/// What Wixe draws itself follows it too: the window is cleared to
+/// `background` and the focus ring drawn in `focus_ring`.
+///
+/// With the `system-theme` feature the accent color the user picked in the
+/// system's settings takes the place of `accent`, of `primary` wherever it is
+/// the accent, and of `focus_ring`, see `Theme::with_accent`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// The window behind everything else
+    pub background: Color,
+    /// Panels, cards and text fields raised from the background
+    pub surface: Color,
+    /// The application's color, which `primary` is unless set otherwise, and
+    /// text selections are highlighted in
+    pub accent: Color,
+    /// Primary buttons, selections and other marks of emphasis
+    pub primary: Color,
+    pub text: Color,
+    /// Text and marks of widgets that cannot be used right now
+    pub disabled: Color,
+    /// Ring around the focused widget after moving focus from the keyboard,
+    /// `None` to draw none
+    pub focus_ring: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+impl Theme {
+    /// Dark text on light gray, the default
+    pub fn light() -> Self {
+        let accent = Color::rgb(50, 120, 230);
+        Self {
+            background: Color::rgb(240, 240, 240),
+            surface: Color::WHITE,
+            accent,
+            primary: accent,
+            text: Color::BLACK,
+            disabled: Color::rgb(150, 150, 150),
+            focus_ring: Some(accent),
+        }
+    }
+
+    /// Light text on dark gray
+    pub fn dark() -> Self {
+        let accent = Color::rgb(80, 150, 250);
+        Self {
+            background: Color::rgb(32, 32, 32),
+            surface: Color::rgb(45, 45, 45),
+            accent,
+            primary: accent,
+            text: Color::rgb(235, 235, 235),
+            disabled: Color::rgb(110, 110, 110),
+            focus_ring: Some(Color::rgb(90, 160, 255)),
+        }
+    }
+
+    /// Highlight behind selected text, the accent made translucent so the
+    /// text stays readable over it
+    pub fn selection(&self) -> Color {
+        self.accent.with_alpha(96)
+    }
+
+    /// The theme in another accent color, which `primary` follows where it
+    /// was the old accent and a drawn focus ring takes
+    pub fn with_accent(&self, accent: Color) -> Self {
+        Self {
+            accent,
+            primary: if self.primary == self.accent {
+                accent
+            } else {
+                self.primary
+            },
+            focus_ring: self.focus_ring.map(|_| accent),
+            ..self.clone()
+        }
+    }
+}
+
+/// The measures of an application's look, shared by the light and dark
+/// themes and read through `Context::tokens`
+///
+/// Stylesheets refer to them by name, `space-1` to `space-6`, `radius-sm`,
+/// `radius-md`, `radius-lg` and `font-1` to `font-6`, so changing this one
+/// table, or only its density, restyles the whole application.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tokens {
+    /// Gaps and padding, tightest first, at `Density::Comfortable`
+    pub spacing: [f32; 6],
+    pub radius: Radii,
+    /// Text sizes, smallest first
+    pub font_sizes: [f32; 6],
+    pub density: Density,
+}
+
+impl Default for Tokens {
+    fn default() -> Self {
+        Self {
+            spacing: [2.0, 4.0, 8.0, 12.0, 16.0, 24.0],
+            radius: Radii::default(),
+            font_sizes: [12.0, 14.0, 16.0, 20.0, 24.0, 32.0],
+            density: Density::default(),
+        }
+    }
+}
+
+impl Tokens {
+    /// `space-{step}` at the density in effect, counting from 1; steps past
+    /// the scale are its loosest
+    pub fn space(&self, step: usize) -> f32 {
+        step_of(&self.spacing, step) * self.density.scale()
+    }
+
+    /// `font-{step}`, counting from 1; steps past the scale are its largest
+    pub fn font_size(&self, step: usize) -> f32 {
+        step_of(&self.font_sizes, step)
+    }
+
+    pub(crate) fn get(&self, token: Token) -> f32 {
+        match token {
+            Token::Space(step) => self.space(step),
+            Token::RadiusSmall => self.radius.small,
+            Token::RadiusMedium => self.radius.medium,
+            Token::RadiusLarge => self.radius.large,
+            Token::FontSize(step) => self.font_size(step),
+        }
+    }
+}
+
+fn step_of(scale: &[f32], step: usize) -> f32 {
+    scale[step.clamp(1, scale.len()) - 1]
+}
+
+/// How tightly widgets are packed, scaling the spacing tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Density {
+    /// Three quarters of the spacing, for dense tools and small screens
+    Compact,
+    #[default]
+    Comfortable,
+}
+
+impl Density {
+    fn scale(self) -> f32 {
+        match self {
+            Density::Compact => 0.75,
+            Density::Comfortable => 1.0,
+        }
+    }
+}
+
+/// A measure named in a stylesheet, looked up when a widget is styled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token {
+    Space(usize),
+    RadiusSmall,
+    RadiusMedium,
+    RadiusLarge,
+    FontSize(usize),
+}
+
+impl Token {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        let step = |n: &str| n.parse().ok().filter(|&n| n >= 1);
+        match name {
+            "radius-sm" => Some(Token::RadiusSmall),
+            "radius-md" => Some(Token::RadiusMedium),
+            "radius-lg" => Some(Token::RadiusLarge),
+            _ => match name.split_once('-')? {
+                ("space", n) => Some(Token::Space(step(n)?)),
+                ("font", n) => Some(Token::FontSize(step(n)?)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Corner radii, from checkboxes and tags to dialogs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Radii {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
+}
+
+impl Default for Radii {
+    fn default() -> Self {
+        Self {
+            small: 3.0,
+            medium: 6.0,
+            large: 12.0,
+        }
+    }
+}
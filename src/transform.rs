@@ -0,0 +1,127 @@
+use crate::geometry::{Point, Rect};
+
+/// A 2D affine transform mapping `(x, y)` to `(a*x + c*y + e, b*x + d*y + f)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    pub const fn translate(dx: f32, dy: f32) -> Self {
+        Transform {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
+            e: dx,
+            f: dy,
+        }
+    }
+
+    pub const fn scale(sx: f32, sy: f32) -> Self {
+        Transform {
+            a: sx,
+            b: 0.0,
+            c: 0.0,
+            d: sy,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// Clockwise rotation (y points down) by `angle` radians about the origin
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Transform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// Rotation by `angle` radians about `center`
+    pub fn rotate_about(angle: f32, center: Point) -> Self {
+        Transform::translate(-center.x, -center.y)
+            .then(Transform::rotate(angle))
+            .then(Transform::translate(center.x, center.y))
+    }
+
+    /// Apply `self` first, then `other`
+    pub fn then(self, o: Transform) -> Transform {
+        Transform {
+            a: o.a * self.a + o.c * self.b,
+            b: o.b * self.a + o.d * self.b,
+            c: o.a * self.c + o.c * self.d,
+            d: o.b * self.c + o.d * self.d,
+            e: o.a * self.e + o.c * self.f + o.e,
+            f: o.b * self.e + o.d * self.f + o.f,
+        }
+    }
+
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Transform {
+            a,
+            b,
+            c,
+            d,
+            e: -(a * self.e + c * self.f),
+            f: -(b * self.e + d * self.f),
+        })
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        Point::new(
+            self.a * p.x + self.c * p.y + self.e,
+            self.b * p.x + self.d * p.y + self.f,
+        )
+    }
+
+    /// True when the transform only moves content without scaling or rotating it
+    pub fn is_translation(&self) -> bool {
+        self.a == 1.0 && self.b == 0.0 && self.c == 0.0 && self.d == 1.0
+    }
+
+    /// Axis-aligned bounding box of a transformed rectangle
+    pub fn map_rect(&self, rect: Rect) -> Rect {
+        let corners = [
+            self.apply(Point::new(rect.min_x(), rect.min_y())),
+            self.apply(Point::new(rect.max_x(), rect.min_y())),
+            self.apply(Point::new(rect.max_x(), rect.max_y())),
+            self.apply(Point::new(rect.min_x(), rect.max_y())),
+        ];
+        let (mut min, mut max) = (corners[0], corners[0]);
+        for p in &corners[1..] {
+            min = Point::new(min.x.min(p.x), min.y.min(p.y));
+            max = Point::new(max.x.max(p.x), max.y.max(p.y));
+        }
+        Rect::from_points(min, max)
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::style::{Property, Style};
+use crate::widget::WidgetId;
+
+/// How a transition speeds up and slows down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    /// Fast at first, settling gently, which suits most state changes
+    #[default]
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// The easing of CSS name `name`
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "linear" => Easing::Linear,
+            "ease-in" => Easing::EaseIn,
+            "ease-out" => Easing::EaseOut,
+            "ease-in-out" => Easing::EaseInOut,
+            _ => return None,
+        })
+    }
+
+    /// How far along the change is at `t` of its duration, both `0.0..=1.0`
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+/// How changes of a style property animate, see `Style::transition`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transition {
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Transition {
+    fn progress(&self, elapsed: Duration) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        self.easing
+            .apply(elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+}
+
+/// A property on its way from one value to another
+#[derive(Debug, Clone)]
+struct Tween {
+    property: Property,
+    /// Style shown when the change began, possibly itself mid-transition
+    from: Style,
+    start: Instant,
+    transition: Transition,
+}
+
+#[derive(Debug, Clone)]
+struct Animated {
+    /// Style the widget was last given, which the shown one moves towards
+    target: Style,
+    shown: Style,
+    tweens: Vec<Tween>,
+    /// Styled during the frame being drawn
+    seen: bool,
+}
+
+/// Styles of the widgets whose properties have transitions
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transitions {
+    widgets: HashMap<WidgetId, Animated>,
+}
+
+impl Transitions {
+    /// The style to draw `id` with at `now`, given the one it resolved to,
+    /// starting transitions for properties that changed since it was last
+    /// styled
+    ///
+    /// A widget styled for the first time shows its style at once.
+    pub(crate) fn animate(&mut self, id: WidgetId, target: Style, now: Instant) -> Style {
+        let Some(animated) = self.widgets.get_mut(&id) else {
+            if !target.transitions.is_empty() {
+                let animated = Animated {
+                    target: target.clone(),
+                    shown: target.clone(),
+                    tweens: Vec::new(),
+                    seen: true,
+                };
+                self.widgets.insert(id, animated);
+            }
+            return target;
+        };
+        for &(property, transition) in &target.transitions {
+            if property.differs(&animated.target, &target) {
+                animated.tweens.retain(|tween| tween.property != property);
+                animated.tweens.push(Tween {
+                    property,
+                    from: animated.shown.clone(),
+                    start: now,
+                    transition,
+                });
+            }
+        }
+        // Properties whose transition was taken away jump to their value
+        (animated.tweens).retain(|tween| target.transition_of(tween.property).is_some());
+        let mut shown = target.clone();
+        animated.tweens.retain(|tween| {
+            let t = tween
+                .transition
+                .progress(now.saturating_duration_since(tween.start));
+            tween.property.blend(&mut shown, &tween.from, &target, t);
+            t < 1.0
+        });
+        if target.transitions.is_empty() && animated.tweens.is_empty() {
+            self.widgets.remove(&id);
+            return shown;
+        }
+        animated.target = target;
+        animated.shown = shown.clone();
+        animated.seen = true;
+        shown
+    }
+
+    /// Widgets mid-transition, which must be drawn again next frame
+    pub(crate) fn animating(&self) -> impl Iterator<Item = WidgetId> + '_ {
+        (self.widgets.iter())
+            .filter(|(_, animated)| !animated.tweens.is_empty())
+            .map(|(&id, _)| id)
+    }
+
+    pub(crate) fn is_animating(&self) -> bool {
+        self.animating().next().is_some()
+    }
+
+    /// Forget widgets that were not styled this frame, gone from the window
+    pub(crate) fn end_frame(&mut self) {
+        self.widgets
+            .retain(|_, animated| std::mem::take(&mut animated.seen));
+    }
+}
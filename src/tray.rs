@@ -0,0 +1,300 @@
+use std::any::Any;
+use std::io;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+
+use tray_icon::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use winit::event_loop::EventLoopProxy;
+
+use crate::bitmap::ImageSource;
+use crate::error::Error;
+use crate::pointer::MouseButton;
+
+/// Prefix of the ids of tray menu items, followed by their index
+const ITEM_ID: &str = "wixe-tray-";
+
+/// An entry of the tray icon's context menu
+#[derive(Debug, Clone)]
+pub enum TrayMenuItem {
+    /// Delivers `action` through `Application::tray_event` when chosen
+    Item {
+        label: String,
+        action: Rc<dyn Any>,
+    },
+    Separator,
+}
+
+impl TrayMenuItem {
+    pub fn item(label: impl Into<String>, action: impl Any) -> Self {
+        TrayMenuItem::Item {
+            label: label.into(),
+            action: Rc::new(action),
+        }
+    }
+}
+
+/// What the tray icon shows, passed to `Tray::show`
+#[derive(Debug, Clone, Default)]
+pub struct TrayOptions {
+    pub icon: Option<ImageSource>,
+    /// Text shown while the pointer rests on the icon
+    pub tooltip: String,
+    /// Context menu, opened with a right click
+    pub menu: Vec<TrayMenuItem>,
+}
+
+/// The tray icon was clicked or an item of its menu chosen, delivered through
+/// `Application::tray_event`
+#[derive(Debug, Clone)]
+pub enum TrayEvent {
+    /// The icon was clicked; Linux does not report clicks, only the menu
+    Click(MouseButton),
+    Menu(Rc<dyn Any>),
+}
+
+impl TrayEvent {
+    /// The action of the menu item chosen, if it is a `T`
+    pub fn action<T: Any>(&self) -> Option<&T> {
+        match self {
+            TrayEvent::Menu(action) => action.downcast_ref(),
+            TrayEvent::Click(_) => None,
+        }
+    }
+}
+
+/// Input from the tray, sent from the OS's thread
+enum Received {
+    Click(tray_icon::MouseButton),
+    /// Index of the menu item chosen
+    Menu(usize),
+}
+
+/// The application's icon in the system tray or menu bar, e.g.
+/// `ctx.tray_mut().show(options)` before `ctx.set_visible(false)` to hide the
+/// window to the tray
+///
+/// Clicks and menu items chosen are delivered through
+/// `Application::tray_event`. On Linux the icon lives on a GTK thread of its
+/// own and needs GTK and libappindicator installed.
+#[derive(Default)]
+pub struct Tray {
+    #[cfg(not(target_os = "linux"))]
+    icon: Option<TrayIcon>,
+    /// The GTK thread, started with the first icon shown
+    #[cfg(target_os = "linux")]
+    thread: Option<mpsc::Sender<Command>>,
+    #[cfg(target_os = "linux")]
+    shown: bool,
+    /// Actions of the menu items, by index
+    actions: Vec<Option<Rc<dyn Any>>>,
+    received: Option<Receiver<Received>>,
+}
+
+impl Tray {
+    /// Receive clicks and menu items, waking the event loop through `proxy`
+    pub(crate) fn listen(&mut self, proxy: EventLoopProxy<()>) {
+        let (sender, receiver) = mpsc::channel();
+        let proxy = Mutex::new(proxy);
+        let send = move |received| {
+            if sender.send(received).is_ok() {
+                // Wake the event loop up to deliver it
+                let _ = proxy.lock().map(|proxy| proxy.send_event(()));
+            }
+        };
+        let send = Arc::new(send);
+        let click = send.clone();
+        TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+            if let TrayIconEvent::Click {
+                button,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                click(Received::Click(button));
+            }
+        }));
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            let index = event.id.0.strip_prefix(ITEM_ID);
+            if let Some(index) = index.and_then(|i| i.parse().ok()) {
+                send(Received::Menu(index));
+            }
+        }));
+        self.received = Some(receiver);
+    }
+
+    /// Show the tray icon, replacing one shown before
+    ///
+    /// Call it from a hook, once the event loop runs. Fails if the image
+    /// cannot be loaded or the OS has no tray.
+    pub fn show(&mut self, options: TrayOptions) -> Result<(), Error> {
+        let icon = match &options.icon {
+            Some(source) => {
+                let bitmap = source.load()?;
+                let rgba = bitmap.to_rgba_image().into_raw();
+                Some((rgba, bitmap.width(), bitmap.height()))
+            }
+            None => None,
+        };
+        let spec = Spec {
+            icon,
+            tooltip: options.tooltip,
+            menu: (options.menu.iter())
+                .map(|item| match item {
+                    TrayMenuItem::Item { label, .. } => Some(label.clone()),
+                    TrayMenuItem::Separator => None,
+                })
+                .collect(),
+        };
+        self.actions = (options.menu.into_iter())
+            .map(|item| match item {
+                TrayMenuItem::Item { action, .. } => Some(action),
+                TrayMenuItem::Separator => None,
+            })
+            .collect();
+        self.set(Some(spec))
+    }
+
+    /// Take the icon out of the tray
+    pub fn remove(&mut self) {
+        let _ = self.set(None);
+        self.actions.clear();
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn is_shown(&self) -> bool {
+        self.icon.is_some()
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set(&mut self, spec: Option<Spec>) -> Result<(), Error> {
+        // The old icon goes first, so the new one takes its place
+        self.icon = None;
+        self.icon = spec.map(Spec::build).transpose()?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set(&mut self, spec: Option<Spec>) -> Result<(), Error> {
+        if spec.is_none() && self.thread.is_none() {
+            return Ok(());
+        }
+        let thread = self.thread.get_or_insert_with(spawn);
+        let (reply, result) = mpsc::channel();
+        let shown = spec.is_some();
+        self.shown = false;
+        // A thread that could not start GTK drops the reply
+        let stopped = || Error::Tray(io::Error::other("GTK is unavailable").into());
+        (thread.send((spec, reply)).ok()).ok_or_else(stopped)?;
+        result.recv().map_err(|_| stopped())??;
+        self.shown = shown;
+        Ok(())
+    }
+
+    /// Clicks and menu items chosen since the last poll
+    pub(crate) fn poll(&mut self) -> Vec<TrayEvent> {
+        let Some(received) = &self.received else {
+            return Vec::new();
+        };
+        let received: Vec<Received> = received.try_iter().collect();
+        received
+            .into_iter()
+            .filter_map(|received| match received {
+                Received::Click(button) => Some(TrayEvent::Click(mouse_button(button))),
+                Received::Menu(index) => {
+                    let action = self.actions.get(index)?.clone()?;
+                    Some(TrayEvent::Menu(action))
+                }
+            })
+            .collect()
+    }
+}
+
+fn mouse_button(button: tray_icon::MouseButton) -> MouseButton {
+    match button {
+        tray_icon::MouseButton::Left => MouseButton::Left,
+        tray_icon::MouseButton::Right => MouseButton::Right,
+        tray_icon::MouseButton::Middle => MouseButton::Middle,
+    }
+}
+
+/// A tray icon described without the handles of any thread, so it can be
+/// built on the one that owns the tray
+struct Spec {
+    /// RGBA pixels, width and height
+    icon: Option<(Vec<u8>, u32, u32)>,
+    tooltip: String,
+    /// Labels of the menu items, `None` for separators
+    menu: Vec<Option<String>>,
+}
+
+impl Spec {
+    fn build(self) -> Result<TrayIcon, tray_icon::Error> {
+        let items: Vec<Box<dyn IsMenuItem>> = (self.menu.into_iter().enumerate())
+            .map(|(i, label)| -> Box<dyn IsMenuItem> {
+                match label {
+                    Some(label) => Box::new(MenuItem::with_id(
+                        format!("{ITEM_ID}{i}"),
+                        label,
+                        true,
+                        None,
+                    )),
+                    None => Box::new(PredefinedMenuItem::separator()),
+                }
+            })
+            .collect();
+        let items: Vec<&dyn IsMenuItem> = items.iter().map(|item| item.as_ref()).collect();
+        // Fails only for items already in another menu, which fresh ones are not
+        let menu = Menu::with_items(&items).map_err(invalid)?;
+        let mut builder = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip(self.tooltip);
+        if let Some((rgba, width, height)) = self.icon {
+            builder = builder.with_icon(Icon::from_rgba(rgba, width, height).map_err(invalid)?);
+        }
+        builder.build()
+    }
+}
+
+fn invalid(e: impl std::error::Error + Send + Sync + 'static) -> tray_icon::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e).into()
+}
+
+/// Show the icon described, or none, replying with the outcome
+#[cfg(target_os = "linux")]
+type Command = (Option<Spec>, mpsc::Sender<Result<(), tray_icon::Error>>);
+
+/// Start the GTK thread the tray icon lives on, which GTK wants running its
+/// own loop
+#[cfg(target_os = "linux")]
+fn spawn() -> mpsc::Sender<Command> {
+    use std::time::Duration;
+
+    use gtk::glib;
+
+    let (sender, commands) = mpsc::channel::<Command>();
+    std::thread::spawn(move || {
+        if gtk::init().is_err() {
+            return;
+        }
+        // Kept alive here, where GTK runs
+        let mut icon: Option<TrayIcon> = None;
+        glib::timeout_add_local(Duration::from_millis(50), move || {
+            for (spec, reply) in commands.try_iter() {
+                // The old icon goes first, so the new one takes its place
+                drop(icon.take());
+                let result = spec.map(Spec::build).transpose();
+                let _ = reply.send(result.map(|built| icon = built));
+            }
+            glib::ControlFlow::Continue
+        });
+        gtk::main();
+    });
+    sender
+}
@@ -0,0 +1,158 @@
+use crate::draw_list::LayerRecorder;
+use crate::geometry::{Point, Rect};
+use crate::painter::Painter;
+use crate::transform::Transform;
+
+/// Zoom factor applied per line of mouse wheel scrolling
+const WHEEL_ZOOM_STEP: f32 = 1.1;
+
+/// A pan and zoom camera showing part of an unbounded world in a screen area
+///
+/// Canvas-like content is drawn in world coordinates under `transform()`;
+/// pointer positions are mapped back with `to_world` for hit-testing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Screen area the world is shown in
+    pub bounds: Rect,
+    /// World position shown at the top-left corner of `bounds`
+    pub offset: Point,
+    /// Screen pixels per world unit
+    pub zoom: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// Last pointer position of a drag in progress, in screen coordinates
+    drag: Option<Point>,
+}
+
+impl Viewport {
+    /// Unzoomed view with the world origin at the top-left of `bounds`
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            offset: Point::ZERO,
+            zoom: 1.0,
+            min_zoom: 0.01,
+            max_zoom: 100.0,
+            drag: None,
+        }
+    }
+
+    pub fn zoom_limits(mut self, min: f32, max: f32) -> Self {
+        self.min_zoom = min;
+        self.max_zoom = max.max(min);
+        self.zoom = self.zoom.clamp(self.min_zoom, self.max_zoom);
+        self
+    }
+
+    /// Mapping from world coordinates to screen pixels
+    pub fn transform(&self) -> Transform {
+        Transform::translate(-self.offset.x, -self.offset.y)
+            .then(Transform::scale(self.zoom, self.zoom))
+            .then(Transform::translate(self.bounds.x, self.bounds.y))
+    }
+
+    pub fn to_screen(&self, world: Point) -> Point {
+        Point::new(
+            (world.x - self.offset.x) * self.zoom + self.bounds.x,
+            (world.y - self.offset.y) * self.zoom + self.bounds.y,
+        )
+    }
+
+    pub fn to_world(&self, screen: Point) -> Point {
+        Point::new(
+            (screen.x - self.bounds.x) / self.zoom + self.offset.x,
+            (screen.y - self.bounds.y) / self.zoom + self.offset.y,
+        )
+    }
+
+    /// World area currently in view
+    pub fn visible_rect(&self) -> Rect {
+        Rect::new(
+            self.offset.x,
+            self.offset.y,
+            self.bounds.width / self.zoom,
+            self.bounds.height / self.zoom,
+        )
+    }
+
+    /// Move the view so content follows a pointer moved by `delta` screen pixels
+    pub fn pan(&mut self, delta: Point) {
+        self.offset.x -= delta.x / self.zoom;
+        self.offset.y -= delta.y / self.zoom;
+    }
+
+    /// Multiply the zoom by `factor`, keeping the world point under `anchor` in place
+    pub fn zoom_at(&mut self, anchor: Point, factor: f32) {
+        let world = self.to_world(anchor);
+        self.zoom = (self.zoom * factor).clamp(self.min_zoom, self.max_zoom);
+        self.offset = Point::new(
+            world.x - (anchor.x - self.bounds.x) / self.zoom,
+            world.y - (anchor.y - self.bounds.y) / self.zoom,
+        );
+    }
+
+    /// Zoom about the cursor for a wheel scrolled by `lines`, positive zooming in
+    pub fn scroll(&mut self, cursor: Point, lines: f32) {
+        self.zoom_at(cursor, WHEEL_ZOOM_STEP.powf(lines));
+    }
+
+    /// Start panning with the pointer at `p`, if it is inside the view
+    pub fn begin_drag(&mut self, p: Point) -> bool {
+        if self.bounds.contains(p) {
+            self.drag = Some(p);
+        }
+        self.drag.is_some()
+    }
+
+    /// Pan by the pointer's movement since the last call, `false` if not dragging
+    pub fn drag_to(&mut self, p: Point) -> bool {
+        let Some(last) = self.drag else {
+            return false;
+        };
+        self.pan(Point::new(p.x - last.x, p.y - last.y));
+        self.drag = Some(p);
+        true
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Zoom and pan so all of `world` is visible and centered
+    pub fn fit(&mut self, world: Rect) {
+        if world.is_empty() {
+            return;
+        }
+        let zoom = (self.bounds.width / world.width).min(self.bounds.height / world.height);
+        self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+        let visible = self.visible_rect();
+        let center = world.center();
+        self.offset = Point::new(
+            center.x - visible.width / 2.0,
+            center.y - visible.height / 2.0,
+        );
+    }
+}
+
+impl Painter<'_> {
+    /// Run `f` clipped to the viewport with world coordinates mapped onto it
+    pub fn with_viewport(&mut self, viewport: &Viewport, f: impl FnOnce(&mut Self)) {
+        self.with_clip(viewport.bounds, |p| {
+            p.with_transform(viewport.transform(), f);
+        });
+    }
+}
+
+impl LayerRecorder<'_> {
+    /// Record `f` in world coordinates, clipped to the viewport
+    pub fn viewport(&mut self, viewport: &Viewport, f: impl FnOnce(&mut Self)) -> &mut Self {
+        self.push_clip(viewport.bounds);
+        self.push_transform(viewport.transform());
+        f(self);
+        self.pop_transform().pop_clip()
+    }
+}
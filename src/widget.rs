@@ -0,0 +1,280 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::cursor::Cursor;
+use crate::geometry::{Point, Rect};
+use crate::style::Style;
+
+/// Names a widget across frames, e.g. `WidgetId::new("save")`
+///
+/// Input is routed by id, so a widget must get the same one every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidgetId(pub(crate) u64);
+
+impl WidgetId {
+    pub fn new(key: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    /// Id of a part of this widget, e.g. `list.with(row)`
+    pub fn with(self, key: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        (self.0, key).hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    id: WidgetId,
+    rect: Rect,
+    parent: Option<usize>,
+    /// Set for widgets that take focus, see `Context::set_tab_index`
+    tab_index: Option<i32>,
+    /// Arrow keys move focus among the focusable widgets inside
+    focus_group: bool,
+    cursor: Option<Cursor>,
+    scrollable: bool,
+    /// Pressing it moves the window, see `Context::set_drag_region`
+    drag_region: bool,
+    /// See `Context::set_disabled`
+    disabled: bool,
+    /// Properties set for this widget alone, see `Context::set_style`
+    style: Style,
+    /// Its style once resolved, which the widgets inside inherit from
+    computed: Option<Style>,
+}
+
+/// Widgets of the last frame in drawing order, each inside its parent
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WidgetTree {
+    nodes: Vec<Node>,
+    /// Widgets begun and not yet ended, innermost last
+    open: Vec<usize>,
+}
+
+impl WidgetTree {
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+        self.open.clear();
+    }
+
+    pub(crate) fn begin(&mut self, id: WidgetId, rect: Rect) {
+        let parent = self.open.last().copied();
+        self.open.push(self.nodes.len());
+        self.nodes.push(Node {
+            id,
+            rect,
+            parent,
+            tab_index: None,
+            focus_group: false,
+            cursor: None,
+            scrollable: false,
+            drag_region: false,
+            disabled: false,
+            style: Style::default(),
+            computed: None,
+        });
+    }
+
+    pub(crate) fn end(&mut self) {
+        self.open.pop();
+    }
+
+    /// Widgets under `p`, outermost first
+    ///
+    /// The last one drawn wins where siblings overlap, and children are only
+    /// hit inside their parents' bounds.
+    pub(crate) fn path_at(&self, p: Point) -> Vec<WidgetId> {
+        for index in (0..self.nodes.len()).rev() {
+            let path = self.ancestry(index);
+            if path.iter().all(|n| n.rect.contains(p)) {
+                return path.iter().rev().map(|n| n.id).collect();
+            }
+        }
+        Vec::new()
+    }
+
+    /// Last widget laid out with `id`
+    fn find(&self, id: WidgetId) -> Option<usize> {
+        self.nodes.iter().rposition(|n| n.id == id)
+    }
+
+    pub(crate) fn rect(&self, id: WidgetId) -> Option<Rect> {
+        Some(self.nodes[self.find(id)?].rect)
+    }
+
+    pub(crate) fn set_tab_index(&mut self, id: WidgetId, tab_index: i32) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].tab_index = Some(tab_index);
+        }
+    }
+
+    pub(crate) fn set_focus_group(&mut self, id: WidgetId) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].focus_group = true;
+        }
+    }
+
+    pub(crate) fn set_cursor(&mut self, id: WidgetId, cursor: Cursor) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].cursor = Some(cursor);
+        }
+    }
+
+    /// Cursor of the innermost widget of `path` that has one
+    pub(crate) fn cursor_in(&self, path: &[WidgetId]) -> Option<&Cursor> {
+        (path.iter().rev()).find_map(|&id| self.nodes[self.find(id)?].cursor.as_ref())
+    }
+
+    pub(crate) fn set_scrollable(&mut self, id: WidgetId) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].scrollable = true;
+        }
+    }
+
+    /// Innermost widget of `path` that scrolls
+    pub(crate) fn scrollable_in(&self, path: &[WidgetId]) -> Option<WidgetId> {
+        let scrollable = |id: &&WidgetId| self.find(**id).is_some_and(|i| self.nodes[i].scrollable);
+        path.iter().rev().find(scrollable).copied()
+    }
+
+    pub(crate) fn set_drag_region(&mut self, id: WidgetId) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].drag_region = true;
+        }
+    }
+
+    pub(crate) fn is_drag_region(&self, id: WidgetId) -> bool {
+        self.find(id).is_some_and(|i| self.nodes[i].drag_region)
+    }
+
+    pub(crate) fn set_disabled(&mut self, id: WidgetId) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].disabled = true;
+        }
+    }
+
+    /// Whether `id` or a widget it is inside is disabled
+    pub(crate) fn is_disabled(&self, id: WidgetId) -> bool {
+        self.find(id).is_some_and(|i| self.disabled_at(i))
+    }
+
+    fn disabled_at(&self, index: usize) -> bool {
+        self.ancestry(index).iter().any(|n| n.disabled)
+    }
+
+    pub(crate) fn set_style(&mut self, id: WidgetId, style: impl FnOnce(Style) -> Style) {
+        if let Some(i) = self.find(id) {
+            let own = std::mem::take(&mut self.nodes[i].style);
+            self.nodes[i].style = style(own);
+        }
+    }
+
+    pub(crate) fn style_of(&self, id: WidgetId) -> Option<&Style> {
+        Some(&self.nodes[self.find(id)?].style)
+    }
+
+    /// Whether its style was taken this frame, so it changes with its state
+    pub(crate) fn is_styled(&self, id: WidgetId) -> bool {
+        self.find(id)
+            .is_some_and(|i| self.nodes[i].computed.is_some())
+    }
+
+    pub(crate) fn set_computed_style(&mut self, id: WidgetId, style: Style) {
+        if let Some(i) = self.find(id) {
+            self.nodes[i].computed = Some(style);
+        }
+    }
+
+    /// Resolved style of the innermost widget around `id` that has one, or
+    /// around the widget being laid out for one not laid out
+    pub(crate) fn parent_style(&self, id: WidgetId) -> Option<&Style> {
+        let parent = match self.find(id) {
+            Some(i) => self.nodes[i].parent,
+            None => self.open.last().copied(),
+        };
+        std::iter::successors(parent, |&i| self.nodes[i].parent)
+            .find_map(|i| self.nodes[i].computed.as_ref())
+    }
+
+    /// Innermost widget of `path` that takes focus
+    pub(crate) fn focusable_in(&self, path: &[WidgetId]) -> Option<WidgetId> {
+        let focusable = |id: &&WidgetId| {
+            self.find(**id)
+                .is_some_and(|i| self.nodes[i].tab_index.is_some() && !self.disabled_at(i))
+        };
+        path.iter().rev().find(focusable).copied()
+    }
+
+    /// Widget Tab moves focus to from `current`, or Shift+Tab when `backwards`
+    ///
+    /// Positive tab indices come first, lowest first, then index 0 in layout
+    /// order. Negative ones are skipped, as are disabled widgets.
+    pub(crate) fn next_focus(
+        &self,
+        current: Option<WidgetId>,
+        backwards: bool,
+    ) -> Option<WidgetId> {
+        let mut order: Vec<_> = (self.nodes.iter().enumerate())
+            .filter(|&(index, _)| !self.disabled_at(index))
+            .filter_map(|(_, n)| n.tab_index.filter(|&i| i >= 0).map(|i| (i, n.id)))
+            .collect();
+        order.sort_by_key(|&(i, _)| if i == 0 { i32::MAX } else { i });
+        let order: Vec<_> = order.into_iter().map(|(_, id)| id).collect();
+        step(&order, current, backwards)
+    }
+
+    /// Focusable widget an arrow key moves focus to from `current`, within the
+    /// innermost focus group around it
+    pub(crate) fn next_in_group(&self, current: WidgetId, backwards: bool) -> Option<WidgetId> {
+        let index = self.find(current)?;
+        let group = self.ancestors(index).find(|&i| self.nodes[i].focus_group)?;
+        let members: Vec<_> = (group + 1..self.nodes.len())
+            .filter(|&i| self.nodes[i].tab_index.is_some() && !self.disabled_at(i))
+            .filter(|&i| self.ancestors(i).any(|a| a == group))
+            .map(|i| self.nodes[i].id)
+            .collect();
+        step(&members, Some(current), backwards)
+    }
+
+    /// `id` and the widgets it is inside, innermost first
+    pub(crate) fn ancestry_of(&self, id: WidgetId) -> Vec<WidgetId> {
+        let Some(index) = self.find(id) else {
+            return Vec::new();
+        };
+        self.ancestry(index).iter().map(|n| n.id).collect()
+    }
+
+    /// Parents of `index`, innermost first
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.nodes[index].parent, |&i| self.nodes[i].parent)
+    }
+
+    /// `index` and its ancestors, innermost first
+    fn ancestry(&self, index: usize) -> Vec<&Node> {
+        let mut path = Vec::new();
+        let mut next = Some(index);
+        while let Some(i) = next {
+            path.push(&self.nodes[i]);
+            next = self.nodes[i].parent;
+        }
+        path
+    }
+}
+
+/// The widget after `current` in `order`, or before it when `backwards`,
+/// wrapping around; from no widget, the first or last
+fn step(order: &[WidgetId], current: Option<WidgetId>, backwards: bool) -> Option<WidgetId> {
+    let n = order.len();
+    let position = current.and_then(|id| order.iter().position(|&o| o == id));
+    let next = match (position, backwards) {
+        (Some(i), false) => (i + 1) % n,
+        (Some(i), true) => (i + n - 1) % n,
+        (None, false) => 0,
+        (None, true) => n.checked_sub(1)?,
+    };
+    order.get(next).copied()
+}
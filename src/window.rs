@@ -0,0 +1,385 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::monitor::MonitorHandle;
+use winit::window::{Fullscreen as WinitFullscreen, Window};
+
+use crate::cursor::CursorIcon;
+
+/// How the window fills the screen, set with `Context::set_fullscreen`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fullscreen {
+    /// A window without borders covering the monitor it is on, which switches
+    /// quickly and leaves other windows a click away
+    Borderless,
+    /// The monitor to the window alone, at its highest resolution and refresh
+    /// rate
+    Exclusive,
+}
+
+impl From<&WinitFullscreen> for Fullscreen {
+    fn from(fullscreen: &WinitFullscreen) -> Self {
+        match fullscreen {
+            WinitFullscreen::Borderless(_) => Self::Borderless,
+            WinitFullscreen::Exclusive(_) => Self::Exclusive,
+        }
+    }
+}
+
+/// Whether the window is minimized, maximized or fullscreen, from
+/// `Context::window_state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowState {
+    #[default]
+    Normal,
+    /// Where the system only reports the window hidden from view, as X11 and
+    /// macOS do, a window entirely covered by others counts as minimized too
+    Minimized,
+    Maximized,
+    Fullscreen,
+}
+
+impl WindowState {
+    pub(crate) fn of(window: &Window, occluded: bool) -> Self {
+        let size = window.inner_size();
+        if occluded || size.width == 0 || size.height == 0 {
+            WindowState::Minimized
+        } else if window.fullscreen().is_some() {
+            WindowState::Fullscreen
+        } else if window.is_maximized() {
+            WindowState::Maximized
+        } else {
+            WindowState::Normal
+        }
+    }
+}
+
+/// A display of the computer, from `Context::monitors`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    pub name: Option<String>,
+    /// Top-left corner on the desktop spanning all monitors, in physical
+    /// pixels
+    pub position: (i32, i32),
+    /// Resolution in physical pixels
+    pub size: (u32, u32),
+    /// Physical pixels per logical pixel
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+impl Monitor {
+    pub(crate) fn new(monitor: &MonitorHandle, primary: Option<&MonitorHandle>) -> Self {
+        let (position, size) = (monitor.position(), monitor.size());
+        Self {
+            name: monitor.name(),
+            position: (position.x, position.y),
+            size: (size.width, size.height),
+            scale_factor: monitor.scale_factor(),
+            primary: primary == Some(monitor),
+        }
+    }
+}
+
+/// Top-left corner of a window of `size` centered on `monitor`
+pub(crate) fn centered_on(
+    monitor: &MonitorHandle,
+    size: PhysicalSize<u32>,
+) -> PhysicalPosition<i32> {
+    let (origin, area) = (monitor.position(), monitor.size());
+    PhysicalPosition::new(
+        origin.x + (area.width as i32 - size.width as i32) / 2,
+        origin.y + (area.height as i32 - size.height as i32) / 2,
+    )
+}
+
+/// A change to the window requested by the application, applied after each
+/// batch of events
+#[derive(Debug, Clone)]
+pub(crate) enum WindowRequest {
+    Title(String),
+    Fullscreen(Option<Fullscreen>),
+    Decorations(bool),
+    AlwaysOnTop(bool),
+    /// Move the window with the pointer until the button is released
+    Drag,
+    Minimize,
+    Maximize(bool),
+    /// Neither minimized nor maximized
+    Restore,
+    Visible(bool),
+    /// Center the window on the monitor at this index of `Context::monitors`
+    MoveToMonitor(usize),
+}
+
+impl WindowRequest {
+    pub(crate) fn apply(self, window: &Window) {
+        match self {
+            WindowRequest::Title(title) => window.set_title(&title),
+            WindowRequest::Fullscreen(fullscreen) => {
+                window.set_fullscreen(fullscreen.and_then(|f| winit_fullscreen(window, f)));
+            }
+            WindowRequest::Decorations(decorations) => window.set_decorations(decorations),
+            WindowRequest::AlwaysOnTop(always_on_top) => window.set_always_on_top(always_on_top),
+            // Fails only when no button is held, when there is nothing to drag
+            WindowRequest::Drag => {
+                let _ = window.drag_window();
+            }
+            WindowRequest::Minimize => window.set_minimized(true),
+            WindowRequest::Maximize(maximized) => window.set_maximized(maximized),
+            WindowRequest::Restore => {
+                window.set_minimized(false);
+                window.set_maximized(false);
+            }
+            WindowRequest::MoveToMonitor(index) => {
+                if let Some(monitor) = window.available_monitors().nth(index) {
+                    window.set_outer_position(centered_on(&monitor, window.outer_size()));
+                }
+            }
+            WindowRequest::Visible(visible) => {
+                window.set_visible(visible);
+                if visible {
+                    window.focus_window();
+                }
+            }
+        }
+    }
+}
+
+fn winit_fullscreen(window: &Window, fullscreen: Fullscreen) -> Option<WinitFullscreen> {
+    match fullscreen {
+        Fullscreen::Borderless => Some(WinitFullscreen::Borderless(None)),
+        Fullscreen::Exclusive => {
+            let modes = window.current_monitor()?.video_modes();
+            let best = modes.max_by_key(|mode| {
+                let size = mode.size();
+                (size.width * size.height, mode.refresh_rate_millihertz())
+            });
+            Some(WinitFullscreen::Exclusive(best?))
+        }
+    }
+}
+
+/// Edges of a window without decorations that the pointer is near enough to
+/// resize the window from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Edges {
+    left: bool,
+    right: bool,
+    top: bool,
+    bottom: bool,
+}
+
+impl Edges {
+    /// Edges within `border` logical pixels of `p`, a position in the window,
+    /// if the window can be resized from them
+    pub(crate) fn at(window: &Window, p: PhysicalPosition<f64>, border: f32) -> Option<Self> {
+        let resizable = window.is_resizable() && !window.is_maximized();
+        if border <= 0.0 || !resizable || window.is_decorated() || window.fullscreen().is_some() {
+            return None;
+        }
+        let border = border as f64 * window.scale_factor();
+        let size = window.inner_size();
+        let edges = Self {
+            left: p.x < border,
+            right: p.x >= size.width as f64 - border,
+            top: p.y < border,
+            bottom: p.y >= size.height as f64 - border,
+        };
+        (edges.left || edges.right || edges.top || edges.bottom).then_some(edges)
+    }
+
+    pub(crate) fn cursor(self) -> CursorIcon {
+        match (self.left || self.right, self.top || self.bottom) {
+            (true, false) => CursorIcon::ResizeHorizontal,
+            (false, true) => CursorIcon::ResizeVertical,
+            _ if self.left == self.top => CursorIcon::ResizeNwSe,
+            _ => CursorIcon::ResizeNeSw,
+        }
+    }
+}
+
+/// The window being resized by dragging its edges, as the system does for
+/// windows with decorations
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EdgeResize {
+    pub(crate) edges: Edges,
+    /// Pointer position on the screen when the drag began
+    start: PhysicalPosition<f64>,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+}
+
+impl EdgeResize {
+    /// Start resizing from `edges` with the pointer at `p` in the window
+    pub(crate) fn start(window: &Window, edges: Edges, p: PhysicalPosition<f64>) -> Option<Self> {
+        let position = window.inner_position().ok()?;
+        Some(Self {
+            edges,
+            start: screen_position(position, p),
+            position,
+            size: window.inner_size(),
+        })
+    }
+
+    /// Follow the pointer to `p` in the window, keeping the size within
+    /// `clamp`, which takes and gives logical pixels
+    pub(crate) fn moved(
+        &self,
+        window: &Window,
+        p: PhysicalPosition<f64>,
+        clamp: impl Fn(u32, u32) -> (u32, u32),
+    ) {
+        let Ok(position) = window.inner_position() else {
+            return;
+        };
+        let p = screen_position(position, p);
+        let (dx, dy) = ((p.x - self.start.x) as i64, (p.y - self.start.y) as i64);
+        let (w, h) = (self.size.width as i64, self.size.height as i64);
+        let e = self.edges;
+        let width = w + if e.left {
+            -dx
+        } else if e.right {
+            dx
+        } else {
+            0
+        };
+        let height = h + if e.top {
+            -dy
+        } else if e.bottom {
+            dy
+        } else {
+            0
+        };
+
+        let scale = window.scale_factor();
+        let logical =
+            PhysicalSize::new(width.max(1) as u32, height.max(1) as u32).to_logical::<u32>(scale);
+        let (width, height) = clamp(logical.width.max(1), logical.height.max(1));
+        let size = LogicalSize::new(width, height).to_physical::<u32>(scale);
+        window.set_inner_size(size);
+        // Dragging the left or top edge keeps the opposite one in place
+        if e.left || e.top {
+            let mut position = self.position;
+            if e.left {
+                position.x += w as i32 - size.width as i32;
+            }
+            if e.top {
+                position.y += h as i32 - size.height as i32;
+            }
+            window.set_outer_position(position);
+        }
+    }
+}
+
+fn screen_position(
+    window: PhysicalPosition<i32>,
+    p: PhysicalPosition<f64>,
+) -> PhysicalPosition<f64> {
+    PhysicalPosition::new(window.x as f64 + p.x, window.y as f64 + p.y)
+}
+
+/// First line of a saved window geometry, naming its format
+const GEOMETRY_HEADER: &str = "wixe-window 1";
+
+/// Where the window was and how large, kept across launches with
+/// `WindowOptions::remember_geometry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Geometry {
+    /// Top-left corner of the window's frame on the screen
+    pub(crate) position: PhysicalPosition<i32>,
+    /// Size of the client area in logical pixels
+    pub(crate) size: (u32, u32),
+    pub(crate) maximized: bool,
+}
+
+impl Geometry {
+    /// The window's geometry as it is now, `None` while it is maximized,
+    /// minimized or fullscreen, which are no size to restore it to
+    pub(crate) fn of(window: &Window) -> Option<Self> {
+        if window.is_maximized() || window.fullscreen().is_some() {
+            return None;
+        }
+        let position = window.outer_position().ok()?;
+        let size = window.inner_size().to_logical::<u32>(window.scale_factor());
+        (size.width > 0 && size.height > 0).then_some(Self {
+            position,
+            size: (size.width, size.height),
+            maximized: false,
+        })
+    }
+
+    /// Geometry saved at `path`, `None` if there is none or it is unreadable
+    pub(crate) fn load(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let mut lines = text.lines();
+        if lines.next()? != GEOMETRY_HEADER {
+            return None;
+        }
+        let mut fields = lines.next()?.split_whitespace();
+        let mut field = || fields.next()?.parse::<i32>().ok();
+        let (x, y) = (field()?, field()?);
+        let (width, height) = (field()?, field()?);
+        let maximized = field()? != 0;
+        Some(Self {
+            position: PhysicalPosition::new(x, y),
+            size: (u32::try_from(width).ok()?, u32::try_from(height).ok()?),
+            maximized,
+        })
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let Self {
+            position: p,
+            size: (width, height),
+            maximized,
+        } = *self;
+        let maximized = maximized as u8;
+        let text = format!(
+            "{GEOMETRY_HEADER}\n{} {} {width} {height} {maximized}\n",
+            p.x, p.y
+        );
+        fs::write(path, text)
+    }
+
+    /// Whether enough of the window's top edge lands on one of `monitors` to
+    /// grab it there, which a monitor unplugged since may no longer allow
+    pub(crate) fn is_reachable(&self, mut monitors: impl Iterator<Item = MonitorHandle>) -> bool {
+        const GRAB: (i32, i32) = (64, 16);
+        let (x, y) = (self.position.x, self.position.y);
+        let right = x.saturating_add(self.size.0.min(i32::MAX as u32) as i32);
+        monitors.any(|monitor| {
+            let (origin, size) = (monitor.position(), monitor.size());
+            let overlap_x = right.min(origin.x + size.width as i32) - x.max(origin.x);
+            let overlap_y = (y + GRAB.1).min(origin.y + size.height as i32) - y.max(origin.y);
+            overlap_x >= GRAB.0 && overlap_y >= GRAB.1
+        })
+    }
+}
+
+/// File the geometry of the application `name` is saved in
+pub(crate) fn geometry_path(name: &str) -> Option<PathBuf> {
+    Some(config_dir()?.join(name).join("window"))
+}
+
+#[cfg(windows)]
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn config_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Application Support"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn config_dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty());
+    (config.map(PathBuf::from))
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))
+}